@@ -7,12 +7,12 @@ use anyhow::bail;
 use darling::FromMeta;
 use evtx::SerializedEvtxRecord;
 use quote::quote;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use super::EvtxFieldView;
 
-#[derive(PartialEq, Eq, Clone, Debug, Serialize)]
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub struct EventId(pub u16);
 
 impl TryFrom<&SerializedEvtxRecord<Value>> for EventId {
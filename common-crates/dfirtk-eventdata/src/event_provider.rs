@@ -3,15 +3,21 @@ use std::{fmt::Display, convert::TryFrom};
 use darling::FromMeta;
 use evtx::SerializedEvtxRecord;
 use quote::quote;
+use serde::{de, Deserialize, Deserializer};
 use serde_json::Value;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum EventProvider {
     TerminalServicesRemoteConnectionManager,
     TerminalServicesLocalSessionManager,
     RemoteDesktopServicesRdpCoreTS,
     SecurityAuditing,
     DesktopWindowManager,
+    ServiceControlManager,
+    Eventlog,
+    PowerShell,
+    WindowsPowerShell,
+    TaskScheduler,
     UnsupportedProvider,
 }
 
@@ -19,7 +25,7 @@ impl Display for EventProvider {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             EventProvider::TerminalServicesRemoteConnectionManager => {
-                "Microsoft-Windows-Terminal-Services-RemoteConnectionManager"
+                "Microsoft-Windows-TerminalServices-RemoteConnectionManager"
             }
             EventProvider::TerminalServicesLocalSessionManager => {
                 "Microsoft-Windows-TerminalServices-LocalSessionManager"
@@ -29,28 +35,23 @@ impl Display for EventProvider {
             }
             EventProvider::SecurityAuditing => "Microsoft-Windows-Security-Auditing",
             EventProvider::DesktopWindowManager => "Desktop Window Manager",
+            EventProvider::ServiceControlManager => "Service Control Manager",
+            EventProvider::Eventlog => "Microsoft-Windows-Eventlog",
+            EventProvider::PowerShell => "Microsoft-Windows-PowerShell",
+            EventProvider::WindowsPowerShell => "PowerShell",
+            EventProvider::TaskScheduler => "Microsoft-Windows-TaskScheduler",
             EventProvider::UnsupportedProvider => "UNSUPPORTED PROVIDER",
         }
         .fmt(f)
     }
 }
 
-impl TryFrom<&SerializedEvtxRecord<Value>> for EventProvider {
-    type Error = anyhow::Error;
-
-    fn try_from(record: &SerializedEvtxRecord<Value>) -> Result<Self, Self::Error> {
-        let provider_name = record.data["Event"]["System"]["Provider"]["#attributes"]["Name"]
-            .as_str()
-            .unwrap();
-        Self::try_from(provider_name)
-    }
-}
-
-impl TryFrom<&str> for EventProvider {
-    type Error = anyhow::Error;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        Ok(match value {
+impl EventProvider {
+    /// returns the provider with the name `value`, or `None` if the provider
+    /// is not known. In contrast to `try_from`, this does not log anything,
+    /// so it can be used for every record of a file.
+    pub fn from_provider_name(value: &str) -> Option<Self> {
+        Some(match value {
             "Microsoft-Windows-TerminalServices-RemoteConnectionManager" => {
                 EventProvider::TerminalServicesRemoteConnectionManager
             }
@@ -62,7 +63,39 @@ impl TryFrom<&str> for EventProvider {
             }
             "Microsoft-Windows-Security-Auditing" => EventProvider::SecurityAuditing,
             "Desktop Window Manager" => EventProvider::DesktopWindowManager,
-            _ => {
+            "Service Control Manager" => EventProvider::ServiceControlManager,
+            "Microsoft-Windows-Eventlog" => EventProvider::Eventlog,
+            "Microsoft-Windows-PowerShell" => EventProvider::PowerShell,
+            "PowerShell" => EventProvider::WindowsPowerShell,
+            "Microsoft-Windows-TaskScheduler" => EventProvider::TaskScheduler,
+            _ => return None,
+        })
+    }
+
+    /// returns the provider name of `record`, if there is one
+    pub fn name_of(record: &SerializedEvtxRecord<Value>) -> Option<&str> {
+        record.data["Event"]["System"]["Provider"]["#attributes"]["Name"].as_str()
+    }
+}
+
+impl TryFrom<&SerializedEvtxRecord<Value>> for EventProvider {
+    type Error = anyhow::Error;
+
+    fn try_from(record: &SerializedEvtxRecord<Value>) -> Result<Self, Self::Error> {
+        match Self::name_of(record) {
+            Some(provider_name) => Self::try_from(provider_name),
+            None => Err(anyhow::anyhow!("missing provider name in event")),
+        }
+    }
+}
+
+impl TryFrom<&str> for EventProvider {
+    type Error = anyhow::Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Ok(match Self::from_provider_name(value) {
+            Some(provider) => provider,
+            None => {
                 //panic!("unknown provider name: {value}");
                 log::warn!("unknown provider name: {value}");
                 Self::UnsupportedProvider
@@ -71,6 +104,17 @@ impl TryFrom<&str> for EventProvider {
     }
 }
 
+impl<'de> Deserialize<'de> for EventProvider {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Self::from_provider_name(&value)
+            .ok_or_else(|| de::Error::custom(format!("unknown provider name: {value}")))
+    }
+}
+
 impl FromMeta for EventProvider {
     fn from_string(value: &str) -> darling::Result<Self> {
         match Self::try_from(value) {
@@ -94,6 +138,11 @@ impl quote::ToTokens for EventProvider {
             }
             EventProvider::SecurityAuditing => quote!(EventProvider::SecurityAuditing),
             EventProvider::DesktopWindowManager => quote!(EventProvider::DesktopWindowManager),
+            EventProvider::ServiceControlManager => quote!(EventProvider::ServiceControlManager),
+            EventProvider::Eventlog => quote!(EventProvider::Eventlog),
+            EventProvider::PowerShell => quote!(EventProvider::PowerShell),
+            EventProvider::WindowsPowerShell => quote!(EventProvider::WindowsPowerShell),
+            EventProvider::TaskScheduler => quote!(EventProvider::TaskScheduler),
             EventProvider::UnsupportedProvider => quote!(EventProvider::UnsupportedProvider),
         };
         tokens.extend(provider_token)
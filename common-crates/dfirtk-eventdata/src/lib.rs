@@ -1,6 +1,12 @@
 mod session_event_info;
 pub use session_event_info::*;
 
+mod session_event_definition;
+pub use session_event_definition::*;
+
+mod session_id_type;
+pub use session_id_type::*;
+
 mod session_id;
 pub use session_id::*;
 
@@ -27,3 +33,6 @@ pub use event_level::*;
 
 mod process_id;
 pub use process_id::*;
+
+mod logon_type;
+pub use logon_type::*;
@@ -0,0 +1,108 @@
+use std::fmt::Display;
+
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+
+/// logon type, as found in Security 4624 and 4625
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum LogonType {
+    System,
+    Interactive,
+    Network,
+    Batch,
+    Service,
+    Unlock,
+    NetworkCleartext,
+    NewCredentials,
+    RemoteInteractive,
+    CachedInteractive,
+    CachedRemoteInteractive,
+    CachedUnlock,
+    Unknown(u64),
+}
+
+impl LogonType {
+    /// parses the value of a `LogonType` field, which might be a number or a string
+    pub fn from_value(value: &Value) -> Option<Self> {
+        match value.as_u64() {
+            Some(logon_type) => Some(logon_type.into()),
+            None => value.as_str()?.parse::<u64>().ok().map(Self::from),
+        }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::System => "System",
+            Self::Interactive => "Interactive",
+            Self::Network => "Network",
+            Self::Batch => "Batch",
+            Self::Service => "Service",
+            Self::Unlock => "Unlock",
+            Self::NetworkCleartext => "NetworkCleartext",
+            Self::NewCredentials => "NewCredentials",
+            Self::RemoteInteractive => "RemoteInteractive",
+            Self::CachedInteractive => "CachedInteractive",
+            Self::CachedRemoteInteractive => "CachedRemoteInteractive",
+            Self::CachedUnlock => "CachedUnlock",
+            Self::Unknown(_) => return None,
+        })
+    }
+}
+
+impl From<u64> for LogonType {
+    fn from(value: u64) -> Self {
+        match value {
+            0 => Self::System,
+            2 => Self::Interactive,
+            3 => Self::Network,
+            4 => Self::Batch,
+            5 => Self::Service,
+            7 => Self::Unlock,
+            8 => Self::NetworkCleartext,
+            9 => Self::NewCredentials,
+            10 => Self::RemoteInteractive,
+            11 => Self::CachedInteractive,
+            12 => Self::CachedRemoteInteractive,
+            13 => Self::CachedUnlock,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<LogonType> for u64 {
+    fn from(value: LogonType) -> Self {
+        match value {
+            LogonType::System => 0,
+            LogonType::Interactive => 2,
+            LogonType::Network => 3,
+            LogonType::Batch => 4,
+            LogonType::Service => 5,
+            LogonType::Unlock => 7,
+            LogonType::NetworkCleartext => 8,
+            LogonType::NewCredentials => 9,
+            LogonType::RemoteInteractive => 10,
+            LogonType::CachedInteractive => 11,
+            LogonType::CachedRemoteInteractive => 12,
+            LogonType::CachedUnlock => 13,
+            LogonType::Unknown(value) => value,
+        }
+    }
+}
+
+impl Display for LogonType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{name} ({})", u64::from(*self)),
+            None => u64::from(*self).fmt(f),
+        }
+    }
+}
+
+impl Serialize for LogonType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
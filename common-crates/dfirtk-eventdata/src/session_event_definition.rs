@@ -0,0 +1,83 @@
+use darling::FromMeta;
+use evtx::SerializedEvtxRecord;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    EventId, EventProvider, MissingSessionId, SessionEventInfo, SessionId, SessionIdType,
+};
+
+/// describes how the session data can be found in an event. This is used by
+/// `#[derive(SessionEvent)]` via the `event_data(...)` attribute, as well as
+/// by session event definitions which are loaded at runtime.
+#[derive(FromMeta, Deserialize, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SessionEventDefinition {
+    /// the name of the provider, as found in `Event/System/Provider`. Runtime
+    /// definitions may use providers which are unknown to [`EventProvider`].
+    pub provider: String,
+    pub event_id: EventId,
+    pub description: String,
+    pub session_id: SessionIdType,
+    pub username_path: Option<String>,
+    pub domain_path: Option<String>,
+    pub client_hostname_path: Option<String>,
+    pub client_address_path: Option<String>,
+    pub server_hostname_path: Option<String>,
+    pub server_address_path: Option<String>,
+
+    /// the channel whose files contain this event, if it is not one of the
+    /// channels which are read anyway
+    pub channel: Option<String>,
+}
+
+/// returns the string value found at `path` (e.g. `Event/EventData/TargetUserName`)
+pub fn value_at_path(record: &SerializedEvtxRecord<Value>, path: &str) -> Option<String> {
+    path.split('/')
+        .fold(&record.data, |value, part| &value[part])
+        .as_str()
+        .map(|s| s.to_owned())
+}
+
+impl SessionEventDefinition {
+    fn value_of(&self, path: &Option<String>, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        value_at_path(record, path.as_ref()?)
+    }
+}
+
+impl SessionEventInfo for SessionEventDefinition {
+    fn event_id(&self) -> EventId {
+        self.event_id.clone()
+    }
+    fn description(&self) -> &str {
+        &self.description
+    }
+    fn provider(&self) -> EventProvider {
+        EventProvider::from_provider_name(&self.provider)
+            .unwrap_or(EventProvider::UnsupportedProvider)
+    }
+    fn generate_id(
+        &self,
+        record: &SerializedEvtxRecord<Value>,
+    ) -> Result<SessionId, MissingSessionId> {
+        self.session_id.session_id_of(record)
+    }
+    fn username(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.value_of(&self.username_path, record)
+    }
+    fn domain(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.value_of(&self.domain_path, record)
+    }
+    fn client_address(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.value_of(&self.client_address_path, record)
+    }
+    fn client_hostname(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.value_of(&self.client_hostname_path, record)
+    }
+    fn server_address(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.value_of(&self.server_address_path, record)
+    }
+    fn server_hostname(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.value_of(&self.server_hostname_path, record)
+    }
+}
@@ -1,13 +1,15 @@
+use std::sync::Arc;
+
 use evtx::SerializedEvtxRecord;
 use serde_json::Value;
 
-use crate::{SessionId, EventId, EventProvider};
+use crate::{SessionId, EventId, EventProvider, MissingSessionId};
 
 pub trait SessionEventInfo {
     fn event_id(&self) -> EventId;
-    fn description(&self) -> &'static str;
+    fn description(&self) -> &str;
     fn provider(&self) -> EventProvider;
-    fn generate_id(&self, record: &SerializedEvtxRecord<Value>) -> SessionId;
+    fn generate_id(&self, record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId>;
     fn username(&self, record: &SerializedEvtxRecord<Value>) -> Option<String>;
     fn domain(&self, record: &SerializedEvtxRecord<Value>) -> Option<String>;
     fn client_address(&self, record: &SerializedEvtxRecord<Value>) -> Option<String>;
@@ -15,4 +17,37 @@ pub trait SessionEventInfo {
     fn server_address(&self, record: &SerializedEvtxRecord<Value>) -> Option<String>;
     fn server_hostname(&self, record: &SerializedEvtxRecord<Value>) -> Option<String>;
 
-}
\ No newline at end of file
+}
+
+impl<T: SessionEventInfo + ?Sized> SessionEventInfo for Arc<T> {
+    fn event_id(&self) -> EventId {
+        self.as_ref().event_id()
+    }
+    fn description(&self) -> &str {
+        self.as_ref().description()
+    }
+    fn provider(&self) -> EventProvider {
+        self.as_ref().provider()
+    }
+    fn generate_id(&self, record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
+        self.as_ref().generate_id(record)
+    }
+    fn username(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.as_ref().username(record)
+    }
+    fn domain(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.as_ref().domain(record)
+    }
+    fn client_address(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.as_ref().client_address(record)
+    }
+    fn client_hostname(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.as_ref().client_hostname(record)
+    }
+    fn server_address(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.as_ref().server_address(record)
+    }
+    fn server_hostname(&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
+        self.as_ref().server_hostname(record)
+    }
+}
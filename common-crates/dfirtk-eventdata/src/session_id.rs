@@ -1,3 +1,4 @@
+use std::fmt::Display;
 use std::sync::Mutex;
 use std::convert::TryFrom;
 
@@ -15,44 +16,74 @@ pub enum SessionId {
     None(u64),
 }
 
+impl Display for SessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionId::ActivityId(id)
+            | SessionId::SessionName(id)
+            | SessionId::LogonId(id)
+            | SessionId::SessionId(id) => id.fmt(f),
+            SessionId::None(id) => id.fmt(f),
+        }
+    }
+}
+
+/// the error which is returned if an event does not contain the field
+/// which should hold its session id
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub struct MissingSessionId(pub &'static str);
+
+impl Display for MissingSessionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the event does not contain the field '{}', which should hold the session id",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for MissingSessionId {}
+
 pub trait SessionIdGenerator {
-    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> SessionId;
+    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId>;
 }
 
 static NO_SESSION_ID_MUTEX: Mutex<u64> = Mutex::new(0);
 pub struct NoSessionId {}
 impl SessionIdGenerator for NoSessionId {
-    fn session_id_of(_: &SerializedEvtxRecord<Value>) -> SessionId {
+    fn session_id_of(_: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
         let mut id_mutex = NO_SESSION_ID_MUTEX.lock().unwrap();
         let id = *id_mutex;
         *id_mutex = id + 1;
-        SessionId::None(id)
+        Ok(SessionId::None(id))
     }
 }
 
 pub struct SessionNameInEventData {}
 impl SessionIdGenerator for SessionNameInEventData {
-    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> SessionId {
-        SessionId::SessionName(
-            record.data["Event"]["EventData"]["SessionName"]
-                .as_str()
-                .expect("missing SessionName in event")
-                .into(),
-        )
+    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
+        match record.data["Event"]["EventData"]["SessionName"].as_str() {
+            Some(name) => Ok(SessionId::SessionName(name.into())),
+            None => Err(MissingSessionId("SessionName")),
+        }
     }
 }
 
 pub struct SessionNameInActivityId {}
 impl SessionIdGenerator for SessionNameInActivityId {
-    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> SessionId {
-        let activity_id = ActivityId::try_from(record).expect("missing activity id in event");
+    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
+        let activity_id = match ActivityId::try_from(record) {
+            Ok(activity_id) => activity_id,
+            Err(_) => return Err(MissingSessionId("ActivityID")),
+        };
 
         match activity_id.value().as_str() {
             None => {
-                SessionId::ActivityId("".into())
+                Ok(SessionId::ActivityId("".into()))
             }
             Some(activity_id) => {
-                SessionId::ActivityId(activity_id.into())
+                Ok(SessionId::ActivityId(activity_id.into()))
             }
         }
     }
@@ -60,53 +91,47 @@ impl SessionIdGenerator for SessionNameInActivityId {
 
 pub struct SessionNameInTargetLogonId {}
 impl SessionIdGenerator for SessionNameInTargetLogonId {
-    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> SessionId {
-        SessionId::LogonId(
-            record.data["Event"]["EventData"]["SessionName"]
-                .as_str()
-                .expect("missing TargetLogonId in event")
-                .into(),
-        )
+    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
+        match record.data["Event"]["EventData"]["TargetLogonId"].as_str() {
+            Some(id) => Ok(SessionId::LogonId(id.into())),
+            None => Err(MissingSessionId("TargetLogonId")),
+        }
     }
 }
 
 pub struct SessionNameInSubjectLogonId {}
 impl SessionIdGenerator for SessionNameInSubjectLogonId {
-    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> SessionId {
-        SessionId::LogonId(
-            record.data["Event"]["EventData"]["SubjectLogonId"]
-                .as_str()
-                .expect("missing SubjectLogonId in event")
-                .into(),
-        )
+    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
+        match record.data["Event"]["EventData"]["SubjectLogonId"].as_str() {
+            Some(id) => Ok(SessionId::LogonId(id.into())),
+            None => Err(MissingSessionId("SubjectLogonId")),
+        }
     }
 }
 
 pub struct SessionNameInLogonId {}
 impl SessionIdGenerator for SessionNameInLogonId {
-    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> SessionId {
+    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
         if let Some(children) = record.data["Event"]["EventData"].as_object() {
-            for child in children {
-                if child.0.to_lowercase() == "targetlogonid" {
-                    return SessionId::LogonId(child.1.as_str().unwrap().to_owned())
-                }
-                if child.0.to_lowercase() == "logonid" {
-                    return SessionId::LogonId(child.1.as_str().unwrap().to_owned())
+            for (name, value) in children {
+                let name = name.to_lowercase();
+                if name == "targetlogonid" || name == "logonid" {
+                    if let Some(id) = value.as_str() {
+                        return Ok(SessionId::LogonId(id.to_owned()));
+                    }
                 }
             }
         }
-        panic!("missing LogonId in event: {event}", event = record.data);
+        Err(MissingSessionId("LogonId"))
     }
 }
 
 pub struct SessionIdInUserData {}
 impl SessionIdGenerator for SessionIdInUserData {
-    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> SessionId {
-        SessionId::LogonId(
-            record.data["Event"]["UserData"]["EventXML"]["SessionID"]
-                .as_str()
-                .expect("missing SessionID in event")
-                .into(),
-        )
+    fn session_id_of(record: &SerializedEvtxRecord<Value>) -> Result<SessionId, MissingSessionId> {
+        match record.data["Event"]["UserData"]["EventXML"]["SessionID"].as_str() {
+            Some(id) => Ok(SessionId::LogonId(id.into())),
+            None => Err(MissingSessionId("SessionID")),
+        }
     }
 }
@@ -0,0 +1,74 @@
+use darling::FromMeta;
+use evtx::SerializedEvtxRecord;
+use quote::{ToTokens, quote};
+use serde::{de, Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::{
+    MissingSessionId, NoSessionId, SessionId, SessionIdGenerator, SessionNameInActivityId,
+    SessionNameInEventData, SessionNameInLogonId,
+};
+
+/// the field of an event which holds its session id
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum SessionIdType {
+    ActivityId,
+    SessionName,
+    LogonId,
+    None,
+}
+
+impl SessionIdType {
+    pub fn from_name(value: &str) -> Option<Self> {
+        match value {
+            "ActivityId" => Some(SessionIdType::ActivityId),
+            "SessionName" => Some(SessionIdType::SessionName),
+            "LogonId" => Some(SessionIdType::LogonId),
+            "None" => Some(SessionIdType::None),
+            _ => None,
+        }
+    }
+
+    /// reads the session id of `record`, using the same generator as the
+    /// code which is created by `#[derive(SessionEvent)]`
+    pub fn session_id_of(
+        &self,
+        record: &SerializedEvtxRecord<Value>,
+    ) -> Result<SessionId, MissingSessionId> {
+        match self {
+            SessionIdType::ActivityId => SessionNameInActivityId::session_id_of(record),
+            SessionIdType::SessionName => SessionNameInEventData::session_id_of(record),
+            SessionIdType::LogonId => SessionNameInLogonId::session_id_of(record),
+            SessionIdType::None => NoSessionId::session_id_of(record),
+        }
+    }
+}
+
+impl FromMeta for SessionIdType {
+    fn from_string(value: &str) -> darling::Result<Self> {
+        Self::from_name(value).ok_or_else(|| darling::Error::unknown_value(value))
+    }
+}
+
+impl<'de> Deserialize<'de> for SessionIdType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        Self::from_name(&value)
+            .ok_or_else(|| de::Error::custom(format!("unknown session id type: {value}")))
+    }
+}
+
+impl ToTokens for SessionIdType {
+    fn to_tokens(&self, tokens: &mut quote::__private::TokenStream) {
+        let generator = match self {
+            SessionIdType::ActivityId => quote!(SessionNameInActivityId),
+            SessionIdType::SessionName => quote!(SessionNameInEventData),
+            SessionIdType::LogonId => quote!(SessionNameInLogonId),
+            SessionIdType::None => quote!(NoSessionId),
+        };
+        tokens.extend(generator)
+    }
+}
@@ -1,25 +1,16 @@
 use darling::FromDeriveInput;
-use dfirtk_eventdata::{EventProvider, EventId};
+use dfirtk_eventdata::{EventProvider, SessionEventDefinition};
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-mod session_id_type;
-use session_id_type::*;
-
+/// the options of `#[event_data(...)]`, which are the same as those of
+/// session event definitions loaded at runtime
 #[derive(FromDeriveInput)]
 #[darling(attributes(event_data))]
 struct EventStructOptions {
-    provider: EventProvider,
-    event_id: EventId,
-    description: String,
-    session_id: SessionIdType,
-    username_path: Option<String>,
-    domain_path: Option<String>,
-    client_hostname_path: Option<String>,
-    client_address_path: Option<String>,
-    server_hostname_path: Option<String>,
-    server_address_path: Option<String>,
+    #[darling(flatten)]
+    definition: SessionEventDefinition,
 }
 
 fn create_getter(path: &Option<String>, function_name: TokenStream) -> TokenStream {
@@ -29,10 +20,9 @@ fn create_getter(path: &Option<String>, function_name: TokenStream) -> TokenStre
                 None
             }),
         Some(path) => {
-            let parts: Vec<_> = path.split('/').map(|part| quote!([#part])).collect();
             quote!(
                 fn #function_name (&self, record: &SerializedEvtxRecord<Value>) -> Option<String> {
-                    record.data #(#parts)* .as_str().map(|s|s.to_owned())
+                    dfirtk_eventdata::value_at_path(record, #path)
                 })
         }
     }
@@ -43,8 +33,16 @@ pub fn derive_session_event(input: proc_macro::TokenStream) -> proc_macro::Token
     let input = parse_macro_input!(input as DeriveInput);
     let opts = EventStructOptions::from_derive_input(&input).expect("Wrong options");
     let name = input.ident;
+    let opts = opts.definition;
 
-    let provider = opts.provider;
+    let provider = match EventProvider::from_provider_name(&opts.provider) {
+        Some(provider) => provider,
+        None => {
+            return darling::Error::unknown_value(&opts.provider)
+                .write_errors()
+                .into()
+        }
+    };
     let event_id = opts.event_id;
     let description = opts.description;
     let session_id_type = opts.session_id;
@@ -61,13 +59,13 @@ pub fn derive_session_event(input: proc_macro::TokenStream) -> proc_macro::Token
             fn event_id(&self) -> EventId {
                 #event_id
             }
-            fn description(&self) -> &'static str {
+            fn description(&self) -> &str {
                 #description
             }
             fn provider(&self) -> EventProvider {
                 #provider
             }
-            fn generate_id(&self, record: &SerializedEvtxRecord<Value>) -> dfirtk_eventdata::SessionId {
+            fn generate_id(&self, record: &SerializedEvtxRecord<Value>) -> Result<dfirtk_eventdata::SessionId, dfirtk_eventdata::MissingSessionId> {
                 #session_id_type::session_id_of(record)
             }
             #username_getter
@@ -79,6 +79,7 @@ impl EvtxFile {
         Ok(())
     }
 
+    #[allow(clippy::result_large_err)]
     pub(crate) fn count_records(&self) -> Result<usize> {
         let mut parser = EvtxParser::from_read_seek(self.0.clone())?;
         Ok(parser.serialized_records(|r| r.and(Ok(()))).count())
@@ -0,0 +1,237 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use dfirtk_eventdata::{EventId, EventProvider};
+use evtx::SerializedEvtxRecord;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::helpers::EventData;
+
+/// attributes of 4738 which have a value different to `-` if they have been changed
+const CHANGED_ATTRIBUTES: &[&str] = &[
+    "SamAccountName",
+    "DisplayName",
+    "UserPrincipalName",
+    "HomeDirectory",
+    "HomePath",
+    "ScriptPath",
+    "ProfilePath",
+    "UserWorkstations",
+    "PasswordLastSet",
+    "AccountExpires",
+    "PrimaryGroupId",
+    "AllowedToDelegateTo",
+    "OldUacValue",
+    "NewUacValue",
+    "UserAccountControl",
+    "UserParameters",
+    "SidHistory",
+    "LogonHours",
+];
+
+/// RIDs of privileged domain groups
+const PRIVILEGED_DOMAIN_RIDS: &[&str] = &["-512", "-518", "-519", "-520"];
+
+/// SIDs of privileged builtin groups
+const PRIVILEGED_BUILTIN_SIDS: &[&str] = &[
+    "S-1-5-32-544", // Administrators
+    "S-1-5-32-548", // Account Operators
+    "S-1-5-32-549", // Server Operators
+    "S-1-5-32-550", // Print Operators
+    "S-1-5-32-551", // Backup Operators
+    "S-1-5-32-555", // Remote Desktop Users
+    "S-1-5-32-580", // Remote Management Users
+];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountChangeKind {
+    Created,
+    Enabled,
+    Disabled,
+    PasswordChanged,
+    PasswordReset,
+    Deleted,
+    AddedToGroup,
+    Changed,
+    Renamed,
+}
+
+impl Display for AccountChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            Self::Created => "created",
+            Self::Enabled => "enabled",
+            Self::Disabled => "disabled",
+            Self::PasswordChanged => "password changed",
+            Self::PasswordReset => "password reset",
+            Self::Deleted => "deleted",
+            Self::AddedToGroup => "added to group",
+            Self::Changed => "changed",
+            Self::Renamed => "renamed",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// a change of a user account or of a group membership, found in
+/// Security 4720, 4722-4726, 4728, 4732, 4738, 4756 and 4781
+#[derive(Serialize, Clone, Debug)]
+pub struct AccountChange {
+    pub timestamp: DateTime<Utc>,
+    pub computer: String,
+    pub event_id: u16,
+    pub change: AccountChangeKind,
+    pub actor: Option<String>,
+    pub actor_logon_id: Option<String>,
+    pub target: String,
+    pub target_sid: Option<String>,
+    pub group: Option<String>,
+    pub details: Option<String>,
+
+    /// `true` if the target has been added to a privileged group
+    pub privileged: bool,
+}
+
+impl AccountChange {
+    pub fn try_from(record: &SerializedEvtxRecord<Value>) -> Option<Self> {
+        if EventProvider::name_of(record)? != "Microsoft-Windows-Security-Auditing" {
+            return None;
+        }
+        let event_id = EventId::try_from(record).ok()?.value();
+        let event_data = EventData::of(record);
+
+        let change = match event_id {
+            4720 => AccountChangeKind::Created,
+            4722 => AccountChangeKind::Enabled,
+            4723 => AccountChangeKind::PasswordChanged,
+            4724 => AccountChangeKind::PasswordReset,
+            4725 => AccountChangeKind::Disabled,
+            4726 => AccountChangeKind::Deleted,
+            4728 | 4732 | 4756 => AccountChangeKind::AddedToGroup,
+            4738 => AccountChangeKind::Changed,
+            4781 => AccountChangeKind::Renamed,
+            _ => return None,
+        };
+
+        let mut group = None;
+        let mut details = None;
+        let mut privileged = false;
+        let target_sid;
+        let target = match change {
+            AccountChangeKind::AddedToGroup => {
+                let group_sid = event_data.field("TargetSid");
+                privileged = group_sid.as_deref().is_some_and(is_privileged_group);
+                group = event_data.account("TargetDomainName", "TargetUserName");
+                target_sid = event_data.field("MemberSid");
+                event_data
+                    .field("MemberName")
+                    .or_else(|| target_sid.clone())?
+            }
+            AccountChangeKind::Renamed => {
+                let old_name = event_data.field("OldTargetUserName")?;
+                let new_name = event_data.field("NewTargetUserName")?;
+                details = Some(format!("{old_name} -> {new_name}"));
+                target_sid = event_data.field("TargetSid");
+                event_data.account("TargetDomainName", "NewTargetUserName")?
+            }
+            _ => {
+                if change == AccountChangeKind::Changed || change == AccountChangeKind::Created {
+                    let attributes: Vec<_> = CHANGED_ATTRIBUTES
+                        .iter()
+                        .filter_map(|name| {
+                            event_data
+                                .field(name)
+                                .map(|value| format!("{name}={value}"))
+                        })
+                        .collect();
+                    if !attributes.is_empty() {
+                        details = Some(attributes.join(", "));
+                    }
+                }
+                target_sid = event_data.field("TargetSid");
+                event_data.account("TargetDomainName", "TargetUserName")?
+            }
+        };
+
+        Some(Self {
+            timestamp: record.timestamp,
+            computer: record.data["Event"]["System"]["Computer"]
+                .as_str()
+                .unwrap_or_default()
+                .to_owned(),
+            event_id,
+            change,
+            actor: event_data.account("SubjectDomainName", "SubjectUserName"),
+            actor_logon_id: event_data.field("SubjectLogonId"),
+            target,
+            target_sid,
+            group,
+            details,
+            privileged,
+        })
+    }
+}
+
+fn is_privileged_group(sid: &str) -> bool {
+    PRIVILEGED_BUILTIN_SIDS.contains(&sid)
+        || (sid.starts_with("S-1-5-21-")
+            && PRIVILEGED_DOMAIN_RIDS.iter().any(|rid| sid.ends_with(rid)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{AccountChange, AccountChangeKind};
+    use crate::helpers::record;
+
+    const SECURITY: &str = "Microsoft-Windows-Security-Auditing";
+
+    #[test]
+    fn added_to_domain_admins() {
+        let change = AccountChange::try_from(&record(
+            SECURITY,
+            4728,
+            "dc01",
+            json!({
+                "MemberName": "CN=backup,CN=Users,DC=contoso,DC=local",
+                "MemberSid": "S-1-5-21-1-2-3-1107",
+                "TargetUserName": "Domain Admins",
+                "TargetDomainName": "CONTOSO",
+                "TargetSid": "S-1-5-21-1-2-3-512",
+                "SubjectUserName": "mallory",
+                "SubjectDomainName": "CONTOSO",
+                "SubjectLogonId": "0x3e7a1",
+            }),
+        ))
+        .unwrap();
+        assert_eq!(change.change, AccountChangeKind::AddedToGroup);
+        assert_eq!(change.target, "CN=backup,CN=Users,DC=contoso,DC=local");
+        assert_eq!(change.group.as_deref(), Some("CONTOSO\\Domain Admins"));
+        assert_eq!(change.actor.as_deref(), Some("CONTOSO\\mallory"));
+        assert!(change.privileged);
+    }
+
+    #[test]
+    fn renamed() {
+        let change = AccountChange::try_from(&record(
+            SECURITY,
+            4781,
+            "dc01",
+            json!({
+                "OldTargetUserName": "guest",
+                "NewTargetUserName": "svc_backup",
+                "TargetDomainName": "CONTOSO",
+                "TargetSid": "S-1-5-21-1-2-3-501",
+                "SubjectUserName": "mallory",
+                "SubjectDomainName": "CONTOSO",
+            }),
+        ))
+        .unwrap();
+        assert_eq!(change.target, "CONTOSO\\svc_backup");
+        assert_eq!(change.details.as_deref(), Some("guest -> svc_backup"));
+        assert!(!change.privileged);
+    }
+}
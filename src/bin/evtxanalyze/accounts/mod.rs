@@ -0,0 +1,111 @@
+mod account_change;
+pub use account_change::*;
+
+use std::{io::stdout, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::helpers::{for_each_record, markdown_row};
+
+#[derive(ValueEnum, Clone)]
+pub enum AccountsFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+#[derive(Serialize)]
+struct ChangeAsCsv<'a> {
+    timestamp: DateTime<Utc>,
+    computer: &'a str,
+    event_id: u16,
+    change: AccountChangeKind,
+    actor: Option<&'a str>,
+    actor_logon_id: Option<&'a str>,
+    target: &'a str,
+    target_sid: Option<&'a str>,
+    group: Option<&'a str>,
+    details: Option<&'a str>,
+    privileged: bool,
+}
+
+impl<'a> From<&'a AccountChange> for ChangeAsCsv<'a> {
+    fn from(change: &'a AccountChange) -> Self {
+        Self {
+            timestamp: change.timestamp,
+            computer: &change.computer,
+            event_id: change.event_id,
+            change: change.change,
+            actor: change.actor.as_deref(),
+            actor_logon_id: change.actor_logon_id.as_deref(),
+            target: &change.target,
+            target_sid: change.target_sid.as_deref(),
+            group: change.group.as_deref(),
+            details: change.details.as_deref(),
+            privileged: change.privileged,
+        }
+    }
+}
+
+pub(crate) fn display_accounts(
+    evtx_files: &[PathBuf],
+    privileged_only: bool,
+    format: &AccountsFormat,
+) -> anyhow::Result<()> {
+    let mut changes = Vec::new();
+    for evtx_file in evtx_files {
+        for_each_record(evtx_file, |record| {
+            if let Some(change) = AccountChange::try_from(record) {
+                changes.push(change);
+            }
+        })?;
+    }
+    changes.sort_by_key(|c| c.timestamp);
+
+    let privileged = changes.iter().filter(|c| c.privileged).count();
+    log::info!("found {} account changes", changes.len());
+    if privileged > 0 {
+        log::warn!("found {privileged} additions to privileged groups");
+    }
+    if privileged_only {
+        changes.retain(|c| c.privileged);
+    }
+
+    match format {
+        AccountsFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(stdout());
+            for change in changes.iter() {
+                csv_writer.serialize(ChangeAsCsv::from(change))?;
+            }
+            csv_writer.flush()?;
+        }
+        AccountsFormat::Json => println!("{}", serde_json::to_string_pretty(&changes)?),
+        AccountsFormat::Markdown => {
+            println!(
+                "| timestamp | computer | event id | change | actor | target | group | details |"
+            );
+            println!("|-|-|-|-|-|-|-|-|");
+            for change in changes {
+                let group = match &change.group {
+                    Some(group) if change.privileged => format!("**`{group}`**"),
+                    Some(group) => format!("`{group}`"),
+                    None => String::new(),
+                };
+                let cells = [
+                    change.timestamp.format("%FT%T").to_string(),
+                    change.computer.clone(),
+                    change.event_id.to_string(),
+                    change.change.to_string(),
+                    change.actor.map(|a| format!("`{a}`")).unwrap_or_default(),
+                    format!("`{}`", change.target),
+                    group,
+                    change.details.unwrap_or_default(),
+                ];
+                println!("{}", markdown_row(&cells));
+            }
+        }
+    }
+    Ok(())
+}
@@ -1,10 +1,21 @@
 use std::{io::stdout, path::PathBuf};
 
+use chrono::Duration;
 use clap::{Parser, Subcommand, ValueEnum, ValueHint};
-use dfir_toolkit::common::HasVerboseFlag;
+use dfir_toolkit::common::{HasVerboseFlag, Rfc3339Datetime};
 use log::LevelFilter;
 
-use super::sessions::SessionStore;
+use super::accounts::{display_accounts, AccountsFormat};
+use super::helpers::markdown_row;
+use super::lateral::{display_lateral, LateralFormat};
+use super::logons::{display_logons, LogonsFormat, LogonsOptions};
+use super::persistence::{display_persistence, PersistenceFormat};
+use super::pstree::{labels::Language, process_filter::ProcessSelector};
+use super::scriptblocks::{display_scriptblocks, ScriptBlocksFormat, ScriptBlocksOptions};
+use super::session_pstree::{display_session_pstree, SessionPsTreeFormat};
+use super::sessions::{
+    EventAsCsv, SessionAsBodyfile, SessionAsCsv, SessionAsJson, SessionEventRules, SessionStore,
+};
 
 #[derive(ValueEnum, Clone)]
 pub enum Format {
@@ -16,6 +27,19 @@ pub enum Format {
     LaTeX,
 
     Dot,
+
+    /// self-contained HTML page
+    Html,
+}
+
+#[derive(ValueEnum, Clone)]
+pub enum SessionFormat {
+    Csv,
+    Json,
+    Markdown,
+
+    /// session begin, end, disconnects and reconnects as bodyfile lines
+    Bodyfile,
 }
 
 #[derive(Subcommand)]
@@ -27,40 +51,235 @@ pub enum Command {
         #[clap(short('U'), long("username"))]
         username: Option<String>,
 
-        /// Name of the evtx file to parse (should be the path to "Security.evtx")
-        #[clap(value_hint=ValueHint::FilePath)]
-        evtx_file: PathBuf,
+        /// Names of the evtx files to parse (should be the path to "Security.evtx"
+        /// and/or "Microsoft-Windows-Sysmon%4Operational.evtx"). Process creations
+        /// found in both files are merged
+        #[clap(value_hint=ValueHint::FilePath, required=true)]
+        evtx_files: Vec<PathBuf>,
 
         /// output format
         #[clap(short('F'), long("format"), value_enum, default_value_t=Format::Csv)]
         format: Format,
+
+        /// display only processes started after the specified date (hint: use RFC 3339 syntax).
+        /// Processes whose parent has been started before are displayed as root processes
+        #[clap(short('f'), long("from"))]
+        not_before: Option<Rfc3339Datetime>,
+
+        /// display only processes started before the specified date (hint: use RFC 3339 syntax)
+        #[clap(short('t'), long("to"))]
+        not_after: Option<Rfc3339Datetime>,
+
+        /// display only processes whose image matches (case insensitive regex search),
+        /// together with their ancestors and descendants
+        #[clap(short('I'), long("image"))]
+        image: Option<String>,
+
+        /// display only the process with this process id or image name, together
+        /// with its ancestors and descendants
+        #[clap(short('S'), long("subtree-of"), value_name = "PID|IMAGE")]
+        subtree_of: Option<ProcessSelector>,
+
+        /// language of labels in Markdown, LaTeX and HTML output
+        #[clap(short('L'), long("language"), value_enum, default_value_t=Language::default())]
+        language: Language,
     },
 
     /// display sessions
     #[clap(name = "sessions")]
     Sessions {
-        /// Names of the evtx files directory to parse. Be aware that
-        /// this tool assumes some file names. If you renamed the files,
-        /// session analysis wil not work correctly.
-        #[clap(value_hint=ValueHint::DirPath)]
-        evtx_files_dir: PathBuf,
+        /// Names of the evtx files directories to parse, e.g. one directory per host.
+        /// Subdirectories are searched as well, and files are identified by their
+        /// channel, so that renamed and archived files are included
+        #[clap(value_hint=ValueHint::DirPath, required=true)]
+        evtx_files_dirs: Vec<PathBuf>,
+
+        /// include anonymous sessions
+        #[clap(long("include-anonymous"))]
+        include_anonymous: bool,
+
+        /// TOML file with additional session event definitions, which take
+        /// precedence over the builtin definitions
+        #[clap(short('R'), long("rules"), value_hint=ValueHint::FilePath)]
+        rules: Option<PathBuf>,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=SessionFormat::Csv)]
+        format: SessionFormat,
+
+        /// merge sessions which belong to the same RDP logon (terminal services,
+        /// RdpCoreTS and Security events) into one logical session
+        #[clap(short('C'), long("correlate"))]
+        correlate: bool,
+    },
+
+    /// display the process trees which have been started in logon sessions
+    #[clap(name = "session-pstree")]
+    SessionPsTree {
+        /// Names of the evtx files directories to parse, e.g. one directory per host.
+        /// Processes are read from the Security log and the Sysmon Operational log,
+        /// which are identified by their channel, so that renamed and archived files
+        /// are included
+        #[clap(value_hint=ValueHint::DirPath, required=true)]
+        evtx_files_dirs: Vec<PathBuf>,
 
         /// include anonymous sessions
         #[clap(long("include-anonymous"))]
         include_anonymous: bool,
+
+        /// TOML file with additional session event definitions, which take
+        /// precedence over the builtin definitions
+        #[clap(short('R'), long("rules"), value_hint=ValueHint::FilePath)]
+        rules: Option<PathBuf>,
+
+        /// display only the session with this logon id (e.g. 0x3e7)
+        #[clap(short('S'), long("session"))]
+        session_id: Option<String>,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=SessionPsTreeFormat::Markdown)]
+        format: SessionPsTreeFormat,
+
+        /// language of labels in Markdown output
+        #[clap(short('L'), long("language"), value_enum, default_value_t=Language::default())]
+        language: Language,
+    },
+
+    /// detect brute-force and password-spray attacks in failed and successful logons
+    #[clap(name = "logons")]
+    Logons {
+        /// Names of the evtx files to parse (should be the path to "Security.evtx").
+        /// Logons are read from 4624, 4625, 4771 and 4776
+        #[clap(value_hint=ValueHint::FilePath, required=true)]
+        evtx_files: Vec<PathBuf>,
+
+        /// size of the sliding time window, in seconds
+        #[clap(short('w'), long("window"), default_value_t = 300)]
+        window: u32,
+
+        /// minimum number of failed logons to one single account in a time window,
+        /// which is considered as brute-force
+        #[clap(long("brute-force-threshold"), default_value_t = 10)]
+        brute_force_threshold: usize,
+
+        /// minimum number of accounts with failed logons in a time window,
+        /// which is considered as password spray
+        #[clap(long("spray-threshold"), default_value_t = 5)]
+        spray_threshold: usize,
+
+        /// display the number of logons per source address, workstation and account
+        /// in each time window instead of attacks
+        #[clap(long("statistics"))]
+        statistics: bool,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=LogonsFormat::Markdown)]
+        format: LogonsFormat,
+    },
+
+    /// list installed, modified and deleted services and scheduled tasks
+    #[clap(name = "persistence")]
+    Persistence {
+        /// Names of the evtx files to parse (should be the path to "System.evtx",
+        /// "Security.evtx" and/or "Microsoft-Windows-TaskScheduler%4Operational.evtx").
+        /// Services are read from 7045 and 4697, tasks from 4698-4702, 106, 140, 141, 200 and 201
+        #[clap(value_hint=ValueHint::FilePath, required=true)]
+        evtx_files: Vec<PathBuf>,
+
+        /// display only services and tasks with suspicious binary paths
+        #[clap(short('s'), long("suspicious-only"))]
+        suspicious_only: bool,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=PersistenceFormat::Markdown)]
+        format: PersistenceFormat,
+    },
+
+    /// reassemble PowerShell script blocks and decode encoded commands
+    #[clap(name = "scriptblocks")]
+    ScriptBlocks {
+        /// Names of the evtx files to parse (should be the path to
+        /// "Microsoft-Windows-PowerShell%4Operational.evtx" and/or "Windows PowerShell.evtx").
+        /// Script blocks are read from 4104, encoded commands from 4103, 400 and 800
+        #[clap(value_hint=ValueHint::FilePath, required=true)]
+        evtx_files: Vec<PathBuf>,
+
+        /// directory where every reassembled script block is written to,
+        /// using its script block id as file name
+        #[clap(short('O'), long("output-dir"), value_hint=ValueHint::DirPath)]
+        output_dir: Option<PathBuf>,
+
+        /// display the decoded values of `-EncodedCommand` instead of script blocks
+        #[clap(short('E'), long("encoded-commands"))]
+        encoded_commands: bool,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=ScriptBlocksFormat::Markdown)]
+        format: ScriptBlocksFormat,
+    },
+
+    /// list creations, deletions and changes of user accounts and group memberships
+    #[clap(name = "accounts")]
+    Accounts {
+        /// Names of the evtx files to parse (should be the path to "Security.evtx").
+        /// Changes are read from 4720, 4722-4726, 4728, 4732, 4738, 4756 and 4781
+        #[clap(value_hint=ValueHint::FilePath, required=true)]
+        evtx_files: Vec<PathBuf>,
+
+        /// display only additions to privileged groups, such as Domain Admins or Administrators
+        #[clap(short('P'), long("privileged-only"))]
+        privileged_only: bool,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=AccountsFormat::Markdown)]
+        format: AccountsFormat,
+    },
+
+    /// display connections between hosts, to find lateral movement
+    #[clap(name = "lateral")]
+    Lateral {
+        /// Names of the evtx files directories to parse, e.g. one directory per host.
+        /// Connections are read from the Security log (4624, 4648, 5140 and 5145) and
+        /// the RDPClient Operational log (1024 and 1102), which are identified by their
+        /// channel, so that renamed and archived files are included
+        #[clap(value_hint=ValueHint::DirPath, required=true)]
+        evtx_files_dirs: Vec<PathBuf>,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=LateralFormat::Csv)]
+        format: LateralFormat,
     },
 
     /// display one single session
     #[clap(name = "session")]
     Session {
-        /// Names of the evtx files directory to parse. Be aware that
-        /// this tool assumes some file names. If you renamed the files,
-        /// session analysis wil not work correctly.
-        #[clap(value_hint=ValueHint::DirPath)]
-        evtx_files_dir: PathBuf,
+        /// Names of the evtx files directories to parse, e.g. one directory per host.
+        /// Subdirectories are searched as well, and files are identified by their
+        /// channel, so that renamed and archived files are included
+        #[clap(value_hint=ValueHint::DirPath, required=true)]
+        evtx_files_dirs: Vec<PathBuf>,
 
         /// Session ID
         session_id: String,
+
+        /// name of the computer which logged the session, if the session id is
+        /// not unique across all hosts
+        #[clap(long("computer"))]
+        computer: Option<String>,
+
+        /// TOML file with additional session event definitions, which take
+        /// precedence over the builtin definitions
+        #[clap(short('R'), long("rules"), value_hint=ValueHint::FilePath)]
+        rules: Option<PathBuf>,
+
+        /// output format
+        #[clap(short('F'), long("format"), value_enum, default_value_t=SessionFormat::Csv)]
+        format: SessionFormat,
+
+        /// merge sessions which belong to the same RDP logon (terminal services,
+        /// RdpCoreTS and Security events) into one logical session
+        #[clap(short('C'), long("correlate"))]
+        correlate: bool,
     },
 }
 
@@ -79,19 +298,56 @@ impl Cli {
     pub fn display_single_session(&self) -> anyhow::Result<()> {
         match &self.command {
             Command::Session {
-                evtx_files_dir,
+                evtx_files_dirs,
                 session_id,
+                computer,
+                rules,
+                format,
+                correlate,
             } => {
-                let sessions = SessionStore::import(evtx_files_dir, true)?;
-                match sessions.find_session(session_id) {
+                let rules = SessionEventRules::load_or_default(rules.as_deref())?;
+                let mut sessions = SessionStore::import(evtx_files_dirs, true, &rules)?;
+                if *correlate {
+                    sessions.correlate();
+                }
+                match sessions.find_session(session_id, computer.as_deref()) {
                     None => log::error!("no value found for session id {session_id}"),
-                    Some(session) => {
-                        let mut csv_writer = csv::Writer::from_writer(stdout());
-                        for event in session.iter_events() {
-                            event.to_csv(&mut csv_writer)?;
+                    Some(session) => match format {
+                        SessionFormat::Csv => {
+                            let mut csv_writer = csv::Writer::from_writer(stdout());
+                            for event in session.iter_events() {
+                                event.to_csv(&mut csv_writer)?;
+                            }
+                            csv_writer.flush()?;
                         }
-                        csv_writer.flush()?;
-                    }
+                        SessionFormat::Json => {
+                            let events: Vec<_> =
+                                session.iter_events().map(EventAsCsv::from).collect();
+                            println!("{}", serde_json::to_string_pretty(&events)?);
+                        }
+                        SessionFormat::Markdown => {
+                            println!("| timestamp | event id | level | description | user | client |");
+                            println!("|-|-|-|-|-|-|");
+                            for event in session.iter_events().map(EventAsCsv::from) {
+                                println!(
+                                    "{}",
+                                    markdown_row(&[
+                                        event.timestamp.format("%FT%T").to_string(),
+                                        event.event_id.to_string(),
+                                        event.level.map(|l| l.to_string()).unwrap_or_default(),
+                                        event.description,
+                                        event.username.unwrap_or_default(),
+                                        event.client_address.or(event.client_name).unwrap_or_default(),
+                                    ])
+                                );
+                            }
+                        }
+                        SessionFormat::Bodyfile => {
+                            for line in SessionAsBodyfile::from(session) {
+                                println!("{line}");
+                            }
+                        }
+                    },
                 }
                 Ok(())
             }
@@ -102,21 +358,159 @@ impl Cli {
     pub fn display_sessions(&self) -> anyhow::Result<()> {
         match &self.command {
             Command::Sessions {
-                evtx_files_dir,
+                evtx_files_dirs,
                 include_anonymous,
+                rules,
+                format,
+                correlate,
             } => {
-                let sessions = SessionStore::import(evtx_files_dir, *include_anonymous)?;
+                let rules = SessionEventRules::load_or_default(rules.as_deref())?;
+                let mut sessions =
+                    SessionStore::import(evtx_files_dirs, *include_anonymous, &rules)?;
+                if *correlate {
+                    sessions.correlate();
+                }
 
-                let mut csv_writer = csv::Writer::from_writer(stdout());
-                for session in sessions {
-                    session.into_csv(&mut csv_writer)?;
+                match format {
+                    SessionFormat::Csv => {
+                        let mut csv_writer = csv::Writer::from_writer(stdout());
+                        for session in sessions {
+                            session.into_csv(&mut csv_writer)?;
+                        }
+                        csv_writer.flush()?;
+                    }
+                    SessionFormat::Json => {
+                        let sessions: Vec<SessionAsJson> =
+                            sessions.into_iter().map(Into::into).collect();
+                        println!("{}", serde_json::to_string_pretty(&sessions)?);
+                    }
+                    SessionFormat::Markdown => {
+                        println!("| begin | end | duration | session id | related sessions | domain | users | clients | computer | lifecycle | events |");
+                        println!("|-|-|-|-|-|-|-|-|-|-|-|");
+                        for session in sessions {
+                            let session: SessionAsCsv = session.into();
+                            println!(
+                                "{}",
+                                markdown_row(&[
+                                    session.begin.format("%FT%T").to_string(),
+                                    session.end.format("%FT%T").to_string(),
+                                    session.duration.to_string(),
+                                    session.session_id.to_string(),
+                                    session.related_sessions,
+                                    session.domain.map(|d| d.to_string()).unwrap_or_default(),
+                                    session.usernames,
+                                    session.clients,
+                                    session.computer,
+                                    session.lifecycle,
+                                    session.events.to_string(),
+                                ])
+                            );
+                        }
+                    }
+                    SessionFormat::Bodyfile => {
+                        for session in sessions {
+                            for line in SessionAsBodyfile::from(&session) {
+                                println!("{line}");
+                            }
+                        }
+                    }
                 }
-                csv_writer.flush()?;
                 Ok(())
             }
             _ => unreachable!(),
         }
     }
+
+    pub fn display_session_pstree(&self) -> anyhow::Result<()> {
+        match &self.command {
+            Command::SessionPsTree {
+                evtx_files_dirs,
+                include_anonymous,
+                rules,
+                session_id,
+                format,
+                language,
+            } => display_session_pstree(
+                evtx_files_dirs,
+                *include_anonymous,
+                rules.as_deref(),
+                session_id.as_ref(),
+                format,
+                language.labels(),
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn display_logons(&self) -> anyhow::Result<()> {
+        match &self.command {
+            Command::Logons {
+                evtx_files,
+                window,
+                brute_force_threshold,
+                spray_threshold,
+                statistics,
+                format,
+            } => display_logons(LogonsOptions {
+                evtx_files,
+                window: Duration::seconds((*window).into()),
+                brute_force_threshold: *brute_force_threshold,
+                spray_threshold: *spray_threshold,
+                format,
+                statistics: *statistics,
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn display_persistence(&self) -> anyhow::Result<()> {
+        match &self.command {
+            Command::Persistence {
+                evtx_files,
+                suspicious_only,
+                format,
+            } => display_persistence(evtx_files, *suspicious_only, format),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn display_scriptblocks(&self) -> anyhow::Result<()> {
+        match &self.command {
+            Command::ScriptBlocks {
+                evtx_files,
+                output_dir,
+                encoded_commands,
+                format,
+            } => display_scriptblocks(ScriptBlocksOptions {
+                evtx_files,
+                output_dir: output_dir.as_deref(),
+                encoded_commands: *encoded_commands,
+                format,
+            }),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn display_accounts(&self) -> anyhow::Result<()> {
+        match &self.command {
+            Command::Accounts {
+                evtx_files,
+                privileged_only,
+                format,
+            } => display_accounts(evtx_files, *privileged_only, format),
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn display_lateral(&self) -> anyhow::Result<()> {
+        match &self.command {
+            Command::Lateral {
+                evtx_files_dirs,
+                format,
+            } => display_lateral(evtx_files_dirs, format),
+            _ => unreachable!(),
+        }
+    }
 }
 
 impl HasVerboseFlag for Cli {
@@ -124,3 +518,4 @@ impl HasVerboseFlag for Cli {
         self.verbose.log_level_filter()
     }
 }
+
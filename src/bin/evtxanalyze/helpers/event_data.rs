@@ -0,0 +1,71 @@
+use evtx::SerializedEvtxRecord;
+use serde_json::Value;
+
+/// the `EventData` of a record. Windows writes `-` into fields which have
+/// no value, so such fields are handled like empty or missing fields.
+pub(crate) struct EventData<'a>(&'a Value);
+
+impl<'a> EventData<'a> {
+    pub(crate) fn of(record: &'a SerializedEvtxRecord<Value>) -> Self {
+        Self(&record.data["Event"]["EventData"])
+    }
+
+    /// the raw value of the field `name`
+    pub(crate) fn value(&self, name: &str) -> &'a Value {
+        &self.0[name]
+    }
+
+    /// the trimmed string value of the field `name`, if it has one
+    pub(crate) fn field(&self, name: &str) -> Option<String> {
+        match self.0[name].as_str().map(str::trim) {
+            None | Some("") | Some("-") => None,
+            Some(value) => Some(value.to_owned()),
+        }
+    }
+
+    /// the account name stored in the fields `domain` and `user`, which
+    /// contains the domain only if it is known
+    pub(crate) fn account(&self, domain: &str, user: &str) -> Option<String> {
+        match (self.field(domain), self.field(user)) {
+            (Some(domain), Some(user)) => Some(format!("{domain}\\{user}")),
+            (None, user) => user,
+            (_, None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::EventData;
+    use crate::helpers::record;
+
+    #[test]
+    fn missing_values() {
+        let record = record(
+            "Microsoft-Windows-Security-Auditing",
+            4624,
+            "server",
+            json!({
+                "SubjectUserName": " alice ",
+                "SubjectDomainName": "-",
+                "TargetUserName": "bob",
+                "TargetDomainName": "CONTOSO",
+                "IpAddress": "",
+            }),
+        );
+        let event_data = EventData::of(&record);
+        assert_eq!(event_data.field("IpAddress"), None);
+        assert_eq!(event_data.field("WorkstationName"), None);
+        assert_eq!(
+            event_data.account("SubjectDomainName", "SubjectUserName"),
+            Some("alice".to_owned())
+        );
+        assert_eq!(
+            event_data.account("TargetDomainName", "TargetUserName"),
+            Some("CONTOSO\\bob".to_owned())
+        );
+        assert_eq!(event_data.account("TargetDomainName", "MemberName"), None);
+    }
+}
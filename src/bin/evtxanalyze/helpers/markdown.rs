@@ -0,0 +1,30 @@
+/// formats `cells` as a row of a Markdown table. Pipes and line breaks
+/// inside of a cell would end the cell or the row, so they are escaped.
+pub(crate) fn markdown_row(cells: &[String]) -> String {
+    let cells: Vec<_> = cells
+        .iter()
+        .map(|c| {
+            c.replace('|', "\\|")
+                .replace("\r\n", "<br>")
+                .replace('\n', "<br>")
+        })
+        .collect();
+    format!("| {} |", cells.join(" | "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::markdown_row;
+
+    #[test]
+    fn escape_markdown_cells() {
+        assert_eq!(
+            markdown_row(&["a|b".to_owned(), String::new(), "c".to_owned()]),
+            r"| a\|b |  | c |"
+        );
+        assert_eq!(
+            markdown_row(&["line 1\r\nline 2\nline 3".to_owned()]),
+            "| line 1<br>line 2<br>line 3 |"
+        );
+    }
+}
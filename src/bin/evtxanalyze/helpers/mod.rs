@@ -0,0 +1,8 @@
+mod event_data;
+mod markdown;
+mod powershell;
+mod records;
+pub(crate) use event_data::*;
+pub(crate) use markdown::*;
+pub(crate) use powershell::*;
+pub(crate) use records::*;
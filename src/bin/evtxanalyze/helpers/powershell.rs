@@ -0,0 +1,23 @@
+/// PowerShell accepts every unique prefix of `-EncodedCommand`, as well as `-e` and `-ec`
+pub(crate) fn is_encoded_command_parameter(token: &str) -> bool {
+    match token.strip_prefix(['-', '/']) {
+        Some("ec") => true,
+        Some(param) => !param.is_empty() && "encodedcommand".starts_with(param),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_encoded_command_parameter;
+
+    #[test]
+    fn encoded_command_parameters() {
+        for param in ["-e", "-ec", "-enc", "/encodedcommand", "-EncodedCommand"] {
+            assert!(is_encoded_command_parameter(&param.to_lowercase()));
+        }
+        for param in ["-", "-ex", "-executionpolicy", "-encodedcommands", "enc"] {
+            assert!(!is_encoded_command_parameter(param));
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::path::Path;
+
+#[cfg(test)]
+use chrono::Utc;
+use evtx::{EvtxParser, SerializedEvtxRecord};
+use serde_json::Value;
+
+/// calls `handle_record` for every record of `evtx_file`. Records which
+/// cannot be read are reported and skipped.
+pub(crate) fn for_each_record<F>(evtx_file: &Path, mut handle_record: F) -> anyhow::Result<()>
+where
+    F: FnMut(&SerializedEvtxRecord<Value>),
+{
+    let mut parser = EvtxParser::from_path(evtx_file)?;
+    for record in parser.records_json_value() {
+        match record {
+            Err(why) => log::warn!(
+                "error while reading an event of {}: {why}",
+                evtx_file.display()
+            ),
+            Ok(record) => handle_record(&record),
+        }
+    }
+    Ok(())
+}
+
+/// creates a record of `provider`, as it would have been read by
+/// [`for_each_record`]
+#[cfg(test)]
+pub(crate) fn record(
+    provider: &str,
+    event_id: u64,
+    computer: &str,
+    event_data: Value,
+) -> SerializedEvtxRecord<Value> {
+    SerializedEvtxRecord {
+        event_record_id: 1,
+        timestamp: Utc::now(),
+        data: serde_json::json!({
+            "Event": {
+                "System": {
+                    "Provider": {"#attributes": {"Name": provider}},
+                    "EventID": event_id,
+                    "Computer": computer,
+                },
+                "EventData": event_data,
+            }
+        }),
+    }
+}
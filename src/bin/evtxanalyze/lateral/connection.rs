@@ -0,0 +1,193 @@
+use std::{fmt::Display, net::IpAddr};
+
+use chrono::{DateTime, Utc};
+use dfirtk_eventdata::{EventId, EventProvider, LogonType};
+use evtx::SerializedEvtxRecord;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::helpers::EventData;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionKind {
+    /// network logon (Security 4624 with logon type 3)
+    NetworkLogon,
+
+    /// RDP logon (Security 4624 with logon type 10)
+    RdpLogon,
+
+    /// outgoing RDP connection (RDPClient 1024 and 1102)
+    RdpClient,
+
+    /// logon using explicit credentials (Security 4648)
+    ExplicitCredentials,
+
+    /// access to a network share (Security 5140 and 5145)
+    ShareAccess,
+}
+
+impl Display for ConnectionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            Self::NetworkLogon => "network logon",
+            Self::RdpLogon => "rdp logon",
+            Self::RdpClient => "rdp client",
+            Self::ExplicitCredentials => "explicit credentials",
+            Self::ShareAccess => "share access",
+        };
+        write!(f, "{kind}")
+    }
+}
+
+/// a connection from one host to another one. Hosts are identified by
+/// their host name without DNS suffix, or by their IP address if the
+/// host name is unknown.
+#[derive(Clone, Debug)]
+pub struct Connection {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ConnectionKind,
+    pub source: String,
+    pub destination: String,
+    pub user: Option<String>,
+    pub share: Option<String>,
+
+    /// the IP address of the source, if the source is identified by its
+    /// host name. This is used to find the names of hosts which are only
+    /// known by their IP address in other events.
+    pub source_address: Option<String>,
+}
+
+impl Connection {
+    pub fn try_from(record: &SerializedEvtxRecord<Value>) -> Option<Self> {
+        let provider = EventProvider::name_of(record)?;
+        let event_id = EventId::try_from(record).ok()?.value();
+        let system = &record.data["Event"]["System"];
+        let event_data = EventData::of(record);
+        let computer = system["Computer"].as_str().map(normalize_host)?;
+
+        let mut source_address = None;
+        let (kind, source, destination, user, share) = match (provider, event_id) {
+            ("Microsoft-Windows-Security-Auditing", 4624) => {
+                let kind = match LogonType::from_value(event_data.value("LogonType"))? {
+                    LogonType::Network => ConnectionKind::NetworkLogon,
+                    LogonType::RemoteInteractive => ConnectionKind::RdpLogon,
+                    _ => return None,
+                };
+                let address = event_data.field("IpAddress");
+                let source = match event_data.field("WorkstationName") {
+                    Some(workstation) => {
+                        source_address = address.map(|addr| normalize_host(&addr));
+                        workstation
+                    }
+                    None => address?,
+                };
+                (
+                    kind,
+                    source,
+                    computer,
+                    event_data.account("TargetDomainName", "TargetUserName"),
+                    None,
+                )
+            }
+            ("Microsoft-Windows-Security-Auditing", 4648) => (
+                ConnectionKind::ExplicitCredentials,
+                computer,
+                event_data.field("TargetServerName")?,
+                event_data.account("TargetDomainName", "TargetUserName"),
+                None,
+            ),
+            ("Microsoft-Windows-Security-Auditing", 5140 | 5145) => (
+                ConnectionKind::ShareAccess,
+                event_data.field("IpAddress")?,
+                computer,
+                event_data.account("SubjectDomainName", "SubjectUserName"),
+                event_data.field("ShareName"),
+            ),
+            ("Microsoft-Windows-TerminalServices-ClientActiveXCore", 1024 | 1102) => (
+                ConnectionKind::RdpClient,
+                computer,
+                event_data.field("Value")?,
+                system["Security"]["#attributes"]["UserID"]
+                    .as_str()
+                    .map(|sid| sid.to_owned()),
+                None,
+            ),
+            _ => return None,
+        };
+
+        let source = normalize_host(&source);
+        let destination = normalize_host(&destination);
+        if is_local(&source) || is_local(&destination) || source == destination {
+            return None;
+        }
+        let source_address = source_address.filter(|addr| is_ip_address(addr) && !is_local(addr));
+
+        Some(Self {
+            timestamp: record.timestamp,
+            kind,
+            source,
+            destination,
+            user,
+            share,
+            source_address,
+        })
+    }
+}
+
+/// converts host names to lowercase and removes their DNS suffix, so that
+/// the FQDN in `Computer` and NetBIOS names like in `WorkstationName` match.
+/// The IPv6 prefix of IPv4 mapped addresses is removed as well.
+pub(crate) fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_start_matches('\\').to_lowercase();
+    let host = host.strip_prefix("::ffff:").unwrap_or(&host);
+    if is_ip_address(host) {
+        host.to_owned()
+    } else {
+        host.split('.').next().unwrap_or(host).to_owned()
+    }
+}
+
+fn is_ip_address(host: &str) -> bool {
+    host.parse::<IpAddr>().is_ok()
+}
+
+fn is_local(host: &str) -> bool {
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{normalize_host, Connection, ConnectionKind};
+    use crate::helpers::record;
+
+    #[test]
+    fn normalize_hosts() {
+        assert_eq!(normalize_host("SRV01.contoso.local"), "srv01");
+        assert_eq!(normalize_host("\\\\SRV01"), "srv01");
+        assert_eq!(normalize_host("::ffff:10.0.0.5"), "10.0.0.5");
+        assert_eq!(normalize_host("fe80::1"), "fe80::1");
+    }
+
+    #[test]
+    fn rdp_logon() {
+        let record = record(
+            "Microsoft-Windows-Security-Auditing",
+            4624,
+            "SRV01.contoso.local",
+            json!({
+                "TargetUserName": "alice",
+                "TargetDomainName": "CONTOSO",
+                "LogonType": 10,
+                "IpAddress": "::ffff:10.0.0.5",
+            }),
+        );
+        let connection = Connection::try_from(&record).unwrap();
+        assert_eq!(connection.kind, ConnectionKind::RdpLogon);
+        assert_eq!(connection.source, "10.0.0.5");
+        assert_eq!(connection.destination, "srv01");
+        assert_eq!(connection.user.as_deref(), Some("CONTOSO\\alice"));
+    }
+}
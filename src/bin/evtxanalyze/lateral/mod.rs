@@ -0,0 +1,90 @@
+mod connection;
+mod movement_graph;
+pub use connection::*;
+pub use movement_graph::*;
+
+use std::{io::stdout, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::helpers::for_each_record;
+use crate::sessions::find_evtx_files_of_channels;
+
+/// channels which contain connections between hosts
+const CHANNELS: &[&str] = &[
+    "Security",
+    "Microsoft-Windows-TerminalServices-RDPClient/Operational",
+];
+
+#[derive(ValueEnum, Clone)]
+pub enum LateralFormat {
+    Csv,
+    Dot,
+
+    #[clap(name = "graphml")]
+    GraphML,
+}
+
+#[derive(Serialize)]
+struct EdgeAsCsv<'a> {
+    source: &'a str,
+    destination: &'a str,
+    user: Option<&'a str>,
+    kind: ConnectionKind,
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
+    count: usize,
+    shares: String,
+}
+
+impl<'a> From<&'a MovementEdge> for EdgeAsCsv<'a> {
+    fn from(edge: &'a MovementEdge) -> Self {
+        let shares: Vec<_> = edge.shares.iter().map(|s| &s[..]).collect();
+        Self {
+            source: &edge.source,
+            destination: &edge.destination,
+            user: edge.user.as_deref(),
+            kind: edge.kind,
+            first: edge.first,
+            last: edge.last,
+            count: edge.count,
+            shares: shares.join(", "),
+        }
+    }
+}
+
+pub(crate) fn display_lateral(
+    evtx_files_dirs: &[PathBuf],
+    format: &LateralFormat,
+) -> anyhow::Result<()> {
+    let mut graph = MovementGraph::default();
+    for evtx_file in find_evtx_files_of_channels(evtx_files_dirs, CHANNELS)? {
+        log::info!("reading connections from {}", evtx_file.display());
+        for_each_record(&evtx_file, |record| {
+            if let Some(connection) = Connection::try_from(record) {
+                graph.add(connection);
+            }
+        })?;
+    }
+    graph.resolve_addresses();
+    log::info!(
+        "found {} connections between {} hosts",
+        graph.edges().count(),
+        graph.hosts().len()
+    );
+
+    match format {
+        LateralFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(stdout());
+            for edge in graph.edges() {
+                csv_writer.serialize(EdgeAsCsv::from(edge))?;
+            }
+            csv_writer.flush()?;
+        }
+        LateralFormat::Dot => print!("{}", graph.to_dot()),
+        LateralFormat::GraphML => print!("{}", graph.to_graphml()),
+    }
+    Ok(())
+}
@@ -0,0 +1,313 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::{Connection, ConnectionKind};
+
+/// all connections of the same kind and user between two hosts
+#[derive(Serialize, Clone, Debug)]
+pub struct MovementEdge {
+    pub source: String,
+    pub destination: String,
+    pub user: Option<String>,
+    pub kind: ConnectionKind,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+    pub count: usize,
+    pub shares: BTreeSet<String>,
+}
+
+type EdgeKey = (String, String, Option<String>, ConnectionKind);
+
+#[derive(Default)]
+pub struct MovementGraph {
+    edges: BTreeMap<EdgeKey, MovementEdge>,
+
+    /// the host names which have been seen together with an IP address
+    host_names: HashMap<String, BTreeSet<String>>,
+}
+
+impl MovementGraph {
+    pub fn add(&mut self, connection: Connection) {
+        if let Some(address) = &connection.source_address {
+            self.host_names
+                .entry(address.clone())
+                .or_default()
+                .insert(connection.source.clone());
+        }
+        self.add_edge(MovementEdge {
+            source: connection.source,
+            destination: connection.destination,
+            user: connection.user,
+            kind: connection.kind,
+            first: connection.timestamp,
+            last: connection.timestamp,
+            count: 1,
+            shares: connection.share.into_iter().collect(),
+        });
+    }
+
+    fn add_edge(&mut self, edge: MovementEdge) {
+        let key = (
+            edge.source.clone(),
+            edge.destination.clone(),
+            edge.user.clone(),
+            edge.kind,
+        );
+        match self.edges.get_mut(&key) {
+            None => {
+                self.edges.insert(key, edge);
+            }
+            Some(existing) => {
+                existing.first = existing.first.min(edge.first);
+                existing.last = existing.last.max(edge.last);
+                existing.count += edge.count;
+                existing.shares.extend(edge.shares);
+            }
+        }
+    }
+
+    /// replaces IP addresses by host names, if the address has been seen
+    /// together with exactly one host name in any of the connections.
+    /// This must be called after all connections have been added, because
+    /// the name of a host might be found in a different file than the
+    /// connections using its address.
+    pub fn resolve_addresses(&mut self) {
+        let mut resolved = HashMap::new();
+        for (address, names) in self.host_names.iter() {
+            if names.len() == 1 {
+                resolved.insert(address.clone(), names.iter().next().unwrap().clone());
+            } else {
+                log::warn!("{address} has been used by multiple hosts: {names:?}");
+            }
+        }
+        let resolve = |host: String| resolved.get(&host).cloned().unwrap_or(host);
+
+        let edges: Vec<_> = std::mem::take(&mut self.edges)
+            .into_values()
+            .map(|edge| MovementEdge {
+                source: resolve(edge.source),
+                destination: resolve(edge.destination),
+                ..edge
+            })
+            .collect();
+        for edge in edges {
+            if edge.source != edge.destination {
+                self.add_edge(edge);
+            }
+        }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &MovementEdge> {
+        self.edges.values()
+    }
+
+    pub fn hosts(&self) -> BTreeSet<&str> {
+        self.edges
+            .values()
+            .flat_map(|e| [&e.source[..], &e.destination[..]])
+            .collect()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let hosts: BTreeMap<_, _> = self.hosts().into_iter().zip(0..).collect();
+        let mut dot = String::from("digraph {\nrankdir=\"LR\";\n");
+        for (host, id) in hosts.iter() {
+            dot.push_str(&format!(
+                "h{id} [label=\"{}\", shape=\"box\"];\n",
+                escape_dot(host)
+            ));
+        }
+        for edge in self.edges() {
+            dot.push_str(&format!(
+                "h{} -> h{} [label=\"{}\"];\n",
+                hosts[&edge.source[..]],
+                hosts[&edge.destination[..]],
+                escape_dot(&edge_label(edge))
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_graphml(&self) -> String {
+        let hosts: BTreeMap<_, _> = self.hosts().into_iter().zip(0..).collect();
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n",
+            "  <key id=\"user\" for=\"edge\" attr.name=\"user\" attr.type=\"string\"/>\n",
+            "  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+            "  <key id=\"first\" for=\"edge\" attr.name=\"first\" attr.type=\"string\"/>\n",
+            "  <key id=\"last\" for=\"edge\" attr.name=\"last\" attr.type=\"string\"/>\n",
+            "  <key id=\"count\" for=\"edge\" attr.name=\"count\" attr.type=\"int\"/>\n",
+            "  <key id=\"shares\" for=\"edge\" attr.name=\"shares\" attr.type=\"string\"/>\n",
+            "  <graph id=\"lateral_movement\" edgedefault=\"directed\">\n",
+        ));
+        for (host, id) in hosts.iter() {
+            xml.push_str(&format!(
+                "    <node id=\"h{id}\"><data key=\"name\">{}</data></node>\n",
+                escape_xml(host)
+            ));
+        }
+        for (idx, edge) in self.edges().enumerate() {
+            xml.push_str(&format!(
+                "    <edge id=\"e{idx}\" source=\"h{}\" target=\"h{}\">",
+                hosts[&edge.source[..]],
+                hosts[&edge.destination[..]]
+            ));
+            if let Some(user) = &edge.user {
+                xml.push_str(&format!("<data key=\"user\">{}</data>", escape_xml(user)));
+            }
+            xml.push_str(&format!(
+                "<data key=\"kind\">{}</data><data key=\"first\">{}</data><data key=\"last\">{}</data><data key=\"count\">{}</data>",
+                edge.kind,
+                edge.first.to_rfc3339(),
+                edge.last.to_rfc3339(),
+                edge.count
+            ));
+            if !edge.shares.is_empty() {
+                let shares: Vec<_> = edge.shares.iter().map(|s| &s[..]).collect();
+                xml.push_str(&format!(
+                    "<data key=\"shares\">{}</data>",
+                    escape_xml(&shares.join(", "))
+                ));
+            }
+            xml.push_str("</edge>\n");
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+fn edge_label(edge: &MovementEdge) -> String {
+    let mut label = match &edge.user {
+        Some(user) => format!("{user} ({})", edge.kind),
+        None => edge.kind.to_string(),
+    };
+    label.push_str(&format!(
+        "\n{} - {}, {}x",
+        edge.first.format("%FT%T"),
+        edge.last.format("%FT%T"),
+        edge.count
+    ));
+    label
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use serde_json::json;
+
+    use super::MovementGraph;
+    use crate::{
+        helpers::record,
+        lateral::{Connection, ConnectionKind},
+    };
+
+    const SECURITY: &str = "Microsoft-Windows-Security-Auditing";
+
+    fn connection(seconds: i64, source: &str, destination: &str) -> Connection {
+        Connection {
+            timestamp: Utc.with_ymd_and_hms(2023, 11, 14, 10, 0, 0).unwrap()
+                + Duration::seconds(seconds),
+            kind: ConnectionKind::ShareAccess,
+            source: source.to_owned(),
+            destination: destination.to_owned(),
+            user: Some("CONTOSO\\alice".to_owned()),
+            share: Some("\\\\*\\ADMIN$".to_owned()),
+            source_address: None,
+        }
+    }
+
+    #[test]
+    fn merge_edges() {
+        let mut graph = MovementGraph::default();
+        graph.add(connection(10, "10.0.0.5", "srv01"));
+        graph.add(connection(0, "10.0.0.5", "srv01"));
+        graph.add(connection(5, "srv01", "srv02"));
+
+        let edges: Vec<_> = graph.edges().collect();
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].count, 2);
+        assert_eq!(edges[0].last - edges[0].first, Duration::seconds(10));
+        assert_eq!(graph.hosts().len(), 3);
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("h0 -> h1"));
+        assert!(dot.contains("h1 -> h2"));
+        assert!(graph.to_graphml().contains("\\\\*\\ADMIN$"));
+    }
+
+    #[test]
+    fn chain_hosts_of_different_logs() {
+        let records = [
+            // Security.evtx of WS01: explicit credentials used for SRV01
+            record(
+                SECURITY,
+                4648,
+                "WS01.contoso.local",
+                json!({"TargetUserName": "alice", "TargetDomainName": "CONTOSO", "TargetServerName": "SRV01"}),
+            ),
+            // Security.evtx of SRV01: RDP logon from WS01, and a share accessed
+            // from an address which is known only from another logon
+            record(
+                SECURITY,
+                4624,
+                "SRV01.contoso.local",
+                json!({"TargetUserName": "alice", "LogonType": 10, "IpAddress": "10.0.0.5", "WorkstationName": "WS01"}),
+            ),
+            record(
+                SECURITY,
+                5140,
+                "SRV01.contoso.local",
+                json!({"SubjectUserName": "alice", "IpAddress": "10.0.0.5", "ShareName": "\\\\*\\C$"}),
+            ),
+            // Security.evtx of DC01: network logon from SRV01
+            record(
+                SECURITY,
+                4624,
+                "DC01.contoso.local",
+                json!({"TargetUserName": "alice", "LogonType": 3, "IpAddress": "::ffff:10.0.0.7", "WorkstationName": "SRV01"}),
+            ),
+        ];
+
+        let mut graph = MovementGraph::default();
+        for record in records.iter() {
+            graph.add(Connection::try_from(record).unwrap());
+        }
+        graph.resolve_addresses();
+
+        assert_eq!(
+            graph.hosts().into_iter().collect::<Vec<_>>(),
+            vec!["dc01", "srv01", "ws01"]
+        );
+        let edges: Vec<_> = graph
+            .edges()
+            .map(|e| (&e.source[..], &e.destination[..], e.kind))
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("srv01", "dc01", ConnectionKind::NetworkLogon),
+                ("ws01", "srv01", ConnectionKind::ExplicitCredentials),
+                ("ws01", "srv01", ConnectionKind::RdpLogon),
+                ("ws01", "srv01", ConnectionKind::ShareAccess),
+            ]
+        );
+    }
+}
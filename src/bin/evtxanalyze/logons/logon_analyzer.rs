@@ -0,0 +1,422 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{DateTime, Duration, Utc};
+use dfirtk_eventdata::LogonType;
+use serde::Serialize;
+
+use super::{Account, LogonAttempt};
+
+/// average number of failures per account, up to which many failed accounts
+/// are considered as password spray
+const MAX_SPRAY_ATTEMPTS_PER_ACCOUNT: usize = 3;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum AttackPattern {
+    /// many failed logons to one single account
+    BruteForce,
+
+    /// few failed logons to many accounts
+    PasswordSpray,
+}
+
+/// a successful logon after a burst of failed logons
+#[derive(Serialize, Clone, Debug)]
+pub struct SuccessfulLogon {
+    pub timestamp: DateTime<Utc>,
+    pub account: String,
+    pub logon_type: Option<LogonType>,
+}
+
+/// a burst of failed logons from one single source
+#[derive(Serialize, Clone, Debug)]
+pub struct LogonFinding {
+    pub pattern: AttackPattern,
+    pub source: String,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+    pub failures: usize,
+    pub accounts: Vec<String>,
+    pub workstations: Vec<String>,
+    pub followed_by_success: Option<SuccessfulLogon>,
+}
+
+/// number of logons per source address, workstation and target account
+/// in one time window
+#[derive(Serialize, Clone, Debug)]
+pub struct LogonStatistics {
+    pub source_address: Option<String>,
+    pub workstation: Option<String>,
+    pub account: String,
+    pub first: DateTime<Utc>,
+    pub last: DateTime<Utc>,
+    pub failures: usize,
+    pub successes: usize,
+}
+
+pub struct LogonAnalyzer {
+    window: Duration,
+    brute_force_threshold: usize,
+    spray_threshold: usize,
+}
+
+impl LogonAnalyzer {
+    pub fn new(window: Duration, brute_force_threshold: usize, spray_threshold: usize) -> Self {
+        Self {
+            window,
+            brute_force_threshold,
+            spray_threshold,
+        }
+    }
+
+    /// aggregates the logon attempts per source address, workstation and
+    /// target account over time windows. Every window starts with the first
+    /// attempt which is not part of the previous window.
+    pub fn statistics(&self, attempts: &[LogonAttempt]) -> Vec<LogonStatistics> {
+        let mut groups: BTreeMap<_, Vec<(Account, Vec<&LogonAttempt>)>> = BTreeMap::new();
+        for attempt in attempts {
+            let key = (attempt.source_address.clone(), attempt.workstation.clone());
+            let accounts = groups.entry(key).or_default();
+            let account = attempt.account();
+            match accounts.iter_mut().find(|(a, _)| a.matches(&account)) {
+                Some((a, attempts)) => {
+                    a.merge(&account);
+                    attempts.push(attempt);
+                }
+                None => accounts.push((account, vec![attempt])),
+            }
+        }
+
+        let mut statistics = Vec::new();
+        for ((source_address, workstation), accounts) in groups {
+            for (account, mut attempts) in accounts {
+                attempts.sort_by_key(|a| a.timestamp);
+                let mut begin = 0;
+                while begin < attempts.len() {
+                    let end = self.window_end(&attempts, begin);
+                    let window = &attempts[begin..end];
+                    let successes = window.iter().filter(|a| a.success).count();
+                    statistics.push(LogonStatistics {
+                        source_address: source_address.clone(),
+                        workstation: workstation.clone(),
+                        account: account.to_string(),
+                        first: window.first().unwrap().timestamp,
+                        last: window.last().unwrap().timestamp,
+                        failures: window.len() - successes,
+                        successes,
+                    });
+                    begin = end;
+                }
+            }
+        }
+        statistics.sort_by(|a, b| {
+            (&a.source_address, &a.workstation, &a.account, a.first).cmp(&(
+                &b.source_address,
+                &b.workstation,
+                &b.account,
+                b.first,
+            ))
+        });
+        statistics
+    }
+
+    /// searches for bursts of failed logons per source, using a sliding time window
+    pub fn find_attacks(&self, attempts: &[LogonAttempt]) -> Vec<LogonFinding> {
+        let mut sources: HashMap<&str, Vec<&LogonAttempt>> = HashMap::new();
+        for attempt in attempts {
+            sources.entry(attempt.source()).or_default().push(attempt);
+        }
+
+        let mut findings = Vec::new();
+        for (source, mut attempts) in sources {
+            attempts.sort_by_key(|a| a.timestamp);
+            let (failures, successes): (Vec<_>, Vec<_>) =
+                attempts.into_iter().partition(|a| !a.success);
+
+            let mut begin = 0;
+            while begin < failures.len() {
+                let end = self.window_end(&failures, begin);
+                let pattern = match self.pattern_of(&failures[begin..end]) {
+                    None => {
+                        begin += 1;
+                        continue;
+                    }
+                    Some(pattern) => pattern,
+                };
+
+                // extend the burst as long as the following windows show the same pattern
+                let mut burst_end = end;
+                let mut next = begin + 1;
+                while next < burst_end {
+                    let end = self.window_end(&failures, next);
+                    if self.pattern_of(&failures[next..end]) == Some(pattern) {
+                        burst_end = burst_end.max(end);
+                    }
+                    next += 1;
+                }
+
+                findings.push(self.finding(
+                    source,
+                    pattern,
+                    &failures[begin..burst_end],
+                    &successes,
+                ));
+                begin = burst_end;
+            }
+        }
+
+        findings.sort_by_key(|f| f.first);
+        findings
+    }
+
+    /// returns the index of the first attempt which is not in the time window
+    /// starting at `begin`
+    fn window_end(&self, attempts: &[&LogonAttempt], begin: usize) -> usize {
+        let window_end = attempts[begin].timestamp + self.window;
+        begin
+            + attempts[begin..]
+                .iter()
+                .take_while(|f| f.timestamp <= window_end)
+                .count()
+    }
+
+    fn pattern_of(&self, failures: &[&LogonAttempt]) -> Option<AttackPattern> {
+        let mut accounts = AccountCounts::default();
+        for failure in failures {
+            accounts.add(failure.account());
+        }
+
+        let max_failures_per_account = accounts.max_count();
+        if max_failures_per_account >= self.brute_force_threshold {
+            Some(AttackPattern::BruteForce)
+        } else if accounts.len() >= self.spray_threshold
+            && failures.len() <= accounts.len() * MAX_SPRAY_ATTEMPTS_PER_ACCOUNT
+        {
+            Some(AttackPattern::PasswordSpray)
+        } else {
+            None
+        }
+    }
+
+    fn finding(
+        &self,
+        source: &str,
+        pattern: AttackPattern,
+        failures: &[&LogonAttempt],
+        successes: &[&LogonAttempt],
+    ) -> LogonFinding {
+        let first = failures.first().unwrap().timestamp;
+        let last = failures.last().unwrap().timestamp;
+        let mut accounts = AccountCounts::default();
+        for failure in failures {
+            accounts.add(failure.account());
+        }
+        let workstations: HashSet<_> = failures
+            .iter()
+            .filter_map(|f| f.workstation.clone())
+            .collect();
+
+        // a success of an attacked account, during the burst or shortly after it
+        let followed_by_success = successes
+            .iter()
+            .find(|s| {
+                s.timestamp >= first
+                    && s.timestamp <= last + self.window
+                    && accounts.contains(&s.account())
+            })
+            .map(|s| SuccessfulLogon {
+                timestamp: s.timestamp,
+                account: s.account().to_string(),
+                logon_type: s.logon_type,
+            });
+
+        let mut accounts = accounts.names();
+        accounts.sort();
+        let mut workstations: Vec<_> = workstations.into_iter().collect();
+        workstations.sort();
+
+        LogonFinding {
+            pattern,
+            source: source.to_owned(),
+            first,
+            last,
+            failures: failures.len(),
+            accounts,
+            workstations,
+            followed_by_success,
+        }
+    }
+}
+
+/// accounts and their number of attempts. Attempts with and without domain
+/// name are counted for the same account.
+#[derive(Default)]
+struct AccountCounts(Vec<(Account, usize)>);
+
+impl AccountCounts {
+    fn add(&mut self, account: Account) {
+        match self.0.iter_mut().find(|(a, _)| a.matches(&account)) {
+            Some((a, count)) => {
+                a.merge(&account);
+                *count += 1;
+            }
+            None => self.0.push((account, 1)),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn max_count(&self) -> usize {
+        self.0
+            .iter()
+            .map(|(_, count)| *count)
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn contains(&self, account: &Account) -> bool {
+        self.0.iter().any(|(a, _)| a.matches(account))
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.0.iter().map(|(a, _)| a.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use dfirtk_eventdata::LogonType;
+
+    use super::{AttackPattern, LogonAnalyzer};
+    use crate::logons::LogonAttempt;
+
+    fn attempt(seconds: i64, user: &str, success: bool) -> LogonAttempt {
+        LogonAttempt {
+            timestamp: Utc.with_ymd_and_hms(2023, 11, 14, 10, 0, 0).unwrap()
+                + Duration::seconds(seconds),
+            event_id: if success { 4624 } else { 4625 },
+            success,
+            target_user: user.to_owned(),
+            target_domain: Some("CONTOSO".to_owned()),
+            source_address: Some("10.0.0.5".to_owned()),
+            workstation: None,
+            logon_type: Some(LogonType::Network),
+            status: None,
+        }
+    }
+
+    fn attempt_without_domain(seconds: i64, user: &str) -> LogonAttempt {
+        LogonAttempt {
+            event_id: 4776,
+            target_domain: None,
+            logon_type: None,
+            ..attempt(seconds, user, false)
+        }
+    }
+
+    fn analyzer() -> LogonAnalyzer {
+        LogonAnalyzer::new(Duration::seconds(60), 10, 5)
+    }
+
+    #[test]
+    fn brute_force() {
+        let mut attempts: Vec<_> = (0..30).map(|i| attempt(i * 5, "alice", false)).collect();
+        attempts.push(attempt(200, "alice", true));
+
+        let findings = analyzer().find_attacks(&attempts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].pattern, AttackPattern::BruteForce);
+        assert_eq!(findings[0].failures, 30);
+        assert_eq!(findings[0].accounts, vec!["contoso\\alice"]);
+        assert!(findings[0].followed_by_success.is_some());
+    }
+
+    #[test]
+    fn password_spray() {
+        let users = ["alice", "bob", "carol", "dave", "eve", "frank"];
+        let attempts: Vec<_> = users
+            .iter()
+            .enumerate()
+            .map(|(i, user)| attempt(i as i64, user, false))
+            .collect();
+
+        let findings = analyzer().find_attacks(&attempts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].pattern, AttackPattern::PasswordSpray);
+        assert_eq!(findings[0].accounts.len(), 6);
+        assert!(findings[0].followed_by_success.is_none());
+    }
+
+    #[test]
+    fn no_attack() {
+        let attempts: Vec<_> = (0..5).map(|i| attempt(i * 600, "alice", false)).collect();
+        assert!(analyzer().find_attacks(&attempts).is_empty());
+    }
+
+    #[test]
+    fn accounts_without_domain() {
+        let mut attempts: Vec<_> = (0..5)
+            .map(|i| attempt_without_domain(i * 5, "Alice"))
+            .collect();
+        attempts.extend((5..10).map(|i| LogonAttempt {
+            target_domain: Some("contoso.local".to_owned()),
+            ..attempt(i * 5, "alice", false)
+        }));
+        attempts.push(attempt(100, "ALICE", true));
+
+        let findings = analyzer().find_attacks(&attempts);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].pattern, AttackPattern::BruteForce);
+        assert_eq!(findings[0].accounts, vec!["contoso\\alice"]);
+        assert_eq!(
+            findings[0].followed_by_success.as_ref().unwrap().account,
+            "contoso\\alice"
+        );
+    }
+
+    #[test]
+    fn different_domains() {
+        let attempts: Vec<_> = (0..10)
+            .map(|i| LogonAttempt {
+                target_domain: Some(if i % 2 == 0 { "CONTOSO" } else { "FABRIKAM" }.to_owned()),
+                ..attempt(i, "alice", false)
+            })
+            .collect();
+        assert!(analyzer().find_attacks(&attempts).is_empty());
+    }
+
+    #[test]
+    fn statistics_per_window() {
+        let attempts = vec![
+            attempt(0, "alice", false),
+            attempt_without_domain(10, "alice"),
+            attempt(20, "alice", true),
+            attempt(1000, "alice", false),
+            attempt(1010, "bob", false),
+        ];
+
+        let statistics: Vec<_> = analyzer()
+            .statistics(&attempts)
+            .into_iter()
+            .map(|s| {
+                (
+                    s.account,
+                    s.first - attempts[0].timestamp,
+                    s.failures,
+                    s.successes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            statistics,
+            vec![
+                ("contoso\\alice".to_owned(), Duration::seconds(0), 2, 1),
+                ("contoso\\alice".to_owned(), Duration::seconds(1000), 1, 0),
+                ("contoso\\bob".to_owned(), Duration::seconds(1010), 1, 0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use dfirtk_eventdata::{EventId, EventProvider, LogonType};
+use evtx::SerializedEvtxRecord;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::helpers::EventData;
+
+/// a successful or failed logon, found in Security 4624, 4625, 4771 or 4776
+#[derive(Serialize, Clone, Debug)]
+pub struct LogonAttempt {
+    pub timestamp: DateTime<Utc>,
+    pub event_id: u16,
+    pub success: bool,
+    pub target_user: String,
+    pub target_domain: Option<String>,
+    pub source_address: Option<String>,
+    pub workstation: Option<String>,
+    pub logon_type: Option<LogonType>,
+    pub status: Option<String>,
+}
+
+impl LogonAttempt {
+    pub fn try_from(record: &SerializedEvtxRecord<Value>) -> Option<Self> {
+        if EventProvider::name_of(record)? != "Microsoft-Windows-Security-Auditing" {
+            return None;
+        }
+        let event_id = EventId::try_from(record).ok()?.value();
+        let event_data = EventData::of(record);
+
+        let status = match event_id {
+            4625 => event_data
+                .field("SubStatus")
+                .filter(|s| s != "0x0")
+                .or_else(|| event_data.field("Status")),
+            4624 => None,
+            _ => event_data.field("Status"),
+        };
+
+        let success = match event_id {
+            4624 => true,
+            4625 | 4771 => false,
+            4776 => status.as_deref() == Some("0x0"),
+            _ => return None,
+        };
+
+        let workstation = match event_id {
+            4776 => event_data.field("Workstation"),
+            _ => event_data.field("WorkstationName"),
+        };
+
+        Some(Self {
+            timestamp: record.timestamp,
+            event_id,
+            success,
+            target_user: event_data.field("TargetUserName")?,
+            target_domain: event_data.field("TargetDomainName"),
+            source_address: event_data
+                .field("IpAddress")
+                .map(|addr| normalize_address(&addr)),
+            workstation,
+            logon_type: LogonType::from_value(event_data.value("LogonType")),
+            status,
+        })
+    }
+
+    /// the target account of this logon attempt
+    pub fn account(&self) -> Account {
+        Account::new(&self.target_user, self.target_domain.as_deref())
+    }
+
+    /// the source of this logon attempt, which is the source address or,
+    /// if it is unknown, the name of the workstation
+    pub fn source(&self) -> &str {
+        self.source_address
+            .as_deref()
+            .or(self.workstation.as_deref())
+            .unwrap_or("unknown")
+    }
+}
+
+/// the name of an account, which is compared case-insensitively. Some events
+/// (e.g. 4771 and 4776) do not contain the domain of the account, so the
+/// domain is compared only if it is known for both accounts.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Account {
+    user: String,
+    domain: Option<String>,
+}
+
+impl Account {
+    pub fn new(user: &str, domain: Option<&str>) -> Self {
+        Self {
+            user: user.to_lowercase(),
+            // use the NetBIOS name if the DNS name of the domain is given
+            domain: domain
+                .and_then(|domain| domain.split('.').next())
+                .filter(|domain| !domain.is_empty())
+                .map(|domain| domain.to_lowercase()),
+        }
+    }
+
+    /// returns `true` if both names might denote the same account
+    pub fn matches(&self, other: &Self) -> bool {
+        self.user == other.user
+            && match (&self.domain, &other.domain) {
+                (Some(domain), Some(other_domain)) => domain == other_domain,
+                _ => true,
+            }
+    }
+
+    /// adds the domain of `other` if the domain of this account is unknown
+    pub fn merge(&mut self, other: &Self) {
+        if self.domain.is_none() {
+            self.domain.clone_from(&other.domain);
+        }
+    }
+}
+
+impl Display for Account {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.domain {
+            Some(domain) => write!(f, "{domain}\\{}", self.user),
+            None => write!(f, "{}", self.user),
+        }
+    }
+}
+
+/// removes the IPv6 prefix of IPv4 mapped addresses, which is used by 4771
+fn normalize_address(addr: &str) -> String {
+    match addr.strip_prefix("::ffff:") {
+        Some(addr) => addr.to_owned(),
+        None => addr.to_owned(),
+    }
+}
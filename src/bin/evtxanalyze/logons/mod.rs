@@ -0,0 +1,154 @@
+mod logon_analyzer;
+mod logon_attempt;
+pub use logon_analyzer::*;
+pub use logon_attempt::*;
+
+use std::{io::stdout, path::PathBuf};
+
+use chrono::{DateTime, Duration, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::helpers::{for_each_record, markdown_row};
+
+#[derive(ValueEnum, Clone)]
+pub enum LogonsFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+#[derive(Serialize)]
+struct FindingAsCsv<'a> {
+    pattern: AttackPattern,
+    source: &'a str,
+    first: DateTime<Utc>,
+    last: DateTime<Utc>,
+    failures: usize,
+    accounts: String,
+    workstations: String,
+    success_timestamp: Option<DateTime<Utc>>,
+    success_account: Option<&'a str>,
+}
+
+impl<'a> From<&'a LogonFinding> for FindingAsCsv<'a> {
+    fn from(finding: &'a LogonFinding) -> Self {
+        Self {
+            pattern: finding.pattern,
+            source: &finding.source,
+            first: finding.first,
+            last: finding.last,
+            failures: finding.failures,
+            accounts: finding.accounts.join(", "),
+            workstations: finding.workstations.join(", "),
+            success_timestamp: finding.followed_by_success.as_ref().map(|s| s.timestamp),
+            success_account: finding.followed_by_success.as_ref().map(|s| &s.account[..]),
+        }
+    }
+}
+
+pub(crate) struct LogonsOptions<'a> {
+    pub(crate) evtx_files: &'a [PathBuf],
+    pub(crate) window: Duration,
+    pub(crate) brute_force_threshold: usize,
+    pub(crate) spray_threshold: usize,
+    pub(crate) format: &'a LogonsFormat,
+    pub(crate) statistics: bool,
+}
+
+pub(crate) fn display_logons(options: LogonsOptions) -> anyhow::Result<()> {
+    let mut attempts = Vec::new();
+    for evtx_file in options.evtx_files {
+        for_each_record(evtx_file, |record| {
+            if let Some(attempt) = LogonAttempt::try_from(record) {
+                attempts.push(attempt);
+            }
+        })?;
+    }
+    log::info!("found {} logon attempts", attempts.len());
+
+    let analyzer = LogonAnalyzer::new(
+        options.window,
+        options.brute_force_threshold,
+        options.spray_threshold,
+    );
+
+    if options.statistics {
+        let statistics = analyzer.statistics(&attempts);
+        match options.format {
+            LogonsFormat::Csv => {
+                let mut csv_writer = csv::Writer::from_writer(stdout());
+                for entry in statistics {
+                    csv_writer.serialize(entry)?;
+                }
+                csv_writer.flush()?;
+            }
+            LogonsFormat::Json => println!("{}", serde_json::to_string_pretty(&statistics)?),
+            LogonsFormat::Markdown => {
+                println!("| source address | workstation | account | first | last | failures | successes |");
+                println!("|-|-|-|-|-|-|-|");
+                for entry in statistics {
+                    let cells = [
+                        entry.source_address.unwrap_or_default(),
+                        entry.workstation.unwrap_or_default(),
+                        format!("`{}`", entry.account),
+                        entry.first.format("%FT%T").to_string(),
+                        entry.last.format("%FT%T").to_string(),
+                        entry.failures.to_string(),
+                        entry.successes.to_string(),
+                    ];
+                    println!("{}", markdown_row(&cells));
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    let findings = analyzer.find_attacks(&attempts);
+    log::warn!(
+        "found {} suspicious bursts of failed logons",
+        findings.len()
+    );
+    match options.format {
+        LogonsFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(stdout());
+            for finding in findings.iter() {
+                csv_writer.serialize(FindingAsCsv::from(finding))?;
+            }
+            csv_writer.flush()?;
+        }
+        LogonsFormat::Json => println!("{}", serde_json::to_string_pretty(&findings)?),
+        LogonsFormat::Markdown => {
+            println!(
+                "| pattern | source | first | last | failures | accounts | followed by success |"
+            );
+            println!("|-|-|-|-|-|-|-|");
+            for finding in findings {
+                let pattern = match finding.pattern {
+                    AttackPattern::BruteForce => "brute force",
+                    AttackPattern::PasswordSpray => "password spray",
+                };
+                let accounts: Vec<_> = finding.accounts.iter().map(|a| format!("`{a}`")).collect();
+                let success = match &finding.followed_by_success {
+                    Some(success) => format!(
+                        "**`{}` at {}**",
+                        success.account,
+                        success.timestamp.format("%FT%T")
+                    ),
+                    None => String::new(),
+                };
+                let cells = [
+                    pattern.to_owned(),
+                    finding.source,
+                    finding.first.format("%FT%T").to_string(),
+                    finding.last.format("%FT%T").to_string(),
+                    finding.failures.to_string(),
+                    accounts.join(", "),
+                    success,
+                ];
+                println!("{}", markdown_row(&cells));
+            }
+        }
+    }
+    Ok(())
+}
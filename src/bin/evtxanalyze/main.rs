@@ -3,8 +3,15 @@ use dfir_toolkit::common::FancyParser;
 use log::log_enabled;
 use pstree::display_pstree;
 
+mod accounts;
 mod cli;
+mod helpers;
+mod lateral;
+mod logons;
+mod persistence;
 mod pstree;
+mod scriptblocks;
+mod session_pstree;
 mod sessions;
 
 fn main() -> anyhow::Result<()> {
@@ -15,6 +22,12 @@ fn main() -> anyhow::Result<()> {
         Command::PsTree { .. } => display_pstree(&cli),
         Command::Sessions { .. } => cli.display_sessions(),
         Command::Session { .. } => cli.display_single_session(),
+        Command::SessionPsTree { .. } => cli.display_session_pstree(),
+        Command::Logons { .. } => cli.display_logons(),
+        Command::Persistence { .. } => cli.display_persistence(),
+        Command::ScriptBlocks { .. } => cli.display_scriptblocks(),
+        Command::Accounts { .. } => cli.display_accounts(),
+        Command::Lateral { .. } => cli.display_lateral(),
     };
 
     if let Err(why) = result {
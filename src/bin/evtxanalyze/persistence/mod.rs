@@ -0,0 +1,120 @@
+mod persistence_entry;
+mod suspicion;
+pub use persistence_entry::*;
+pub use suspicion::*;
+
+use std::{io::stdout, path::PathBuf};
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::helpers::{for_each_record, markdown_row};
+
+#[derive(ValueEnum, Clone)]
+pub enum PersistenceFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+#[derive(Serialize)]
+struct EntryAsCsv<'a> {
+    timestamp: DateTime<Utc>,
+    computer: &'a str,
+    event_id: u16,
+    kind: PersistenceKind,
+    action: PersistenceAction,
+    name: &'a str,
+    binary_path: Option<&'a str>,
+    account: Option<&'a str>,
+    creator: Option<&'a str>,
+    suspicions: String,
+}
+
+impl<'a> From<&'a PersistenceEntry> for EntryAsCsv<'a> {
+    fn from(entry: &'a PersistenceEntry) -> Self {
+        Self {
+            timestamp: entry.timestamp,
+            computer: &entry.computer,
+            event_id: entry.event_id,
+            kind: entry.kind,
+            action: entry.action,
+            name: &entry.name,
+            binary_path: entry.binary_path.as_deref(),
+            account: entry.account.as_deref(),
+            creator: entry.creator.as_deref(),
+            suspicions: suspicions_of(entry),
+        }
+    }
+}
+
+fn suspicions_of(entry: &PersistenceEntry) -> String {
+    let suspicions: Vec<_> = entry.suspicions.iter().map(|s| s.to_string()).collect();
+    suspicions.join(", ")
+}
+
+pub(crate) fn display_persistence(
+    evtx_files: &[PathBuf],
+    suspicious_only: bool,
+    format: &PersistenceFormat,
+) -> anyhow::Result<()> {
+    let mut entries = Vec::new();
+    for evtx_file in evtx_files {
+        for_each_record(evtx_file, |record| {
+            if let Some(entry) = PersistenceEntry::try_from(record) {
+                entries.push(entry);
+            }
+        })?;
+    }
+    entries.sort_by_key(|e| e.timestamp);
+
+    let suspicious = entries.iter().filter(|e| e.is_suspicious()).count();
+    log::info!("found {} service and task events", entries.len());
+    if suspicious > 0 {
+        log::warn!("found {suspicious} suspicious service and task events");
+    }
+    if suspicious_only {
+        entries.retain(|e| e.is_suspicious());
+    }
+
+    match format {
+        PersistenceFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(stdout());
+            for entry in entries.iter() {
+                csv_writer.serialize(EntryAsCsv::from(entry))?;
+            }
+            csv_writer.flush()?;
+        }
+        PersistenceFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        PersistenceFormat::Markdown => {
+            println!("| timestamp | computer | event id | kind | action | name | binary path | account | creator | suspicious |");
+            println!("|-|-|-|-|-|-|-|-|-|-|");
+            for entry in entries {
+                let kind = match entry.kind {
+                    PersistenceKind::Service => "service",
+                    PersistenceKind::ScheduledTask => "scheduled task",
+                };
+                let binary_path = match &entry.binary_path {
+                    Some(path) if entry.is_suspicious() => format!("**`{path}`**"),
+                    Some(path) => format!("`{path}`"),
+                    None => String::new(),
+                };
+                let cells = [
+                    entry.timestamp.format("%FT%T").to_string(),
+                    entry.computer.clone(),
+                    entry.event_id.to_string(),
+                    kind.to_owned(),
+                    entry.action.to_string(),
+                    format!("`{}`", entry.name),
+                    binary_path,
+                    entry.account.clone().unwrap_or_default(),
+                    entry.creator.clone().unwrap_or_default(),
+                    suspicions_of(&entry),
+                ];
+                println!("{}", markdown_row(&cells));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+use dfirtk_eventdata::{EventId, EventProvider};
+use evtx::SerializedEvtxRecord;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::Suspicion;
+use crate::helpers::EventData;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistenceKind {
+    Service,
+    ScheduledTask,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum PersistenceAction {
+    Installed,
+    Created,
+    Registered,
+    Updated,
+    Deleted,
+    Enabled,
+    Disabled,
+    Started,
+    Completed,
+}
+
+impl Display for PersistenceAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let action = match self {
+            Self::Installed => "installed",
+            Self::Created => "created",
+            Self::Registered => "registered",
+            Self::Updated => "updated",
+            Self::Deleted => "deleted",
+            Self::Enabled => "enabled",
+            Self::Disabled => "disabled",
+            Self::Started => "started",
+            Self::Completed => "completed",
+        };
+        write!(f, "{action}")
+    }
+}
+
+/// the installation or modification of a service or scheduled task, found in
+/// System 7045, Security 4697 and 4698-4702 or TaskScheduler 106, 140, 141, 200 and 201
+#[derive(Serialize, Clone, Debug)]
+pub struct PersistenceEntry {
+    pub timestamp: DateTime<Utc>,
+    pub computer: String,
+    pub event_id: u16,
+    pub kind: PersistenceKind,
+    pub action: PersistenceAction,
+    pub name: String,
+    pub binary_path: Option<String>,
+    pub account: Option<String>,
+    pub creator: Option<String>,
+    pub suspicions: Vec<Suspicion>,
+}
+
+impl PersistenceEntry {
+    pub fn try_from(record: &SerializedEvtxRecord<Value>) -> Option<Self> {
+        let provider = EventProvider::name_of(record)?;
+        let event_id = EventId::try_from(record).ok()?.value();
+        let event_data = EventData::of(record);
+
+        use PersistenceAction::*;
+        use PersistenceKind::*;
+        let (kind, action, name, binary_path, account, creator) = match (provider, event_id) {
+            ("Service Control Manager", 7045) => (
+                Service,
+                Installed,
+                event_data.field("ServiceName")?,
+                event_data.field("ImagePath"),
+                event_data.field("AccountName"),
+                record.data["Event"]["System"]["Security"]["#attributes"]["UserID"]
+                    .as_str()
+                    .map(|sid| sid.to_owned()),
+            ),
+            ("Microsoft-Windows-Security-Auditing", 4697) => (
+                Service,
+                Installed,
+                event_data.field("ServiceName")?,
+                event_data.field("ServiceFileName"),
+                event_data.field("ServiceAccount"),
+                event_data.account("SubjectDomainName", "SubjectUserName"),
+            ),
+            ("Microsoft-Windows-Security-Auditing", 4698..=4702) => {
+                let action = match event_id {
+                    4698 => Created,
+                    4699 => Deleted,
+                    4700 => Enabled,
+                    4701 => Disabled,
+                    _ => Updated,
+                };
+                let content = event_data
+                    .field("TaskContent")
+                    .or_else(|| event_data.field("TaskContentNew"));
+                let (binary_path, account) = match content {
+                    Some(content) => (task_actions(&content), xml_element(&content, "UserId")),
+                    None => (None, None),
+                };
+                (
+                    ScheduledTask,
+                    action,
+                    event_data.field("TaskName")?,
+                    binary_path,
+                    account,
+                    event_data.account("SubjectDomainName", "SubjectUserName"),
+                )
+            }
+            ("Microsoft-Windows-TaskScheduler", 106) => (
+                ScheduledTask,
+                Registered,
+                event_data.field("TaskName")?,
+                None,
+                None,
+                event_data.field("UserContext"),
+            ),
+            ("Microsoft-Windows-TaskScheduler", 140 | 141) => (
+                ScheduledTask,
+                if event_id == 140 { Updated } else { Deleted },
+                event_data.field("TaskName")?,
+                None,
+                None,
+                event_data.field("UserName"),
+            ),
+            ("Microsoft-Windows-TaskScheduler", 200 | 201) => (
+                ScheduledTask,
+                if event_id == 200 { Started } else { Completed },
+                event_data.field("TaskName")?,
+                event_data.field("ActionName"),
+                None,
+                None,
+            ),
+            _ => return None,
+        };
+
+        let mut suspicions = binary_path
+            .as_deref()
+            .map(Suspicion::of)
+            .unwrap_or_default();
+        suspicions.sort();
+        suspicions.dedup();
+
+        Some(Self {
+            timestamp: record.timestamp,
+            computer: record.data["Event"]["System"]["Computer"]
+                .as_str()
+                .unwrap_or_default()
+                .to_owned(),
+            event_id,
+            kind,
+            action,
+            name,
+            binary_path,
+            account,
+            creator,
+            suspicions,
+        })
+    }
+
+    pub fn is_suspicious(&self) -> bool {
+        !self.suspicions.is_empty()
+    }
+}
+
+/// the command lines of all `Exec` actions and the class ids of all
+/// `ComHandler` actions of a task definition
+fn task_actions(content: &str) -> Option<String> {
+    let actions: Vec<_> = xml_elements(content, "Exec")
+        .map(|exec| {
+            let command = xml_element(exec, "Command").unwrap_or_default();
+            match xml_element(exec, "Arguments") {
+                Some(arguments) => format!("{command} {arguments}"),
+                None => command,
+            }
+        })
+        .chain(xml_elements(content, "ComHandler").filter_map(|com| {
+            xml_element(com, "ClassId").map(|class_id| format!("COM handler {class_id}"))
+        }))
+        .collect();
+    if actions.is_empty() {
+        None
+    } else {
+        Some(actions.join("; "))
+    }
+}
+
+/// the unescaped text content of the first element named `name`
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    xml_elements(xml, name)
+        .next()
+        .map(|content| unescape(content.trim()))
+        .filter(|content| !content.is_empty())
+}
+
+/// the raw content of all elements named `name`, which must not be nested
+fn xml_elements<'x>(mut xml: &'x str, name: &str) -> impl Iterator<Item = &'x str> {
+    let begin_tag = format!("<{name}");
+    let end_tag = format!("</{name}>");
+    std::iter::from_fn(move || loop {
+        let begin = xml.find(&begin_tag)?;
+        let rest = &xml[begin + begin_tag.len()..];
+
+        // skip elements which only have the same prefix, such as `<ExecutionTimeLimit>`
+        if !rest.starts_with(['>', ' ', '\t', '\r', '\n']) {
+            xml = rest;
+            continue;
+        }
+        let content_begin = rest.find('>')? + 1;
+        let end = rest.find(&end_tag)?;
+        xml = &rest[end + end_tag.len()..];
+        return rest.get(content_begin..end);
+    })
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{PersistenceAction, PersistenceEntry, PersistenceKind};
+    use crate::{helpers::record, persistence::Suspicion};
+
+    #[test]
+    fn scheduled_task_created() {
+        let content = r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <Settings><ExecutionTimeLimit>PT72H</ExecutionTimeLimit></Settings>
+  <Principals><Principal id="Author"><UserId>S-1-5-18</UserId></Principal></Principals>
+  <Actions Context="Author">
+    <Exec>
+      <Command>cmd.exe</Command>
+      <Arguments>/c &quot;C:\Users\Public\update.bat&quot;</Arguments>
+    </Exec>
+  </Actions>
+</Task>"#;
+        let record = record(
+            "Microsoft-Windows-Security-Auditing",
+            4698,
+            "server",
+            json!({
+                "SubjectUserName": "alice",
+                "SubjectDomainName": "CONTOSO",
+                "TaskName": "\\Updater",
+                "TaskContent": content,
+            }),
+        );
+        let entry = PersistenceEntry::try_from(&record).unwrap();
+        assert_eq!(entry.kind, PersistenceKind::ScheduledTask);
+        assert_eq!(entry.action, PersistenceAction::Created);
+        assert_eq!(
+            entry.binary_path.as_deref(),
+            Some("cmd.exe /c \"C:\\Users\\Public\\update.bat\"")
+        );
+        assert_eq!(entry.account.as_deref(), Some("S-1-5-18"));
+        assert_eq!(entry.creator.as_deref(), Some("CONTOSO\\alice"));
+        assert_eq!(
+            entry.suspicions,
+            vec![Suspicion::UserWritableDirectory, Suspicion::CmdExecution]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use std::fmt::Display;
+
+use serde::Serialize;
+
+use crate::helpers::is_encoded_command_parameter;
+
+/// directories where unprivileged users are allowed to write files
+const USER_WRITABLE_DIRECTORIES: &[&str] = &[
+    "\\users\\",
+    "\\windows\\temp\\",
+    "\\windows\\tasks\\",
+    "\\windows\\tracing\\",
+    "\\programdata\\",
+    "\\perflogs\\",
+    "\\$recycle.bin\\",
+    "%temp%",
+    "%tmp%",
+    "%appdata%",
+    "%localappdata%",
+    "%userprofile%",
+    "%public%",
+    "%programdata%",
+];
+
+/// reason why a service or task looks suspicious
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Suspicion {
+    /// the binary is located in a directory writable by unprivileged users
+    UserWritableDirectory,
+
+    /// PowerShell is started with an encoded command
+    EncodedPowerShell,
+
+    /// the command is executed using `cmd /c`
+    CmdExecution,
+}
+
+impl Display for Suspicion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UserWritableDirectory => write!(f, "user writable directory"),
+            Self::EncodedPowerShell => write!(f, "encoded powershell"),
+            Self::CmdExecution => write!(f, "cmd /c"),
+        }
+    }
+}
+
+impl Suspicion {
+    /// checks a command line for suspicious patterns
+    pub fn of(command_line: &str) -> Vec<Self> {
+        let command_line = command_line.to_lowercase();
+        let tokens: Vec<_> = command_line
+            .split_whitespace()
+            .map(|t| t.trim_matches(|c| c == '"' || c == '\''))
+            .collect();
+        let mut result = Vec::new();
+
+        if USER_WRITABLE_DIRECTORIES
+            .iter()
+            .any(|dir| command_line.contains(dir))
+        {
+            result.push(Self::UserWritableDirectory);
+        }
+
+        if tokens.iter().any(|t| is_image(t, &["powershell", "pwsh"]))
+            && (tokens.iter().any(|t| is_encoded_command_parameter(t))
+                || command_line.contains("frombase64string"))
+        {
+            result.push(Self::EncodedPowerShell);
+        }
+
+        if tokens.windows(2).any(|w| {
+            (is_image(w[0], &["cmd"]) || w[0] == "%comspec%") && (w[1] == "/c" || w[1] == "/k")
+        }) {
+            result.push(Self::CmdExecution);
+        }
+
+        result
+    }
+}
+
+/// checks if `token` is the path of one of `images`, with or without extension
+fn is_image(token: &str, images: &[&str]) -> bool {
+    let filename = token.rsplit(['\\', '/']).next().unwrap_or(token);
+    let filename = filename.strip_suffix(".exe").unwrap_or(filename);
+    images.contains(&filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Suspicion;
+
+    #[test]
+    fn suspicious_command_lines() {
+        assert!(Suspicion::of("C:\\Windows\\system32\\svchost.exe -k netsvcs").is_empty());
+        assert_eq!(
+            Suspicion::of("C:\\Users\\alice\\AppData\\Local\\Temp\\x.exe"),
+            vec![Suspicion::UserWritableDirectory]
+        );
+        assert_eq!(
+            Suspicion::of("powershell.exe -nop -w hidden -enc SQBFAFgA"),
+            vec![Suspicion::EncodedPowerShell]
+        );
+        assert_eq!(
+            Suspicion::of("%COMSPEC% /C \"powershell -e SQBFAFgA\""),
+            vec![Suspicion::EncodedPowerShell, Suspicion::CmdExecution]
+        );
+        assert!(Suspicion::of("powershell.exe -ExecutionPolicy Bypass -File x.ps1").is_empty());
+    }
+}
@@ -0,0 +1,137 @@
+use std::{cell::RefCell, collections::BTreeMap, fmt::Write, rc::Weak};
+
+use chrono::{DateTime, Utc};
+
+use super::{format_runtime, labels::Labels, Process};
+
+static TEMPLATE: &str = include_str!("pstree.html");
+
+/// creates a self-contained HTML page which displays a process tree
+pub(crate) fn html_pstree(
+    procs: &BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>>,
+    labels: &Labels,
+) -> String {
+    let mut tree = String::new();
+    html_processes(&mut tree, procs, labels);
+
+    TEMPLATE
+        .replace("{{process_tree}}", labels.process_tree)
+        .replace("{{search}}", labels.search)
+        .replace("{{color_by}}", labels.color_by)
+        .replace("{{nothing}}", labels.nothing)
+        .replace("{{user}}", labels.user)
+        .replace("{{integrity_level}}", labels.integrity_level)
+        .replace("{{expand_all}}", labels.expand_all)
+        .replace("{{collapse_all}}", labels.collapse_all)
+        .replace("{{tree}}", &tree)
+}
+
+fn html_processes(
+    html: &mut String,
+    procs: &BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>>,
+    labels: &Labels,
+) {
+    for proc in procs.values() {
+        if let Some(proc) = proc.upgrade() {
+            let p = proc.borrow();
+            let search = format!(
+                "0x{:x} {} {} {}",
+                p.new_process_id,
+                p.new_process_id,
+                p.new_process_name,
+                p.command_line
+            )
+            .to_lowercase();
+
+            let _ = write!(
+                html,
+                "<li class=\"process\" data-user=\"{}\" data-integrity=\"{}\" data-search=\"{}\">",
+                escape(p.user_name()),
+                escape(p.integrity_level.as_deref().unwrap_or_default()),
+                escape(&search)
+            );
+
+            let has_children = p.children.values().any(|c| c.strong_count() > 0);
+            if has_children {
+                html.push_str("<details open><summary>");
+                html_node(html, &p, labels);
+                html.push_str("</summary><ul class=\"tree\">");
+                html_processes(html, &p.children, labels);
+                html.push_str("</ul></details>");
+            } else {
+                html.push_str("<div class=\"leaf\">");
+                html_node(html, &p, labels);
+                html.push_str("</div>");
+            }
+            html.push_str("</li>\n");
+        }
+    }
+}
+
+fn html_node(html: &mut String, process: &Process, labels: &Labels) {
+    let mut tooltip = format!(
+        "{}: {}\n{}: {}",
+        labels.command_line,
+        process.command_line,
+        labels.user,
+        process.user_name()
+    );
+    if let Some(integrity_level) = process.integrity_level.as_ref() {
+        let _ = write!(tooltip, "\n{}: {integrity_level}", labels.integrity_level);
+    }
+
+    let lifetime = match (process.end_time, process.runtime()) {
+        (Some(end_time), Some(runtime)) => format!(
+            "{}: {}, {}: {} ({}: {})",
+            labels.started,
+            process.timestamp.format("%FT%T"),
+            labels.ended,
+            end_time.format("%FT%T"),
+            labels.runtime,
+            format_runtime(&runtime)
+        ),
+        _ => format!(
+            "{}: {}, {}",
+            labels.started,
+            process.timestamp.format("%FT%T"),
+            labels.no_termination
+        ),
+    };
+
+    let _ = write!(
+        html,
+        "<span class=\"node\" title=\"{}\"><span class=\"image\">{}</span> <span class=\"pid\">(0x{:04x})</span> <span class=\"ts\">{}</span></span>",
+        escape(&tooltip),
+        escape(&process.new_process_name),
+        process.new_process_id,
+        escape(&lifetime)
+    );
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape;
+
+    #[test]
+    fn escape_html() {
+        assert_eq!(
+            escape(r#"cmd.exe /c "echo <a> & 'b'""#),
+            "cmd.exe /c &quot;echo &lt;a&gt; &amp; &#39;b&#39;&quot;"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+
+/// language of the labels used in pstree outputs
+#[derive(ValueEnum, Clone, Copy, Default)]
+pub enum Language {
+    #[default]
+    #[clap(name = "en")]
+    English,
+
+    #[clap(name = "de")]
+    German,
+}
+
+impl Language {
+    pub fn labels(&self) -> &'static Labels {
+        match self {
+            Self::English => &EN,
+            Self::German => &DE,
+        }
+    }
+}
+
+/// texts used in human readable pstree outputs
+pub struct Labels {
+    pub started: &'static str,
+    pub ended: &'static str,
+    pub runtime: &'static str,
+    pub user: &'static str,
+    pub no_termination: &'static str,
+    pub integrity_level: &'static str,
+    pub command_line: &'static str,
+    pub process_tree: &'static str,
+    pub search: &'static str,
+    pub color_by: &'static str,
+    pub nothing: &'static str,
+    pub expand_all: &'static str,
+    pub collapse_all: &'static str,
+    pub session: &'static str,
+    pub computer: &'static str,
+    pub logon_type: &'static str,
+    pub source_ip: &'static str,
+    pub no_processes: &'static str,
+}
+
+const EN: Labels = Labels {
+    started: "started",
+    ended: "ended",
+    runtime: "runtime",
+    user: "user",
+    no_termination: "no termination found",
+    integrity_level: "integrity level",
+    command_line: "command line",
+    process_tree: "process tree",
+    search: "search",
+    color_by: "color by",
+    nothing: "nothing",
+    expand_all: "expand all",
+    collapse_all: "collapse all",
+    session: "Session",
+    computer: "computer",
+    logon_type: "logon type",
+    source_ip: "source ip",
+    no_processes: "no processes found",
+};
+
+const DE: Labels = Labels {
+    started: "gestartet",
+    ended: "beendet",
+    runtime: "Laufzeit",
+    user: "Benutzer",
+    no_termination: "kein Prozessende gefunden",
+    integrity_level: "Integritätsstufe",
+    command_line: "Kommandozeile",
+    process_tree: "Prozessbaum",
+    search: "Suche",
+    color_by: "Färbung nach",
+    nothing: "nichts",
+    expand_all: "alle ausklappen",
+    collapse_all: "alle einklappen",
+    session: "Sitzung",
+    computer: "Computer",
+    logon_type: "Anmeldetyp",
+    source_ip: "Quell-IP",
+    no_processes: "keine Prozesse gefunden",
+};
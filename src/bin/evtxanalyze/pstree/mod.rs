@@ -1,23 +1,27 @@
+mod html;
+pub mod labels;
 pub mod process;
+pub mod process_filter;
+pub mod process_tree;
 pub mod unique_pid;
 use std::{
     cell::RefCell,
-    collections::{BTreeMap, HashMap, HashSet},
+    collections::{BTreeMap, HashSet},
     io::stdout,
     ops::Deref,
-    rc::{Rc, Weak},
+    rc::Weak,
 };
 
 use chrono::{DateTime, Utc};
-use evtx::EvtxParser;
 pub(crate) use process::*;
+use html::html_pstree;
+use labels::Labels;
+use process_filter::ProcessFilter;
+use process_tree::ProcessTree;
 use regex::Regex;
 use serde_json::{json, Value};
 
-use crate::{
-    cli::{Command, Format},
-    pstree::unique_pid::UniquePid,
-};
+use crate::cli::{Command, Format};
 
 use super::Cli;
 
@@ -25,9 +29,15 @@ pub(crate) fn display_pstree(cli: &Cli) -> anyhow::Result<()> {
     match &cli.command {
         Command::PsTree {
             username,
-            evtx_file,
+            evtx_files,
             format,
+            not_before,
+            not_after,
+            image,
+            subtree_of,
+            language,
         } => {
+            let labels = language.labels();
             let username_regex = username
                 .as_ref()
                 .map(|s| Regex::new(&format!("(?i){}", s)).expect("invalid username regex"));
@@ -40,92 +50,37 @@ pub(crate) fn display_pstree(cli: &Cli) -> anyhow::Result<()> {
                 }
             };
 
-            let mut parser = EvtxParser::from_path(evtx_file)?;
-            let mut unique_pids = HashMap::new();
-            let mut events = HashMap::new();
-            let mut handled_records = 0;
-            let mut expected_records: usize = 0;
-            for record in parser.records_json_value() {
-                expected_records += 1;
-                match record {
-                    Err(why) => {
-                        log::error!("error while parsing a record; read {handled_records} until now. I'll try to continue with the next record");
-                        log::warn!("{why}")
-                    }
-                    Ok(record) => match Process::try_from(record) {
-                        Err(why) => log::error!("{why}"),
-                        Ok(Some(process)) => {
-                            if has_username(&process) {
-                                let pid = UniquePid::from(&process);
-                                unique_pids
-                                    .entry(process.new_process_id)
-                                    .or_insert_with(HashSet::new)
-                                    .insert(pid.clone());
-                                events.insert(pid, Rc::new(RefCell::new(process)));
-                            }
-                            handled_records += 1;
-                        }
-                        Ok(None) => handled_records += 1,
-                    },
-                }
+            let mut tree = ProcessTree::default();
+            for evtx_file in evtx_files {
+                tree.import(evtx_file, has_username)?;
             }
-
-            log::info!("finished reading all records");
-
-            if handled_records < expected_records {
-                log::warn!("I expected {expected_records}, but only {handled_records} could be handled.")
-            }
-
-            log::warn!("found {} process creations", events.len());
-
-            for new_process in events.values() {
-                let parent_pid = new_process.borrow().process_id;
-                let timestamp = new_process.borrow().timestamp;
-
-                /* find the unique parent pid. We assume that it is the pid with the
-                 * largest timestamp which is less than the current timestamp */
-                if let Some(parent_candidates) = unique_pids.get(&parent_pid) {
-                    let mut sorted_candidates: Vec<&UniquePid> = parent_candidates
-                        .iter()
-                        .filter(|p| p.timestamp() <= &timestamp)
-                        .collect();
-                    sorted_candidates.sort();
-                    if let Some(parent_pid) = sorted_candidates.last() {
-                        if let Some(parent) = events.get(parent_pid) {
-                            new_process.borrow_mut().is_root = false;
-                            let child_ts = new_process.borrow().timestamp;
-                            let child_process = Rc::downgrade(new_process);
-                            parent.borrow_mut().children.insert(child_ts, child_process);
-                        } else {
-                            log::error!("parent process not found: {parent_pid}");
-                        }
-                    } else {
-                        log::error!("found no parent for {}", new_process.borrow().command_line);
-                    }
-                }
+            tree.build();
+
+            log::warn!("found {} process creations", tree.len());
+
+            let filter = ProcessFilter {
+                not_before: not_before.as_ref().map(|ts| **ts),
+                not_after: not_after.as_ref().map(|ts| **ts),
+                image: image
+                    .as_ref()
+                    .map(|s| Regex::new(&format!("(?i){}", s)))
+                    .transpose()?,
+                subtree_of: subtree_of.clone(),
+            };
+            if !filter.is_empty() {
+                tree.retain_related(&filter);
+                log::warn!("{} processes remain after filtering", tree.len());
             }
 
-            let root_processes: BTreeMap<_, _> = events
-                .values()
-                .filter(|e| e.borrow().is_root)
-                .map(|e| {
-                    let timestamp = e.borrow().timestamp;
-                    let value = Value::from(&*e.borrow());
-                    (timestamp, value)
-                })
-                .collect();
-
+            let root_processes = tree.root_processes();
             log::warn!("{} processes have no parent", root_processes.len());
 
             match format {
                 Format::Json => {
-                    let root_processes: BTreeMap<_, _> = events
-                        .values()
-                        .filter(|e| e.borrow().is_root)
-                        .map(|e| {
-                            let timestamp = e.borrow().timestamp;
-                            let value = Value::from(&*e.borrow());
-                            (timestamp, value)
+                    let root_processes: BTreeMap<_, _> = root_processes
+                        .iter()
+                        .filter_map(|(timestamp, e)| {
+                            e.upgrade().map(|e| (timestamp, Value::from(&*e.borrow())))
                         })
                         .collect();
 
@@ -135,8 +90,8 @@ pub(crate) fn display_pstree(cli: &Cli) -> anyhow::Result<()> {
 
                 Format::Csv => {
                     let mut wtr = csv::Writer::from_writer(stdout().lock());
-                    let processes: HashSet<_> = events
-                        .values()
+                    let processes: HashSet<_> = tree
+                        .processes()
                         .map(|p| ProcessTableEntry::from(p.as_ref().borrow().deref()))
                         .collect();
                     for process in processes {
@@ -145,41 +100,18 @@ pub(crate) fn display_pstree(cli: &Cli) -> anyhow::Result<()> {
                 }
 
                 Format::Markdown => {
-                    let root_processes: BTreeMap<_, _> = events
-                        .values()
-                        .filter(|e| e.borrow().is_root)
-                        .map(|e| {
-                            let timestamp = e.borrow().timestamp;
-                            let proc = Rc::downgrade(e);
-                            (timestamp, proc)
-                        })
-                        .collect();
-                    display_markdown(&root_processes, 0);
+                    display_markdown(root_processes.values(), 0, labels);
                 }
 
                 Format::LaTeX => {
-                    let root_processes: BTreeMap<_, _> = events
-                        .values()
-                        .filter(|e| e.borrow().is_root)
-                        .map(|e| {
-                            let timestamp = e.borrow().timestamp;
-                            let proc = Rc::downgrade(e);
-                            (timestamp, proc)
-                        })
-                        .collect();
-                    display_latex(&root_processes);
+                    display_latex(&root_processes, labels);
+                }
+
+                Format::Html => {
+                    println!("{}", html_pstree(&root_processes, labels));
                 }
 
                 Format::Dot => {
-                    let root_processes: BTreeMap<_, _> = events
-                        .values()
-                        .filter(|e| e.borrow().is_root)
-                        .map(|e| {
-                            let timestamp = e.borrow().timestamp;
-                            let proc = Rc::downgrade(e);
-                            (timestamp, proc)
-                        })
-                        .collect();
                     println!("digraph {{");
                     println!("rankdir=\"LR\";");
                     display_dot(&root_processes);
@@ -193,16 +125,20 @@ pub(crate) fn display_pstree(cli: &Cli) -> anyhow::Result<()> {
     }
 }
 
-fn display_markdown(procs: &BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>>, indent: usize) {
-    for proc in procs.values() {
+pub(crate) fn display_markdown<'p>(
+    procs: impl IntoIterator<Item = &'p Weak<RefCell<Process>>>,
+    indent: usize,
+    labels: &Labels,
+) {
+    for proc in procs {
         if let Some(proc) = proc.upgrade() {
-            println!("{}- {}", " ".repeat(indent), proc.borrow());
-            display_markdown(&proc.borrow().children, indent + 2);
+            println!("{}- {}", " ".repeat(indent), proc.borrow().to_markdown(labels));
+            display_markdown(proc.borrow().children.values(), indent + 2, labels);
         }
     }
 }
 
-fn display_latex(procs: &BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>>) {
+fn display_latex(procs: &BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>>, labels: &Labels) {
     if !procs.is_empty() {
         println!("\\begin{{enumerate}}");
         for proc in procs.values() {
@@ -211,9 +147,21 @@ fn display_latex(procs: &BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>>) {
                 let pid = &p.new_process_id;
                 let filename = &p.new_process_name;
                 let timestamp = p.timestamp.format("%FT%T");
-                let user = p.subject_user_name.replace('_', "\\_").replace('$', "\\$");
-                println!("\\item[\\texttt{{{pid}}}] \\filename{{{filename}}}, gestartet: \\ts{{{timestamp}}}, Benutzer: \\username{{{user}}}",);
-                display_latex(&proc.borrow().children);
+                let user = p.user_name().replace('_', "\\_").replace('$', "\\$");
+                let end = match (p.end_time, p.runtime()) {
+                    (Some(end_time), Some(runtime)) => format!(
+                        ", {}: \\ts{{{}}} ({}: {})",
+                        labels.ended,
+                        end_time.format("%FT%T"),
+                        labels.runtime,
+                        format_runtime(&runtime)
+                    ),
+                    _ => String::new(),
+                };
+                let started = labels.started;
+                let user_label = labels.user;
+                println!("\\item[\\texttt{{{pid}}}] \\filename{{{filename}}}, {started}: \\ts{{{timestamp}}}{end}, {user_label}: \\username{{{user}}}",);
+                display_latex(&proc.borrow().children, labels);
             }
         }
         println!("\\end{{enumerate}}");
@@ -237,8 +185,17 @@ fn display_dot(procs: &BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>>) {
 }
 
 fn dot_display_process(process: &Process) {
+    let lifetime = match (process.end_time, process.runtime()) {
+        (Some(end_time), Some(runtime)) => format!(
+            "{} - {} ({})",
+            process.timestamp.format("%FT%T"),
+            end_time.format("%FT%T"),
+            format_runtime(&runtime)
+        ),
+        _ => format!("{} - ?", process.timestamp.format("%FT%T")),
+    };
     println!(
-        "p{} [label=<<FONT FACE=\"Courier\">{}</FONT>>, shape=\"box\"];",
+        "p{} [label=<<FONT FACE=\"Courier\">{}</FONT><BR/>{lifetime}>, shape=\"box\"];",
         process.new_process_id,
         process.new_process_name.replace('\\', "\\\\")
     );
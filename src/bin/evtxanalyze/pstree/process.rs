@@ -1,20 +1,22 @@
 use std::{
     cell::RefCell,
     collections::{BTreeMap, HashMap},
-    fmt::Display,
     hash::Hash,
     rc::Weak,
 };
 
 use anyhow::bail;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use evtx::SerializedEvtxRecord;
 use serde::Serialize;
 use serde_json::{json, Value};
 
+use super::labels::Labels;
+
 pub(crate) struct Process {
     pub(crate) timestamp: DateTime<Utc>,
     pub(crate) event_record_id: u64,
+    pub(crate) computer: String,
     pub(crate) subject_user_sid: String,
     pub(crate) subject_user_name: String,
     pub(crate) subject_domain_name: String,
@@ -30,10 +32,29 @@ pub(crate) struct Process {
     pub(crate) target_logon_id: String,
     pub(crate) parent_process_name: Option<String>,
     pub(crate) mandatory_label: Option<String>,
+    pub(crate) process_guid: Option<String>,
+    pub(crate) parent_process_guid: Option<String>,
+    pub(crate) hashes: Option<String>,
+    pub(crate) integrity_level: Option<String>,
+    pub(crate) source: ProcessSource,
+    pub(crate) end_time: Option<DateTime<Utc>>,
     pub(crate) children: BTreeMap<DateTime<Utc>, Weak<RefCell<Self>>>,
     pub(crate) is_root: bool,
 }
 
+/// the log(s) a process creation has been found in
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ProcessSource {
+    /// Security 4688
+    Security,
+
+    /// Sysmon 1
+    Sysmon,
+
+    /// Security 4688 and Sysmon 1
+    Both,
+}
+
 #[derive(Serialize, Clone)]
 pub(crate) struct ProcessTableEntry {
     pub(crate) subject_user_sid: String,
@@ -50,6 +71,14 @@ pub(crate) struct ProcessTableEntry {
     pub(crate) target_domain_name: String,
     pub(crate) target_logon_id: String,
     pub(crate) parent_process_name: Option<String>,
+    pub(crate) process_guid: Option<String>,
+    pub(crate) parent_process_guid: Option<String>,
+    pub(crate) hashes: Option<String>,
+    pub(crate) integrity_level: Option<String>,
+    pub(crate) source: ProcessSource,
+    pub(crate) start_time: DateTime<Utc>,
+    pub(crate) end_time: Option<DateTime<Utc>>,
+    pub(crate) runtime_seconds: Option<i64>,
 }
 
 impl From<&Process> for ProcessTableEntry {
@@ -69,6 +98,14 @@ impl From<&Process> for ProcessTableEntry {
             target_domain_name: value.target_domain_name.clone(),
             target_logon_id: value.target_logon_id.clone(),
             parent_process_name: value.parent_process_name.clone(),
+            process_guid: value.process_guid.clone(),
+            parent_process_guid: value.parent_process_guid.clone(),
+            hashes: value.hashes.clone(),
+            integrity_level: value.integrity_level.clone(),
+            source: value.source,
+            start_time: value.timestamp,
+            end_time: value.end_time,
+            runtime_seconds: value.runtime().map(|r| r.num_seconds()),
         }
     }
 }
@@ -135,6 +172,19 @@ impl From<&Process> for Value {
                 json!(process.parent_process_name),
             ),
             ("MandatoryLabel".to_owned(), json!(process.mandatory_label)),
+            ("ProcessGuid".to_owned(), json!(process.process_guid)),
+            (
+                "ParentProcessGuid".to_owned(),
+                json!(process.parent_process_guid),
+            ),
+            ("Hashes".to_owned(), json!(process.hashes)),
+            ("IntegrityLevel".to_owned(), json!(process.integrity_level)),
+            ("Source".to_owned(), json!(process.source)),
+            ("EndTime".to_owned(), json!(process.end_time)),
+            (
+                "RuntimeSeconds".to_owned(),
+                json!(process.runtime().map(|r| r.num_seconds())),
+            ),
         ]
         .into_iter()
         .collect();
@@ -149,20 +199,41 @@ impl From<&Process> for Value {
     }
 }
 
-impl Display for Process {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "`{}` (`0x{:04x}`, created *`{}`*, user is `{}`)",
-            self.new_process_name,
-            self.new_process_id,
-            self.timestamp
-                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-            self.target_user_name
-        )
+/// parses a logon id like `0x3e7`
+pub(crate) fn parse_logon_id(logon_id: &str) -> Option<u64> {
+    let logon_id = logon_id.trim();
+    let logon_id = logon_id
+        .strip_prefix("0x")
+        .or_else(|| logon_id.strip_prefix("0X"))?;
+    u64::from_str_radix(logon_id, 16).ok()
+}
+
+/// formats a duration as `[days]d hh:mm:ss`
+pub(crate) fn format_runtime(runtime: &Duration) -> String {
+    let seconds = runtime.num_seconds();
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    let time = format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds % 3600) / 60,
+        seconds % 60
+    );
+    if days > 0 {
+        format!("{days}d {time}")
+    } else {
+        time
     }
 }
 
+/// the termination of a process, logged as Security 4689 or Sysmon 5
+pub(crate) struct ProcessTermination {
+    pub(crate) timestamp: DateTime<Utc>,
+    pub(crate) computer: String,
+    pub(crate) process_id: u64,
+    pub(crate) process_name: String,
+    pub(crate) process_guid: Option<String>,
+}
+
 macro_rules! from_json {
     ($value: ident, $( $att:expr ),+ ) => {
         {
@@ -187,6 +258,15 @@ macro_rules! from_json_or_null {
     };
 }
 
+/// the name of the computer which logged `record`
+fn computer_of(record: &SerializedEvtxRecord<Value>) -> String {
+    let value = &record.data;
+    from_json_or_null!(value, "Event", "System", "Computer")
+        .as_str()
+        .unwrap_or_default()
+        .to_owned()
+}
+
 fn u64_from_value(value: &Value) -> anyhow::Result<u64> {
     if let Some(v) = value.as_u64() {
         Ok(v)
@@ -194,6 +274,12 @@ fn u64_from_value(value: &Value) -> anyhow::Result<u64> {
         bail!("Value '{value}' is no u64")
     }
 }
+fn u64_from_number_or_string(value: &Value) -> anyhow::Result<u64> {
+    match value {
+        Value::String(v) => Ok(v.parse()?),
+        v => u64_from_value(v),
+    }
+}
 fn u64_from_hex_value(value: &Value) -> anyhow::Result<u64> {
     if let Some(v) = value.as_str() {
         Ok(u64::from_str_radix(v.trim_start_matches("0x"), 16)?)
@@ -214,11 +300,20 @@ impl Process {
                 None => event_id,
             }
         })?;
+        let provider_name = from_json_or_null!(system, "Provider", "#attributes", "Name").as_str();
 
-        if event_id != 4688 {
-            return Ok(None);
+        match (event_id, provider_name) {
+            (4688, Some("Microsoft-Windows-Security-Auditing")) => {
+                Self::from_security_event(&record)
+            }
+            (1, Some("Microsoft-Windows-Sysmon")) => Self::from_sysmon_event(&record),
+            _ => Ok(None),
         }
+    }
 
+    fn from_security_event(record: &SerializedEvtxRecord<Value>) -> anyhow::Result<Option<Self>> {
+        let value = &record.data;
+        let event = from_json!(value, "Event");
         let event_data = from_json!(event, "EventData");
 
         let subject_user_sid = from_json!(event_data, "SubjectUserSid")
@@ -277,6 +372,7 @@ impl Process {
         Ok(Some(Self {
             timestamp: record.timestamp,
             event_record_id: record.event_record_id,
+            computer: computer_of(record),
             subject_user_sid,
             subject_user_name,
             subject_domain_name,
@@ -292,8 +388,311 @@ impl Process {
             target_logon_id,
             parent_process_name,
             mandatory_label,
+            process_guid: None,
+            parent_process_guid: None,
+            hashes: None,
+            integrity_level: None,
+            source: ProcessSource::Security,
+            end_time: None,
             children: Default::default(),
             is_root: true,
         }))
     }
+
+    /// creates a process from a Sysmon event 1. Sysmon does not log SIDs and
+    /// logon ids of the parent process, so those fields are left empty.
+    fn from_sysmon_event(record: &SerializedEvtxRecord<Value>) -> anyhow::Result<Option<Self>> {
+        let value = &record.data;
+        let event = from_json!(value, "Event");
+        let event_data = from_json!(event, "EventData");
+
+        let string_of = |value: &Value| value.as_str().map(|s| s.to_owned());
+        let split_user = |user: Option<&str>| match user.and_then(|u| u.split_once('\\')) {
+            Some((domain, user)) => (domain.to_owned(), user.to_owned()),
+            None => (String::new(), user.unwrap_or_default().to_owned()),
+        };
+
+        let (target_domain_name, target_user_name) =
+            split_user(from_json_or_null!(event_data, "User").as_str());
+        let (subject_domain_name, subject_user_name) =
+            split_user(from_json_or_null!(event_data, "ParentUser").as_str());
+        let logon_id = string_of(from_json_or_null!(event_data, "LogonId")).unwrap_or_default();
+
+        Ok(Some(Self {
+            timestamp: record.timestamp,
+            event_record_id: record.event_record_id,
+            computer: computer_of(record),
+            subject_user_sid: String::new(),
+            subject_user_name,
+            subject_domain_name,
+            subject_logon_id: String::new(),
+            new_process_id: u64_from_number_or_string(from_json!(event_data, "ProcessId"))?,
+            new_process_name: string_of(from_json!(event_data, "Image")).unwrap_or_default(),
+            token_elevation_type: String::new(),
+            process_id: u64_from_number_or_string(from_json!(event_data, "ParentProcessId"))?,
+            command_line: string_of(from_json_or_null!(event_data, "CommandLine"))
+                .unwrap_or_default(),
+            target_user_sid: String::new(),
+            target_user_name,
+            target_domain_name,
+            target_logon_id: logon_id,
+            parent_process_name: string_of(from_json_or_null!(event_data, "ParentImage")),
+            mandatory_label: None,
+            process_guid: string_of(from_json_or_null!(event_data, "ProcessGuid")),
+            parent_process_guid: string_of(from_json_or_null!(event_data, "ParentProcessGuid")),
+            hashes: string_of(from_json_or_null!(event_data, "Hashes")),
+            integrity_level: string_of(from_json_or_null!(event_data, "IntegrityLevel")),
+            source: ProcessSource::Sysmon,
+            end_time: None,
+            children: Default::default(),
+            is_root: true,
+        }))
+    }
+
+    /// checks if `other` describes the same process creation, but has been
+    /// logged by another source
+    pub fn is_same_process_as(&self, other: &Self) -> bool {
+        self.computer == other.computer
+            && self.new_process_id == other.new_process_id
+            && self.process_id == other.process_id
+            && self.new_process_name.eq_ignore_ascii_case(&other.new_process_name)
+            && (self.timestamp - other.timestamp).num_seconds().abs() <= 2
+    }
+
+    /// adds the information only available in Sysmon events
+    pub fn merge_sysmon_data(&mut self, sysmon: &Self) {
+        self.process_guid.clone_from(&sysmon.process_guid);
+        self.parent_process_guid.clone_from(&sysmon.parent_process_guid);
+        self.hashes.clone_from(&sysmon.hashes);
+        self.integrity_level.clone_from(&sysmon.integrity_level);
+        self.source = ProcessSource::Both;
+    }
+
+    /// the logon session this process runs in. If a process has been
+    /// started using other credentials, this is the target logon id
+    pub fn logon_id(&self) -> Option<u64> {
+        match parse_logon_id(&self.target_logon_id) {
+            None | Some(0) => parse_logon_id(&self.subject_logon_id),
+            logon_id => logon_id,
+        }
+    }
+
+    /// the name of the user this process runs as
+    pub fn user_name(&self) -> &str {
+        match self.target_user_name.as_str() {
+            "" | "-" => &self.subject_user_name,
+            user => user,
+        }
+    }
+
+    pub fn to_markdown(&self, labels: &Labels) -> String {
+        format!(
+            "`{}` (`0x{:04x}`, {}: *`{}`*, {}, {}: `{}`)",
+            self.new_process_name,
+            self.new_process_id,
+            labels.started,
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            match (self.end_time, self.runtime()) {
+                (Some(end_time), Some(runtime)) => format!(
+                    "{}: *`{}`* ({}: `{}`)",
+                    labels.ended,
+                    end_time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+                    labels.runtime,
+                    format_runtime(&runtime)
+                ),
+                _ => labels.no_termination.to_owned(),
+            },
+            labels.user,
+            self.user_name()
+        )
+    }
+
+    pub fn runtime(&self) -> Option<Duration> {
+        self.end_time.map(|end_time| end_time - self.timestamp)
+    }
+}
+
+impl ProcessTermination {
+    pub fn try_from(record: &SerializedEvtxRecord<Value>) -> anyhow::Result<Option<Self>> {
+        let value = &record.data;
+        let event = from_json!(value, "Event");
+        let system = from_json!(event, "System");
+        let event_id = u64_from_value({
+            let event_id = from_json!(system, "EventID");
+            match event_id.get("#text") {
+                Some(eid) => eid,
+                None => event_id,
+            }
+        })?;
+        let provider_name = from_json_or_null!(system, "Provider", "#attributes", "Name").as_str();
+
+        let (process_id, process_name, process_guid) = match (event_id, provider_name) {
+            (4689, Some("Microsoft-Windows-Security-Auditing")) => {
+                let event_data = from_json!(event, "EventData");
+                (
+                    u64_from_hex_value(from_json!(event_data, "ProcessId"))?,
+                    from_json_or_null!(event_data, "ProcessName").as_str(),
+                    None,
+                )
+            }
+            (5, Some("Microsoft-Windows-Sysmon")) => {
+                let event_data = from_json!(event, "EventData");
+                (
+                    u64_from_number_or_string(from_json!(event_data, "ProcessId"))?,
+                    from_json_or_null!(event_data, "Image").as_str(),
+                    from_json_or_null!(event_data, "ProcessGuid").as_str(),
+                )
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(Self {
+            timestamp: record.timestamp,
+            computer: computer_of(record),
+            process_id,
+            process_name: process_name.unwrap_or_default().to_owned(),
+            process_guid: process_guid.map(|s| s.to_owned()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use serde_json::json;
+
+    use super::{format_runtime, parse_logon_id, Process, ProcessSource, ProcessTermination};
+    use crate::helpers::record;
+
+    fn security_process() -> Process {
+        let record = record(
+            "Microsoft-Windows-Security-Auditing",
+            4688,
+            "HOST",
+            json!({
+                "SubjectUserSid": "S-1-5-18",
+                "SubjectUserName": "HOST$",
+                "SubjectDomainName": "WORKGROUP",
+                "SubjectLogonId": "0x3e7",
+                "NewProcessId": "0x1a2c",
+                "NewProcessName": "C:\\Windows\\System32\\cmd.exe",
+                "TokenElevationType": "%%1936",
+                "ProcessId": "0x3e8",
+                "CommandLine": "cmd.exe /c whoami",
+                "TargetUserSid": "S-1-0-0",
+                "TargetUserName": "alice",
+                "TargetDomainName": "CONTOSO",
+                "TargetLogonId": "0x12345",
+            }),
+        );
+        Process::try_from(record).unwrap().unwrap()
+    }
+
+    fn sysmon_process() -> Process {
+        let record = record(
+            "Microsoft-Windows-Sysmon",
+            1,
+            "HOST",
+            json!({
+                "ProcessGuid": "{6b9a2a51-1c1a-6553-1a00-000000000d00}",
+                "ProcessId": 6700,
+                "Image": "C:\\Windows\\system32\\cmd.exe",
+                "CommandLine": "cmd.exe /c whoami",
+                "User": "CONTOSO\\alice",
+                "LogonId": "0x12345",
+                "IntegrityLevel": "High",
+                "Hashes": "SHA256=0123",
+                "ParentProcessGuid": "{6b9a2a51-1c19-6553-1900-000000000d00}",
+                "ParentProcessId": 1000,
+                "ParentImage": "C:\\Windows\\explorer.exe",
+            }),
+        );
+        Process::try_from(record).unwrap().unwrap()
+    }
+
+    #[test]
+    fn sysmon_process_creation() {
+        let process = sysmon_process();
+        assert_eq!(process.source, ProcessSource::Sysmon);
+        assert_eq!(process.new_process_id, 6700);
+        assert_eq!(process.process_id, 1000);
+        assert_eq!(process.target_domain_name, "CONTOSO");
+        assert_eq!(process.target_user_name, "alice");
+        assert_eq!(process.integrity_level.as_deref(), Some("High"));
+    }
+
+    #[test]
+    fn merge_sources() {
+        let mut security = security_process();
+        let mut sysmon = sysmon_process();
+        sysmon.timestamp = security.timestamp + Duration::milliseconds(500);
+        assert!(security.is_same_process_as(&sysmon));
+
+        security.merge_sysmon_data(&sysmon);
+        assert_eq!(security.source, ProcessSource::Both);
+        assert_eq!(security.process_guid, sysmon.process_guid);
+        assert_eq!(security.target_user_sid, "S-1-0-0");
+
+        sysmon.timestamp = security.timestamp + Duration::seconds(10);
+        assert!(!security.is_same_process_as(&sysmon));
+    }
+
+    #[test]
+    fn process_terminations() {
+        let security = record(
+            "Microsoft-Windows-Security-Auditing",
+            4689,
+            "HOST",
+            json!({
+                "SubjectUserName": "alice",
+                "SubjectLogonId": "0x12345",
+                "Status": "0x0",
+                "ProcessId": "0x1a2c",
+                "ProcessName": "C:\\Windows\\System32\\cmd.exe",
+            }),
+        );
+        let termination = ProcessTermination::try_from(&security).unwrap().unwrap();
+        assert_eq!(termination.process_id, 0x1a2c);
+        assert!(termination.process_guid.is_none());
+
+        let sysmon = record(
+            "Microsoft-Windows-Sysmon",
+            5,
+            "HOST",
+            json!({
+                "ProcessGuid": "{6b9a2a51-1c1a-6553-1a00-000000000d00}",
+                "ProcessId": 6700,
+                "Image": "C:\\Windows\\system32\\cmd.exe",
+            }),
+        );
+        let termination = ProcessTermination::try_from(&sysmon).unwrap().unwrap();
+        assert_eq!(termination.process_id, 6700);
+        assert_eq!(termination.process_guid, sysmon_process().process_guid);
+
+        let creation = record("Microsoft-Windows-Sysmon", 1, "HOST", json!({}));
+        assert!(ProcessTermination::try_from(&creation).unwrap().is_none());
+
+        let other_provider = record("Microsoft-Windows-Kernel-Process", 4689, "HOST", json!({}));
+        assert!(ProcessTermination::try_from(&other_provider).unwrap().is_none());
+    }
+
+    #[test]
+    fn runtime_format() {
+        assert_eq!(format_runtime(&Duration::seconds(3723)), "01:02:03");
+        assert_eq!(format_runtime(&Duration::seconds(90061)), "1d 01:01:01");
+    }
+
+    #[test]
+    fn logon_ids() {
+        assert_eq!(parse_logon_id("0x3e7"), Some(0x3e7));
+        assert_eq!(parse_logon_id("0X3E7"), Some(0x3e7));
+        assert_eq!(parse_logon_id("-"), None);
+
+        let mut process = security_process();
+        assert_eq!(process.logon_id(), Some(0x12345));
+        process.target_logon_id = "0x0".to_owned();
+        assert_eq!(process.logon_id(), Some(0x3e7));
+        assert_eq!(sysmon_process().logon_id(), Some(0x12345));
+    }
 }
@@ -0,0 +1,122 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+use super::Process;
+
+/// selects a process by its process id or by its image name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProcessSelector {
+    Pid(u64),
+    Image(String),
+}
+
+impl FromStr for ProcessSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("process selector must not be empty".to_owned());
+        }
+
+        let pid = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        };
+
+        Ok(match pid {
+            Some(pid) => Self::Pid(pid),
+            None => Self::Image(s.to_lowercase()),
+        })
+    }
+}
+
+impl ProcessSelector {
+    /// an image matches if it is equal to the full path or to the file name
+    /// of the process image (case insensitive)
+    pub fn matches(&self, process: &Process) -> bool {
+        match self {
+            Self::Pid(pid) => process.new_process_id == *pid,
+            Self::Image(image) => {
+                let process_image = process.new_process_name.to_lowercase();
+                let filename = process_image.rsplit('\\').next().unwrap_or_default();
+                &process_image == image || filename == image
+            }
+        }
+    }
+}
+
+/// selects the processes which should be displayed in a process tree.
+/// `image` and `subtree_of` select processes which are displayed together
+/// with their ancestors and descendants, while `not_before` and `not_after`
+/// restrict all displayed processes to a time window.
+#[derive(Default)]
+pub(crate) struct ProcessFilter {
+    pub(crate) not_before: Option<DateTime<Utc>>,
+    pub(crate) not_after: Option<DateTime<Utc>>,
+    pub(crate) image: Option<Regex>,
+    pub(crate) subtree_of: Option<ProcessSelector>,
+}
+
+impl ProcessFilter {
+    pub fn is_empty(&self) -> bool {
+        !self.has_time_window() && !self.selects_processes()
+    }
+
+    pub fn has_time_window(&self) -> bool {
+        self.not_before.is_some() || self.not_after.is_some()
+    }
+
+    /// returns `true` if only some processes and their relatives shall be displayed
+    pub fn selects_processes(&self) -> bool {
+        self.image.is_some() || self.subtree_of.is_some()
+    }
+
+    /// checks if the process has been selected by `image` and `subtree_of`
+    pub fn is_selected(&self, process: &Process) -> bool {
+        if let Some(image) = self.image.as_ref() {
+            if !image.is_match(&process.new_process_name) {
+                return false;
+            }
+        }
+        if let Some(subtree_of) = self.subtree_of.as_ref() {
+            if !subtree_of.matches(process) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// checks if the process has been started inside of the time window
+    pub fn is_in_time_window(&self, process: &Process) -> bool {
+        if let Some(not_before) = self.not_before.as_ref() {
+            if &process.timestamp < not_before {
+                return false;
+            }
+        }
+        if let Some(not_after) = self.not_after.as_ref() {
+            if &process.timestamp > not_after {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ProcessSelector;
+
+    #[test]
+    fn parse_selector() {
+        assert_eq!("1234".parse(), Ok(ProcessSelector::Pid(1234)));
+        assert_eq!("0x1a2c".parse(), Ok(ProcessSelector::Pid(0x1a2c)));
+        assert_eq!(
+            "CMD.exe".parse(),
+            Ok(ProcessSelector::Image("cmd.exe".to_owned()))
+        );
+        assert!("".parse::<ProcessSelector>().is_err());
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    rc::{Rc, Weak},
+};
+
+use chrono::{DateTime, Utc};
+use evtx::EvtxParser;
+
+use super::{
+    process_filter::ProcessFilter, unique_pid::UniquePid, Process, ProcessSource,
+    ProcessTermination,
+};
+
+/// all processes found in one or more evtx files
+#[derive(Default)]
+pub(crate) struct ProcessTree {
+    processes: HashMap<UniquePid, Rc<RefCell<Process>>>,
+    /// all processes with the same process id on one computer
+    unique_pids: HashMap<(String, u64), HashSet<UniquePid>>,
+    terminations: Vec<ProcessTermination>,
+}
+
+impl ProcessTree {
+    /// reads all process creations of an evtx file (Security 4688 or
+    /// Sysmon 1), for which `filter` returns `true`, and all process
+    /// terminations (Security 4689 or Sysmon 5)
+    pub fn import<F>(&mut self, evtx_file: &Path, filter: F) -> anyhow::Result<()>
+    where
+        F: Fn(&Process) -> bool,
+    {
+        let mut parser = EvtxParser::from_path(evtx_file)?;
+        let mut handled_records = 0;
+        let mut expected_records: usize = 0;
+        for record in parser.records_json_value() {
+            expected_records += 1;
+            match record {
+                Err(why) => {
+                    log::error!("error while parsing a record; read {handled_records} until now. I'll try to continue with the next record");
+                    log::warn!("{why}")
+                }
+                Ok(record) => match ProcessTermination::try_from(&record) {
+                    Err(why) => log::error!("{why}"),
+                    Ok(Some(termination)) => {
+                        self.terminations.push(termination);
+                        handled_records += 1;
+                    }
+                    Ok(None) => match Process::try_from(record) {
+                        Err(why) => log::error!("{why}"),
+                        Ok(Some(process)) => {
+                            if filter(&process) {
+                                self.insert(process);
+                            }
+                            handled_records += 1;
+                        }
+                        Ok(None) => handled_records += 1,
+                    },
+                },
+            }
+        }
+
+        log::info!("finished reading all records of {}", evtx_file.display());
+
+        if handled_records < expected_records {
+            log::warn!("I expected {expected_records}, but only {handled_records} could be handled.")
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, process: Process) {
+        let pid = UniquePid::from(&process);
+        self.unique_pids
+            .entry(pid.key())
+            .or_default()
+            .insert(pid.clone());
+        self.processes.insert(pid, Rc::new(RefCell::new(process)));
+    }
+
+    fn remove(&mut self, pid: &UniquePid) {
+        self.processes.remove(pid);
+        if let Some(pids) = self.unique_pids.get_mut(&pid.key()) {
+            pids.remove(pid);
+            if pids.is_empty() {
+                self.unique_pids.remove(&pid.key());
+            }
+        }
+    }
+
+    /// merges process creations which have been logged by Security and by
+    /// Sysmon, links every process to its parent and to its termination
+    pub fn build(&mut self) {
+        self.merge_sources();
+        self.link_processes();
+        self.link_terminations();
+    }
+
+    /// removes all processes which have not been selected by `filter`,
+    /// except ancestors and descendants of selected processes. Afterwards,
+    /// all processes outside of the time window of `filter` are removed.
+    /// Processes whose parent has been removed become root processes.
+    /// Must be called after [`Self::build`]
+    pub fn retain_related(&mut self, filter: &ProcessFilter) {
+        let mut parents = HashMap::new();
+        for process in self.processes.values() {
+            for child in process.borrow().children.values() {
+                parents.insert(Weak::as_ptr(child), Rc::clone(process));
+            }
+        }
+
+        let mut related = HashSet::new();
+        if filter.selects_processes() {
+            let selected: Vec<_> = self
+                .processes
+                .values()
+                .filter(|p| filter.is_selected(&p.borrow()))
+                .cloned()
+                .collect();
+
+            for process in selected {
+                // ancestors
+                let mut parent = parents.get(&Rc::as_ptr(&process));
+                while let Some(p) = parent {
+                    if !related.insert(Rc::as_ptr(p)) {
+                        break;
+                    }
+                    parent = parents.get(&Rc::as_ptr(p));
+                }
+
+                // the process itself and its descendants
+                let mut descendants = vec![process];
+                while let Some(p) = descendants.pop() {
+                    if related.insert(Rc::as_ptr(&p)) {
+                        descendants.extend(p.borrow().children.values().filter_map(Weak::upgrade));
+                    }
+                }
+            }
+        } else {
+            related.extend(self.processes.values().map(Rc::as_ptr));
+        }
+
+        let retained: HashSet<_> = self
+            .processes
+            .values()
+            .filter(|p| related.contains(&Rc::as_ptr(p)) && filter.is_in_time_window(&p.borrow()))
+            .map(Rc::as_ptr)
+            .collect();
+
+        let removed: Vec<_> = self
+            .processes
+            .iter()
+            .filter(|(_, p)| !retained.contains(&Rc::as_ptr(p)))
+            .map(|(pid, _)| pid.clone())
+            .collect();
+        for pid in removed.iter() {
+            self.remove(pid);
+        }
+
+        for process in self.processes.values() {
+            if let Some(parent) = parents.get(&Rc::as_ptr(process)) {
+                if !retained.contains(&Rc::as_ptr(parent)) {
+                    process.borrow_mut().is_root = true;
+                }
+            }
+        }
+    }
+
+    fn link_terminations(&mut self) {
+        let guids: HashMap<_, _> = self
+            .processes
+            .values()
+            .filter_map(|p| {
+                p.borrow()
+                    .process_guid
+                    .as_ref()
+                    .map(|guid| (guid.clone(), Rc::clone(p)))
+            })
+            .collect();
+
+        let terminations = std::mem::take(&mut self.terminations);
+        let mut unmatched = 0;
+        for termination in terminations.iter() {
+            let process = match termination.process_guid.as_ref() {
+                Some(guid) => guids.get(guid),
+                None => None,
+            }
+            .or_else(|| {
+                // the process which has been started last before the termination
+                let candidates = self
+                    .unique_pids
+                    .get(&(termination.computer.clone(), termination.process_id))?;
+                candidates
+                    .iter()
+                    .filter(|p| p.timestamp() <= &termination.timestamp)
+                    .max()
+                    .and_then(|pid| self.processes.get(pid))
+            });
+
+            match process {
+                Some(process) => {
+                    let mut process = process.borrow_mut();
+                    if !termination.process_name.is_empty()
+                        && !process
+                            .new_process_name
+                            .eq_ignore_ascii_case(&termination.process_name)
+                    {
+                        unmatched += 1;
+                        continue;
+                    }
+
+                    // a termination might have been logged by Security and Sysmon
+                    if process.end_time.is_none() {
+                        process.end_time = Some(termination.timestamp);
+                    }
+                }
+                None => unmatched += 1,
+            }
+        }
+
+        if unmatched > 0 {
+            log::info!("found no process creation for {unmatched} process terminations");
+        }
+    }
+
+    fn merge_sources(&mut self) {
+        let sysmon_processes: Vec<_> = self
+            .processes
+            .iter()
+            .filter(|(_, p)| p.borrow().source == ProcessSource::Sysmon)
+            .map(|(pid, p)| (pid.clone(), Rc::clone(p)))
+            .collect();
+
+        let mut merged = 0;
+        for (sysmon_pid, sysmon_process) in sysmon_processes {
+            let sysmon_process = sysmon_process.borrow();
+            let security_process = self
+                .unique_pids
+                .get(&(
+                    sysmon_process.computer.clone(),
+                    sysmon_process.new_process_id,
+                ))
+                .into_iter()
+                .flatten()
+                .filter_map(|pid| self.processes.get(pid))
+                .find(|p| {
+                    let p = p.borrow();
+                    p.source == ProcessSource::Security && p.is_same_process_as(&sysmon_process)
+                });
+
+            if let Some(security_process) = security_process {
+                security_process
+                    .borrow_mut()
+                    .merge_sysmon_data(&sysmon_process);
+                self.remove(&sysmon_pid);
+                merged += 1;
+            }
+        }
+
+        if merged > 0 {
+            log::info!("merged {merged} processes found in Security and Sysmon logs");
+        }
+    }
+
+    fn link_processes(&mut self) {
+        let guids: HashMap<_, _> = self
+            .processes
+            .values()
+            .filter_map(|p| {
+                p.borrow()
+                    .process_guid
+                    .as_ref()
+                    .map(|guid| (guid.clone(), Rc::clone(p)))
+            })
+            .collect();
+
+        for new_process in self.processes.values() {
+            let parent = match self.find_parent_by_guid(new_process, &guids) {
+                Some(parent) => Some(parent),
+                None => self.find_parent_by_timestamp(new_process),
+            };
+
+            if let Some(parent) = parent {
+                new_process.borrow_mut().is_root = false;
+                let child_ts = new_process.borrow().timestamp;
+                let child_process = Rc::downgrade(new_process);
+                parent.borrow_mut().children.insert(child_ts, child_process);
+            }
+        }
+    }
+
+    /// Sysmon logs the unique id of the parent process
+    fn find_parent_by_guid<'a>(
+        &self,
+        new_process: &Rc<RefCell<Process>>,
+        guids: &'a HashMap<String, Rc<RefCell<Process>>>,
+    ) -> Option<&'a Rc<RefCell<Process>>> {
+        let new_process = new_process.borrow();
+        let parent_guid = new_process.parent_process_guid.as_ref()?;
+        guids.get(parent_guid)
+    }
+
+    /// find the unique parent pid. We assume that it is the pid with the
+    /// largest timestamp which is less than the current timestamp
+    fn find_parent_by_timestamp(
+        &self,
+        new_process: &Rc<RefCell<Process>>,
+    ) -> Option<&Rc<RefCell<Process>>> {
+        let computer = new_process.borrow().computer.clone();
+        let parent_pid = new_process.borrow().process_id;
+        let timestamp = new_process.borrow().timestamp;
+
+        let parent_candidates = self.unique_pids.get(&(computer, parent_pid))?;
+        let mut sorted_candidates: Vec<&UniquePid> = parent_candidates
+            .iter()
+            .filter(|p| p.timestamp() <= &timestamp)
+            .collect();
+        sorted_candidates.sort();
+        match sorted_candidates.last() {
+            Some(parent_pid) => {
+                let parent = self.processes.get(parent_pid);
+                if parent.is_none() {
+                    log::error!("parent process not found: {parent_pid}");
+                }
+                parent
+            }
+            None => {
+                log::error!("found no parent for {}", new_process.borrow().command_line);
+                None
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.processes.len()
+    }
+
+    pub fn processes(&self) -> impl Iterator<Item = &Rc<RefCell<Process>>> {
+        self.processes.values()
+    }
+
+    pub fn root_processes(&self) -> BTreeMap<DateTime<Utc>, Weak<RefCell<Process>>> {
+        self.processes
+            .values()
+            .filter(|e| e.borrow().is_root)
+            .map(|e| {
+                let timestamp = e.borrow().timestamp;
+                (timestamp, Rc::downgrade(e))
+            })
+            .collect()
+    }
+
+    /// all processes which have been started in the logon session
+    /// `logon_id` on `computer`, but whose parent does not belong to this
+    /// session, sorted by their creation time
+    pub fn session_roots(&self, computer: &str, logon_id: u64) -> Vec<Weak<RefCell<Process>>> {
+        let session_processes: Vec<_> = self
+            .processes
+            .values()
+            .filter(|p| {
+                let p = p.borrow();
+                p.logon_id() == Some(logon_id) && p.computer.eq_ignore_ascii_case(computer)
+            })
+            .collect();
+
+        let children: HashSet<_> = session_processes
+            .iter()
+            .flat_map(|p| {
+                p.borrow()
+                    .children
+                    .values()
+                    .map(Weak::as_ptr)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        let mut roots: Vec<_> = session_processes
+            .into_iter()
+            .filter(|p| !children.contains(&Rc::as_ptr(p)))
+            .collect();
+        roots.sort_by_key(|p| p.borrow().timestamp);
+        roots.into_iter().map(Rc::downgrade).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use evtx::SerializedEvtxRecord;
+    use regex::Regex;
+    use serde_json::json;
+
+    use super::ProcessTree;
+    use crate::{
+        helpers::record,
+        pstree::{process_filter::ProcessFilter, Process},
+    };
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 11, 14, 8, 0, 0).unwrap()
+    }
+
+    fn process(seconds: i64, pid: u64, parent_pid: u64, image: &str) -> Process {
+        process_of("HOST", seconds, pid, parent_pid, image)
+    }
+
+    fn process_of(computer: &str, seconds: i64, pid: u64, parent_pid: u64, image: &str) -> Process {
+        let record = SerializedEvtxRecord {
+            event_record_id: pid,
+            timestamp: start() + Duration::seconds(seconds),
+            ..record(
+                "Microsoft-Windows-Security-Auditing",
+                4688,
+                computer,
+                json!({
+                    "SubjectUserSid": "S-1-5-18",
+                    "SubjectUserName": "HOST$",
+                    "SubjectDomainName": "WORKGROUP",
+                    "SubjectLogonId": "0x3e7",
+                    "NewProcessId": format!("0x{pid:x}"),
+                    "NewProcessName": image,
+                    "TokenElevationType": "%%1936",
+                    "ProcessId": format!("0x{parent_pid:x}"),
+                    "CommandLine": image,
+                    "TargetUserSid": "S-1-0-0",
+                    "TargetUserName": "-",
+                    "TargetDomainName": "-",
+                    "TargetLogonId": "0x0",
+                }),
+            )
+        };
+        Process::try_from(record).unwrap().unwrap()
+    }
+
+    /// smss.exe and services.exe are started at boot time, cmd.exe and
+    /// whoami.exe one hour later
+    fn tree() -> ProcessTree {
+        let mut tree = ProcessTree::default();
+        tree.insert(process(0, 0x100, 0x4, "C:\\Windows\\System32\\smss.exe"));
+        tree.insert(process(5, 0x200, 0x100, "C:\\Windows\\System32\\services.exe"));
+        tree.insert(process(3600, 0x300, 0x200, "C:\\Windows\\System32\\cmd.exe"));
+        tree.insert(process(3601, 0x400, 0x300, "C:\\Windows\\System32\\whoami.exe"));
+        tree.build();
+        tree
+    }
+
+    fn images(tree: &ProcessTree) -> (Vec<String>, Vec<String>) {
+        let image = |p: &Process| p.new_process_name.rsplit('\\').next().unwrap().to_owned();
+        let mut all: Vec<_> = tree.processes().map(|p| image(&p.borrow())).collect();
+        all.sort();
+        let roots = tree
+            .root_processes()
+            .values()
+            .filter_map(|p| p.upgrade())
+            .map(|p| image(&p.borrow()))
+            .collect();
+        (all, roots)
+    }
+
+    #[test]
+    fn retain_time_window() {
+        let mut tree = tree();
+        tree.retain_related(&ProcessFilter {
+            not_before: Some(start() + Duration::minutes(30)),
+            ..Default::default()
+        });
+        assert_eq!(
+            images(&tree),
+            (vec!["cmd.exe".into(), "whoami.exe".into()], vec!["cmd.exe".into()])
+        );
+
+        let mut tree = self::tree();
+        tree.retain_related(&ProcessFilter {
+            not_after: Some(start() + Duration::minutes(30)),
+            ..Default::default()
+        });
+        assert_eq!(
+            images(&tree),
+            (vec!["services.exe".into(), "smss.exe".into()], vec!["smss.exe".into()])
+        );
+    }
+
+    #[test]
+    fn retain_relatives_in_time_window() {
+        let mut tree = tree();
+        tree.retain_related(&ProcessFilter {
+            image: Some(Regex::new("(?i)cmd").unwrap()),
+            ..Default::default()
+        });
+        assert_eq!(images(&tree).0.len(), 4);
+
+        let mut tree = self::tree();
+        tree.retain_related(&ProcessFilter {
+            not_before: Some(start() + Duration::minutes(30)),
+            image: Some(Regex::new("(?i)whoami").unwrap()),
+            ..Default::default()
+        });
+        assert_eq!(
+            images(&tree),
+            (vec!["cmd.exe".into(), "whoami.exe".into()], vec!["cmd.exe".into()])
+        );
+    }
+
+    #[test]
+    fn session_roots_of_the_same_second() {
+        let mut tree = tree();
+        tree.insert(process(0, 0x500, 0x8, "C:\\Windows\\System32\\csrss.exe"));
+        tree.build();
+
+        let mut roots: Vec<_> = tree
+            .session_roots("host", 0x3e7)
+            .iter()
+            .filter_map(|p| p.upgrade())
+            .map(|p| p.borrow().new_process_id)
+            .collect();
+        roots.sort();
+        assert_eq!(roots, vec![0x100, 0x500]);
+    }
+
+    #[test]
+    fn separate_processes_of_different_computers() {
+        let mut tree = tree();
+        tree.insert(process_of("OTHER", 0, 0x200, 0x4, "smss.exe"));
+        tree.insert(process_of("OTHER", 3602, 0x500, 0x300, "notepad.exe"));
+        tree.build();
+
+        let roots = |computer| -> Vec<_> {
+            tree.session_roots(computer, 0x3e7)
+                .iter()
+                .filter_map(|p| p.upgrade())
+                .map(|p| p.borrow().new_process_id)
+                .collect()
+        };
+        assert_eq!(roots("HOST"), vec![0x100]);
+        assert_eq!(roots("OTHER"), vec![0x200, 0x500]);
+    }
+}
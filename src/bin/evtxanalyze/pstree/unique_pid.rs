@@ -7,6 +7,7 @@ use super::Process;
 #[derive(Eq, PartialEq, Hash, Clone)]
 pub (crate) struct UniquePid {
     timestamp: DateTime<Utc>,
+    computer: String,
     pid: u64
 }
 
@@ -14,6 +15,7 @@ impl From<&Process> for UniquePid {
     fn from(process: &Process) -> Self {
         Self {
             timestamp: process.timestamp,
+            computer: process.computer.clone(),
             pid: process.new_process_id
         }
     }
@@ -29,6 +31,11 @@ impl UniquePid {
     pub fn timestamp(&self) -> &DateTime<Utc> {
         &self.timestamp
     }
+
+    /// process ids are only unique on one single computer
+    pub fn key(&self) -> (String, u64) {
+        (self.computer.clone(), self.pid)
+    }
 }
 
 impl Ord for UniquePid {
@@ -40,7 +47,7 @@ impl Ord for UniquePid {
 #[allow(clippy::non_canonical_partial_ord_impl)]
 impl PartialOrd for UniquePid {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self.pid != other.pid {
+        if self.pid != other.pid || self.computer != other.computer {
             None
         } else {
             Some(self.timestamp.cmp(&other.timestamp))
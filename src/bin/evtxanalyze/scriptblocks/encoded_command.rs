@@ -0,0 +1,128 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use dfirtk_eventdata::{EventId, EventProvider};
+use evtx::SerializedEvtxRecord;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::helpers::is_encoded_command_parameter;
+
+/// a base64 encoded command passed to PowerShell, found in PowerShell 4103
+/// or in Windows PowerShell 400 and 800
+#[derive(Serialize, Clone, Debug)]
+pub struct EncodedCommand {
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub computer: String,
+    pub event_ids: Vec<u16>,
+    pub occurrences: usize,
+    pub encoded: String,
+    pub decoded: String,
+}
+
+impl EncodedCommand {
+    /// returns all encoded commands found in the event data of `record`
+    pub fn from_record(record: &SerializedEvtxRecord<Value>) -> Vec<Self> {
+        let event_id = match (EventProvider::name_of(record), EventId::try_from(record)) {
+            (Some("Microsoft-Windows-PowerShell"), Ok(id)) if id.value() == 4103 => id.value(),
+            (Some("PowerShell"), Ok(id)) if id.value() == 400 || id.value() == 800 => id.value(),
+            _ => return Vec::new(),
+        };
+
+        let mut strings = Vec::new();
+        collect_strings(&record.data["Event"]["EventData"], &mut strings);
+        strings
+            .into_iter()
+            .flat_map(encoded_arguments)
+            .filter_map(|encoded| {
+                decode(&encoded).map(|decoded| Self {
+                    first_seen: record.timestamp,
+                    last_seen: record.timestamp,
+                    computer: record.data["Event"]["System"]["Computer"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .to_owned(),
+                    event_ids: vec![event_id],
+                    occurrences: 1,
+                    encoded,
+                    decoded,
+                })
+            })
+            .collect()
+    }
+
+    /// merges another occurrence of the same command into this one
+    pub fn merge(&mut self, other: Self) {
+        self.first_seen = self.first_seen.min(other.first_seen);
+        self.last_seen = self.last_seen.max(other.last_seen);
+        self.occurrences += other.occurrences;
+        for event_id in other.event_ids {
+            if !self.event_ids.contains(&event_id) {
+                self.event_ids.push(event_id);
+            }
+        }
+    }
+}
+
+fn collect_strings<'v>(value: &'v Value, strings: &mut Vec<&'v str>) {
+    match value {
+        Value::String(s) => strings.push(s),
+        Value::Array(values) => values.iter().for_each(|v| collect_strings(v, strings)),
+        Value::Object(values) => values.values().for_each(|v| collect_strings(v, strings)),
+        _ => (),
+    }
+}
+
+/// all values which follow an `-EncodedCommand` parameter
+fn encoded_arguments(text: &str) -> Vec<String> {
+    let tokens: Vec<_> = text
+        .split_whitespace()
+        .map(|t| t.trim_matches(|c| c == '"' || c == '\''))
+        .collect();
+    tokens
+        .windows(2)
+        .filter(|w| is_encoded_command_parameter(&w[0].to_lowercase()))
+        .map(|w| w[1].to_owned())
+        .collect()
+}
+
+/// decodes a base64 encoded UTF-16LE string
+fn decode(encoded: &str) -> Option<String> {
+    let bytes = STANDARD.decode(encoded).ok()?;
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let chars: Vec<_> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    String::from_utf16(&chars).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::EncodedCommand;
+    use crate::helpers::record;
+
+    #[test]
+    fn decode_host_application() {
+        let record = record(
+            "PowerShell",
+            400,
+            "client",
+            json!({
+                "Data": {"#text": [
+                    "Available",
+                    "None",
+                    "\tNewEngineState=Available\r\n\tHostApplication=powershell.exe -NoP -EncodedCommand dwBoAG8AYQBtAGkA\r\n"
+                ]}
+            }),
+        );
+        let commands = EncodedCommand::from_record(&record);
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].decoded, "whoami");
+        assert_eq!(commands[0].event_ids, vec![400]);
+    }
+}
@@ -0,0 +1,241 @@
+mod encoded_command;
+mod script_block;
+pub use encoded_command::*;
+pub use script_block::*;
+
+use std::{
+    collections::HashMap,
+    io::stdout,
+    path::{Path, PathBuf},
+};
+
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::helpers::{for_each_record, markdown_row};
+
+#[derive(ValueEnum, Clone)]
+pub enum ScriptBlocksFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+#[derive(Serialize)]
+struct ScriptBlockAsCsv<'a> {
+    script_block_id: &'a str,
+    first_seen: DateTime<Utc>,
+    computer: &'a str,
+    user_id: Option<&'a str>,
+    path: Option<&'a str>,
+    fragments_found: usize,
+    fragments_total: u64,
+    missing_fragments: String,
+    malformed: bool,
+    file: Option<String>,
+}
+
+#[derive(Serialize)]
+struct EncodedCommandAsCsv<'a> {
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+    computer: &'a str,
+    event_ids: String,
+    occurrences: usize,
+    decoded: &'a str,
+    encoded: &'a str,
+}
+
+pub(crate) struct ScriptBlocksOptions<'a> {
+    pub(crate) evtx_files: &'a [PathBuf],
+    pub(crate) output_dir: Option<&'a Path>,
+    pub(crate) encoded_commands: bool,
+    pub(crate) format: &'a ScriptBlocksFormat,
+}
+
+pub(crate) fn display_scriptblocks(options: ScriptBlocksOptions) -> anyhow::Result<()> {
+    if let Some(output_dir) = options.output_dir {
+        if !output_dir.is_dir() {
+            bail!("'{}' is not a directory", output_dir.display());
+        }
+    }
+
+    let mut fragments = Vec::new();
+    let mut commands: Vec<EncodedCommand> = Vec::new();
+    let mut command_index: HashMap<(String, String), usize> = HashMap::new();
+
+    for evtx_file in options.evtx_files {
+        for_each_record(evtx_file, |record| {
+            if let Some(fragment) = ScriptBlockFragment::try_from(record) {
+                fragments.push(fragment);
+            }
+            for command in EncodedCommand::from_record(record) {
+                let key = (command.computer.clone(), command.encoded.clone());
+                match command_index.get(&key) {
+                    Some(idx) => commands[*idx].merge(command),
+                    None => {
+                        command_index.insert(key, commands.len());
+                        commands.push(command);
+                    }
+                }
+            }
+        })?;
+    }
+
+    if options.encoded_commands {
+        return display_encoded_commands(commands, options.format);
+    }
+
+    let blocks = reassemble(fragments);
+    log::info!("found {} script blocks", blocks.len());
+
+    let incomplete = blocks
+        .iter()
+        .filter(|b| !b.missing_fragments().is_empty())
+        .count();
+    if incomplete > 0 {
+        log::warn!("{incomplete} script blocks are incomplete");
+    }
+    let malformed = blocks.iter().filter(|b| b.is_malformed()).count();
+    if malformed > 0 {
+        log::warn!("{malformed} script blocks have an invalid number of fragments");
+    }
+
+    let mut files = Vec::new();
+    for block in blocks.iter() {
+        match options.output_dir {
+            Some(output_dir) => {
+                let path = output_dir.join(block.file_name());
+                std::fs::write(&path, block.text())?;
+                files.push(Some(path.display().to_string()));
+            }
+            None => files.push(None),
+        }
+    }
+
+    match options.format {
+        ScriptBlocksFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(stdout());
+            for (block, file) in blocks.iter().zip(files) {
+                csv_writer.serialize(ScriptBlockAsCsv {
+                    script_block_id: &block.script_block_id,
+                    first_seen: block.first_seen,
+                    computer: &block.computer,
+                    user_id: block.user_id.as_deref(),
+                    path: block.path.as_deref(),
+                    fragments_found: block.fragments_found(),
+                    fragments_total: block.message_total,
+                    missing_fragments: join(&block.missing_fragments()),
+                    malformed: block.is_malformed(),
+                    file,
+                })?;
+            }
+            csv_writer.flush()?;
+        }
+        ScriptBlocksFormat::Json => {
+            let blocks: Vec<_> = blocks
+                .iter()
+                .zip(files)
+                .map(|(block, file)| {
+                    serde_json::json!({
+                        "script_block_id": block.script_block_id,
+                        "first_seen": block.first_seen,
+                        "computer": block.computer,
+                        "user_id": block.user_id,
+                        "path": block.path,
+                        "fragments_found": block.fragments_found(),
+                        "fragments_total": block.message_total,
+                        "missing_fragments": block.missing_fragments(),
+                        "malformed": block.is_malformed(),
+                        "file": file,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&blocks)?);
+        }
+        ScriptBlocksFormat::Markdown => {
+            println!("| first seen | computer | user | script block id | path | fragments | missing fragments | file |");
+            println!("|-|-|-|-|-|-|-|-|");
+            for (block, file) in blocks.iter().zip(files) {
+                let missing = block.missing_fragments();
+                let fragments = if block.is_malformed() {
+                    format!(
+                        "**{}/{} (malformed)**",
+                        block.fragments_found(),
+                        block.message_total
+                    )
+                } else if missing.is_empty() {
+                    format!("{}/{}", block.fragments_found(), block.message_total)
+                } else {
+                    format!("**{}/{}**", block.fragments_found(), block.message_total)
+                };
+                let cells = [
+                    block.first_seen.format("%FT%T").to_string(),
+                    block.computer.clone(),
+                    block.user_id.clone().unwrap_or_default(),
+                    format!("`{}`", block.script_block_id),
+                    block
+                        .path
+                        .as_deref()
+                        .map(|p| format!("`{p}`"))
+                        .unwrap_or_default(),
+                    fragments,
+                    join(&missing),
+                    file.map(|f| format!("`{f}`")).unwrap_or_default(),
+                ];
+                println!("{}", markdown_row(&cells));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn display_encoded_commands(
+    commands: Vec<EncodedCommand>,
+    format: &ScriptBlocksFormat,
+) -> anyhow::Result<()> {
+    log::info!("found {} distinct encoded commands", commands.len());
+    match format {
+        ScriptBlocksFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(stdout());
+            for command in commands.iter() {
+                csv_writer.serialize(EncodedCommandAsCsv {
+                    first_seen: command.first_seen,
+                    last_seen: command.last_seen,
+                    computer: &command.computer,
+                    event_ids: join(&command.event_ids),
+                    occurrences: command.occurrences,
+                    decoded: &command.decoded,
+                    encoded: &command.encoded,
+                })?;
+            }
+            csv_writer.flush()?;
+        }
+        ScriptBlocksFormat::Json => println!("{}", serde_json::to_string_pretty(&commands)?),
+        ScriptBlocksFormat::Markdown => {
+            println!(
+                "| first seen | last seen | computer | event ids | occurrences | decoded command |"
+            );
+            println!("|-|-|-|-|-|-|");
+            for command in commands {
+                let cells = [
+                    command.first_seen.format("%FT%T").to_string(),
+                    command.last_seen.format("%FT%T").to_string(),
+                    command.computer,
+                    join(&command.event_ids),
+                    command.occurrences.to_string(),
+                    format!("`{}`", command.decoded.replace(['\r', '\n'], " ")),
+                ];
+                println!("{}", markdown_row(&cells));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn join<T: ToString>(values: &[T]) -> String {
+    let values: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+    values.join(", ")
+}
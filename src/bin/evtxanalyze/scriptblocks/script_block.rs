@@ -0,0 +1,238 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use dfirtk_eventdata::{EventId, EventProvider};
+use evtx::SerializedEvtxRecord;
+use serde_json::Value;
+
+/// number of fragments which may follow the last fragment found. Larger
+/// values of `MessageTotal` are considered as malformed, because they would
+/// make us report a huge number of missing fragments.
+const MAX_FRAGMENTS_AFTER_LAST: u64 = 1024;
+
+/// one part of a script block, found in PowerShell 4104
+pub struct ScriptBlockFragment {
+    pub timestamp: DateTime<Utc>,
+    pub computer: String,
+    pub user_id: Option<String>,
+    pub script_block_id: String,
+    pub path: Option<String>,
+    pub message_number: u64,
+    pub message_total: u64,
+    pub text: String,
+}
+
+impl ScriptBlockFragment {
+    pub fn try_from(record: &SerializedEvtxRecord<Value>) -> Option<Self> {
+        if EventProvider::name_of(record)? != "Microsoft-Windows-PowerShell"
+            || EventId::try_from(record).ok()?.value() != 4104
+        {
+            return None;
+        }
+        let system = &record.data["Event"]["System"];
+        let event_data = &record.data["Event"]["EventData"];
+        let number = |name: &str| match event_data[name].as_u64() {
+            Some(value) => Some(value),
+            None => event_data[name].as_str()?.parse().ok(),
+        };
+
+        Some(Self {
+            timestamp: record.timestamp,
+            computer: system["Computer"].as_str().unwrap_or_default().to_owned(),
+            user_id: system["Security"]["#attributes"]["UserID"]
+                .as_str()
+                .map(|sid| sid.to_owned()),
+            script_block_id: event_data["ScriptBlockId"].as_str()?.to_owned(),
+            path: event_data["Path"]
+                .as_str()
+                .filter(|p| !p.is_empty())
+                .map(|p| p.to_owned()),
+            message_number: number("MessageNumber")?,
+            message_total: number("MessageTotal")?,
+            text: event_data["ScriptBlockText"]
+                .as_str()
+                .unwrap_or_default()
+                .to_owned(),
+        })
+    }
+}
+
+/// a script block, reassembled from all of its fragments
+pub struct ScriptBlock {
+    pub script_block_id: String,
+    pub first_seen: DateTime<Utc>,
+    pub computer: String,
+    pub user_id: Option<String>,
+    pub path: Option<String>,
+    pub message_total: u64,
+    fragments: BTreeMap<u64, String>,
+}
+
+impl From<ScriptBlockFragment> for ScriptBlock {
+    fn from(fragment: ScriptBlockFragment) -> Self {
+        Self {
+            script_block_id: fragment.script_block_id,
+            first_seen: fragment.timestamp,
+            computer: fragment.computer,
+            user_id: fragment.user_id,
+            path: fragment.path,
+            message_total: fragment.message_total,
+            fragments: BTreeMap::from([(fragment.message_number, fragment.text)]),
+        }
+    }
+}
+
+impl ScriptBlock {
+    /// adds a fragment of this script block. If the same fragment has been
+    /// logged more than once, the first one is kept.
+    fn add(&mut self, fragment: ScriptBlockFragment) {
+        self.first_seen = self.first_seen.min(fragment.timestamp);
+        self.message_total = self.message_total.max(fragment.message_total);
+        if self.path.is_none() {
+            self.path = fragment.path;
+        }
+        self.fragments
+            .entry(fragment.message_number)
+            .or_insert(fragment.text);
+    }
+
+    pub fn fragments_found(&self) -> usize {
+        self.fragments.len()
+    }
+
+    /// returns `true` if `MessageTotal` does not match the fragments found,
+    /// i.e. it is zero, less than the number of a fragment or much larger
+    /// than the number of the last fragment
+    pub fn is_malformed(&self) -> bool {
+        let last = self.last_fragment_number();
+        self.message_total == 0
+            || self.message_total < last
+            || self.message_total - last > MAX_FRAGMENTS_AFTER_LAST
+    }
+
+    fn last_fragment_number(&self) -> u64 {
+        self.fragments.keys().max().copied().unwrap_or_default()
+    }
+
+    /// the number of fragments this script block presumably consists of.
+    /// `MessageTotal` is used only if it is plausible.
+    fn expected_fragments(&self) -> u64 {
+        if self.is_malformed() {
+            self.last_fragment_number()
+        } else {
+            self.message_total
+        }
+    }
+
+    pub fn missing_fragments(&self) -> Vec<u64> {
+        (1..=self.expected_fragments())
+            .filter(|n| !self.fragments.contains_key(n))
+            .collect()
+    }
+
+    /// the text of the script block, where every missing fragment is
+    /// replaced by a comment
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for number in 1..=self.expected_fragments() {
+            match self.fragments.get(&number) {
+                Some(fragment) => text.push_str(fragment),
+                None => text.push_str(&format!(
+                    "\n# ---- fragment {number} of {} is missing ----\n",
+                    self.message_total
+                )),
+            }
+        }
+        text
+    }
+
+    /// a file name which is derived from the script block id
+    pub fn file_name(&self) -> String {
+        let id: String = self
+            .script_block_id
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect();
+        format!("{id}.ps1")
+    }
+}
+
+/// groups fragments by their script block id, and returns all script
+/// blocks ordered by the time they have been seen first
+pub fn reassemble(fragments: impl IntoIterator<Item = ScriptBlockFragment>) -> Vec<ScriptBlock> {
+    let mut blocks: HashMap<String, ScriptBlock> = HashMap::new();
+    for fragment in fragments {
+        match blocks.get_mut(&fragment.script_block_id) {
+            Some(block) => block.add(fragment),
+            None => {
+                blocks.insert(fragment.script_block_id.clone(), fragment.into());
+            }
+        }
+    }
+    let mut blocks: Vec<_> = blocks.into_values().collect();
+    blocks.sort_by(|a, b| {
+        a.first_seen
+            .cmp(&b.first_seen)
+            .then_with(|| a.script_block_id.cmp(&b.script_block_id))
+    });
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::{reassemble, ScriptBlockFragment};
+
+    fn fragment(number: u64, text: &str) -> ScriptBlockFragment {
+        ScriptBlockFragment {
+            timestamp: Utc::now(),
+            computer: "client".to_owned(),
+            user_id: None,
+            script_block_id: "6e8f4a2b-33c1-4a4e-9b8e-0c2e5f1d7a90".to_owned(),
+            path: None,
+            message_number: number,
+            message_total: 4,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn reassemble_fragments() {
+        let blocks = reassemble(vec![
+            fragment(2, "b"),
+            fragment(1, "a"),
+            fragment(4, "d"),
+            fragment(1, "x"),
+        ]);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].fragments_found(), 3);
+        assert_eq!(blocks[0].missing_fragments(), vec![3]);
+        assert_eq!(
+            blocks[0].text(),
+            "ab\n# ---- fragment 3 of 4 is missing ----\nd"
+        );
+        assert_eq!(
+            blocks[0].file_name(),
+            "6e8f4a2b-33c1-4a4e-9b8e-0c2e5f1d7a90.ps1"
+        );
+    }
+
+    #[test]
+    fn malformed_message_total() {
+        let mut fragments = vec![fragment(1, "a"), fragment(2, "b")];
+        fragments[1].message_total = u64::MAX;
+        let blocks = reassemble(fragments);
+        assert!(blocks[0].is_malformed());
+        assert!(blocks[0].missing_fragments().is_empty());
+        assert_eq!(blocks[0].text(), "ab");
+
+        let blocks = reassemble(vec![fragment(1, "a"), fragment(5, "e")]);
+        assert!(blocks[0].is_malformed());
+        assert_eq!(blocks[0].missing_fragments(), vec![2, 3, 4]);
+
+        let blocks = reassemble(vec![fragment(1, "a")]);
+        assert!(!blocks[0].is_malformed());
+        assert_eq!(blocks[0].missing_fragments(), vec![2, 3, 4]);
+    }
+}
@@ -0,0 +1,137 @@
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use dfirtk_eventdata::SessionId;
+use serde_json::{json, Value};
+
+use crate::{
+    pstree::{display_markdown, labels::Labels, parse_logon_id, process_tree::ProcessTree},
+    sessions::{find_evtx_files_of_channels, Session, SessionEventRules, SessionStore},
+};
+
+static PROCESS_CHANNELS: &[&str] = &["Security", "Microsoft-Windows-Sysmon/Operational"];
+
+#[derive(ValueEnum, Clone)]
+pub enum SessionPsTreeFormat {
+    Json,
+    Markdown,
+}
+
+/// displays all logon sessions together with the processes which have been
+/// started in them
+pub(crate) fn display_session_pstree(
+    evtx_files_dirs: &[PathBuf],
+    include_anonymous: bool,
+    rules: Option<&Path>,
+    session_id: Option<&String>,
+    format: &SessionPsTreeFormat,
+    labels: &Labels,
+) -> anyhow::Result<()> {
+    let rules = SessionEventRules::load_or_default(rules)?;
+    let sessions = SessionStore::import(evtx_files_dirs, include_anonymous, &rules)?;
+
+    let mut tree = ProcessTree::default();
+    for evtx_file in find_evtx_files_of_channels(evtx_files_dirs, PROCESS_CHANNELS)? {
+        log::info!("reading processes from {}", evtx_file.display());
+        tree.import(&evtx_file, |_| true)?;
+    }
+    tree.build();
+    log::warn!("found {} process creations", tree.len());
+
+    let mut json_sessions = Vec::new();
+    for session in sessions {
+        let (logon_id_str, logon_id) = match session.session_id() {
+            SessionId::LogonId(id) => match parse_logon_id(id) {
+                Some(logon_id) => (id.clone(), logon_id),
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        if let Some(session_id) = session_id {
+            if parse_logon_id(session_id) != Some(logon_id) {
+                continue;
+            }
+        }
+
+        let processes = tree.session_roots(session.computer(), logon_id);
+
+        // sessions without any processes are only of interest
+        // if the user asked for them
+        if processes.is_empty() && session_id.is_none() {
+            continue;
+        }
+
+        match format {
+            SessionPsTreeFormat::Json => {
+                let processes: Vec<_> = processes
+                    .iter()
+                    .filter_map(|p| p.upgrade().map(|p| Value::from(&*p.borrow())))
+                    .collect();
+                json_sessions.push(json!({
+                    "session_id": logon_id_str,
+                    "computer": session.computer(),
+                    "begin": session.first_event().record().timestamp,
+                    "end": session.last_event().record().timestamp,
+                    "domain": session.domain(),
+                    "usernames": session.usernames().collect::<Vec<_>>(),
+                    "logon_type": session.logon_type(),
+                    "source_ip": session.source_ip(),
+                    "processes": processes,
+                }));
+            }
+            SessionPsTreeFormat::Markdown => {
+                display_session_markdown(&logon_id_str, &session, labels);
+                if processes.is_empty() {
+                    println!("{}", labels.no_processes);
+                } else {
+                    display_markdown(&processes, 0, labels);
+                }
+                println!();
+            }
+        }
+    }
+
+    if matches!(format, SessionPsTreeFormat::Json) {
+        println!("{}", serde_json::to_string_pretty(&json_sessions)?);
+    }
+    Ok(())
+}
+
+fn display_session_markdown(logon_id: &str, session: &Session, labels: &Labels) {
+    println!(
+        "## {} `{}` (*`{}`* - *`{}`*)",
+        labels.session,
+        logon_id,
+        session
+            .first_event()
+            .record()
+            .timestamp
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        session
+            .last_event()
+            .record()
+            .timestamp
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+    );
+    println!();
+
+    println!("- {}: `{}`", labels.computer, session.computer());
+    let usernames: Vec<_> = session
+        .usernames()
+        .map(|u| match session.domain() {
+            Some(domain) => format!("`{domain}\\{u}`"),
+            None => format!("`{u}`"),
+        })
+        .collect();
+    if !usernames.is_empty() {
+        println!("- {}: {}", labels.user, usernames.join(", "));
+    }
+    if let Some(logon_type) = session.logon_type() {
+        println!("- {}: `{logon_type}`", labels.logon_type);
+    }
+    if let Some(source_ip) = session.source_ip() {
+        println!("- {}: `{source_ip}`", labels.source_ip);
+    }
+    println!();
+}
@@ -6,10 +6,10 @@ use super::SessionEvent;
 
 #[derive(Serialize)]
 pub struct EventAsCsv {
-    level: Option<EventLevel>,
-    timestamp: DateTime<Utc>,
-    event_id: EventId,
-    description: String,
+    pub level: Option<EventLevel>,
+    pub timestamp: DateTime<Utc>,
+    pub event_id: EventId,
+    pub description: String,
 
     pub domain: Option<String>,
 
@@ -0,0 +1,123 @@
+use dfirtk_eventdata::{EventProvider, LogonType};
+use serde::Serialize;
+
+use super::SessionEvent;
+use crate::helpers::EventData;
+
+/// details of a successful logon, found in Security 4624
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct LogonDetails {
+    pub logon_type: Option<LogonType>,
+    pub authentication_package: Option<String>,
+    pub lm_package_name: Option<String>,
+
+    /// `true` if NTLM V1 has been used, which should not be the case in modern networks
+    pub ntlm_v1: bool,
+    pub elevated_token: Option<bool>,
+    pub impersonation_level: Option<String>,
+    pub ip_address: Option<String>,
+    pub ip_port: Option<u16>,
+    pub process_name: Option<String>,
+}
+
+impl LogonDetails {
+    /// returns `None` if `event` is no Security 4624
+    pub fn try_from(event: &SessionEvent) -> Option<Self> {
+        if event.event_type().provider() != EventProvider::SecurityAuditing
+            || event.event_type().event_id().value() != 4624
+        {
+            return None;
+        }
+
+        let event_data = EventData::of(event.record());
+
+        let lm_package_name = event_data.field("LmPackageName");
+        let ip_port = match event_data.value("IpPort").as_u64() {
+            Some(port) => u16::try_from(port).ok(),
+            None => event_data
+                .field("IpPort")
+                .and_then(|port| port.parse().ok()),
+        }
+        .filter(|port| *port != 0);
+
+        Some(Self {
+            logon_type: LogonType::from_value(event_data.value("LogonType")),
+            authentication_package: event_data.field("AuthenticationPackageName"),
+            ntlm_v1: lm_package_name.as_deref() == Some("NTLM V1"),
+            lm_package_name,
+            elevated_token: event_data
+                .field("ElevatedToken")
+                .and_then(|t| yes_or_no(&t)),
+            impersonation_level: event_data
+                .field("ImpersonationLevel")
+                .map(|l| impersonation_level(&l)),
+            ip_address: event_data.field("IpAddress"),
+            ip_port,
+            process_name: event_data.field("ProcessName"),
+        })
+    }
+}
+
+/// decodes the message ids used by Security 4624
+fn yes_or_no(value: &str) -> Option<bool> {
+    match value {
+        "%%1842" => Some(true),
+        "%%1843" => Some(false),
+        _ => None,
+    }
+}
+
+/// decodes the message ids used by Security 4624
+fn impersonation_level(value: &str) -> String {
+    match value {
+        "%%1831" => "Anonymous",
+        "%%1832" => "Identification",
+        "%%1833" => "Impersonation",
+        "%%1840" => "Delegation",
+        value => value,
+    }
+    .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use dfirtk_eventdata::LogonType;
+    use serde_json::json;
+
+    use super::LogonDetails;
+    use crate::{helpers::record, sessions::SessionEvent};
+
+    #[test]
+    fn decode_4624() {
+        let record = record(
+            "Microsoft-Windows-Security-Auditing",
+            4624,
+            "server",
+            json!({
+                "TargetUserName": "alice",
+                "TargetLogonId": "0x1234",
+                "LogonType": 3,
+                "LogonProcessName": "NtLmSsp ",
+                "AuthenticationPackageName": "NTLM",
+                "LmPackageName": "NTLM V1",
+                "ElevatedToken": "%%1842",
+                "ImpersonationLevel": "%%1833",
+                "IpAddress": "10.0.0.5",
+                "IpPort": "49712",
+                "ProcessName": "-",
+            }),
+        );
+        let event = SessionEvent::try_from(record).unwrap();
+        let details = LogonDetails::try_from(&event).unwrap();
+        assert_eq!(details.logon_type, Some(LogonType::Network));
+        assert_eq!(details.authentication_package.as_deref(), Some("NTLM"));
+        assert!(details.ntlm_v1);
+        assert_eq!(details.elevated_token, Some(true));
+        assert_eq!(
+            details.impersonation_level.as_deref(),
+            Some("Impersonation")
+        );
+        assert_eq!(details.ip_port, Some(49712));
+        assert!(details.process_name.is_none());
+    }
+}
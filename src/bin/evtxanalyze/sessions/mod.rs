@@ -2,16 +2,26 @@ mod session;
 mod session_store;
 mod session_event;
 mod session_event_templates;
+mod session_event_rules;
 mod session_event_error;
 mod session_as_json;
 mod session_as_csv;
+mod session_as_bodyfile;
+mod session_lifecycle;
+mod session_correlator;
+mod logon_details;
 mod event_as_csv;
 mod active_directory_domain_name;
 pub use session::*;
 pub use session_store::*;
 pub use session_event::*;
 pub use session_event_error::*;
+pub use session_event_rules::*;
 pub use session_as_json::*;
 pub use session_as_csv::*;
+pub use session_as_bodyfile::*;
+pub use session_lifecycle::*;
+pub use session_correlator::*;
+pub use logon_details::*;
 pub use event_as_csv::*;
 pub use active_directory_domain_name::*;
\ No newline at end of file
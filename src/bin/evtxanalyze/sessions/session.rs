@@ -3,8 +3,11 @@ use std::{
     io::Write,
 };
 
-use super::{ActiveDirectoryDomainName, SessionAsCsv, SessionAsJson, SessionEvent};
-use dfirtk_eventdata::SessionId;
+use super::{
+    ActiveDirectoryDomainName, LifecycleEntry, LogonDetails, SessionAsCsv, SessionAsJson, SessionEvent,
+    SessionEventError, SessionLifecycleStep,
+};
+use dfirtk_eventdata::{EventProvider, LogonType, SessionId};
 
 pub struct Session {
     events: BTreeSet<SessionEvent>,
@@ -14,6 +17,7 @@ pub struct Session {
     clients: HashSet<String>,
     server: Option<String>,
     computer: String,
+    related_sessions: Vec<SessionId>,
 }
 
 impl Session {
@@ -25,31 +29,117 @@ impl Session {
         self.events.iter()
     }
 
-    pub fn add_event(&mut self, event: SessionEvent) {
+    pub fn domain(&self) -> Option<&ActiveDirectoryDomainName> {
+        self.domain.as_ref()
+    }
+
+    pub fn usernames(&self) -> impl Iterator<Item = &String> {
+        self.usernames.iter()
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = &String> {
+        self.clients.iter()
+    }
+
+    pub fn computer(&self) -> &str {
+        &self.computer
+    }
+
+    /// returns `true` if this session has the id `index` or if a session
+    /// with this id has been merged into this session
+    pub fn has_id(&self, index: &str) -> bool {
+        std::iter::once(&self.session_id)
+            .chain(self.related_sessions.iter())
+            .any(|id| id.to_string() == index)
+    }
+
+    /// all client addresses found in the events of this session
+    pub fn client_addresses(&self) -> HashSet<String> {
+        self.events
+            .iter()
+            .filter_map(|e| e.event_type().client_address(e.record()))
+            .chain(self.source_ip())
+            .filter(|addr| !addr.is_empty() && addr != "-")
+            .collect()
+    }
+
+    /// all terminal services session numbers found in the events of this session
+    pub fn terminal_session_numbers(&self) -> HashSet<String> {
+        self.events
+            .iter()
+            .filter_map(|e| {
+                let id = &e.record().data["Event"]["UserData"]["EventXML"]["SessionID"];
+                match id.as_u64() {
+                    Some(id) => Some(id.to_string()),
+                    None => id.as_str().map(|id| id.to_owned()),
+                }
+            })
+            .collect()
+    }
+
+    /// the events of this session which document a phase of its lifecycle
+    pub fn lifecycle(&self) -> impl Iterator<Item = (&SessionEvent, SessionLifecycleStep)> {
+        self.events
+            .iter()
+            .filter_map(|e| SessionLifecycleStep::of(e).map(|step| (e, step)))
+    }
+
+    /// merges the events of `other` into this session
+    pub fn merge(&mut self, other: Session) {
+        self.related_sessions.push(other.session_id);
+        self.related_sessions.extend(other.related_sessions);
+        self.usernames.extend(other.usernames);
+        self.clients.extend(other.clients);
+        if self.domain.is_none() {
+            self.domain = other.domain;
+        }
+        if self.server.is_none() {
+            self.server = other.server;
+        }
+        self.events.extend(other.events);
+    }
+
+    /// the first successful logon (Security 4624) of this session
+    fn logon_event(&self) -> Option<&SessionEvent> {
+        self.events.iter().find(|e| {
+            e.event_type().provider() == EventProvider::SecurityAuditing
+                && e.event_type().event_id().value() == 4624
+        })
+    }
+
+    /// the details of the first successful logon (Security 4624) of this session
+    pub fn logon_details(&self) -> Option<LogonDetails> {
+        LogonDetails::try_from(self.logon_event()?)
+    }
+
+    /// the logon type found in 4624
+    pub fn logon_type(&self) -> Option<LogonType> {
+        self.logon_details()?.logon_type
+    }
+
+    /// the source ip address found in 4624, if any
+    pub fn source_ip(&self) -> Option<String> {
+        self.logon_details()?.ip_address
+    }
+
+    /// adds `event` to this session. If the event contradicts the data
+    /// already found in this session, the event is not added.
+    pub fn add_event(&mut self, event: SessionEvent) -> Result<(), SessionEventError> {
         assert_eq!(event.session_id(), &self.session_id);
         let mut domain_from_username = None;
-        let username;
+        let mut username = None;
 
         if let Some(u) = event.event_type().username(event.record()) {
-            if u.contains('\\') {
-                let mut parts: Vec<_> = u.split('\\').map(|s| s.to_owned()).collect();
-                assert_eq!(parts.len(), 2);
-                username = parts.pop().unwrap();
-                domain_from_username = Some(parts.pop().unwrap());
-            } else {
-                username = u;
-            }
-            self.usernames.insert(username);
-        }
-
-        if let Some(addr) = event.event_type().client_address(event.record()) {
-            if let Some(hostname) = event.event_type().client_hostname(event.record()) {
-                self.clients.insert(format!("{hostname}({addr})"));
-            } else {
-                self.clients.insert(addr);
+            match u.split_once('\\') {
+                Some((_, user)) if user.contains('\\') => {
+                    return Err(SessionEventError::InvalidUsername(u))
+                }
+                Some((domain, user)) => {
+                    domain_from_username = Some(domain.to_owned());
+                    username = Some(user.to_owned());
+                }
+                None => username = Some(u),
             }
-        } else if let Some(hostname) = event.event_type().client_hostname(event.record()) {
-            self.clients.insert(hostname);
         }
 
         let server = if let Some(addr) = event.event_type().server_address(event.record()) {
@@ -62,36 +152,65 @@ impl Session {
             event.event_type().server_hostname(event.record())
         };
 
-        if let Some(server) = server {
-            match &self.server {
-                None => self.server = Some(server),
-                Some(s) => {
-                    assert_eq!(s, &server, "multiple servers on one single connection are not supported: {s} != {server}");
-                }
+        if let (Some(s), Some(server)) = (&self.server, &server) {
+            if s != server {
+                return Err(SessionEventError::ConflictingServers(
+                    s.clone(),
+                    server.clone(),
+                ));
             }
         }
 
-        if let Some(domain_from_record) = event.event_type().domain(event.record()) {
-            let domain = match domain_from_username {
+        let domain = match event.event_type().domain(event.record()) {
+            Some(domain_from_record) => match domain_from_username {
                 Some(domain) if !domain.is_empty() => {
-                    assert_eq!(domain, domain_from_record, "multiple domains on one single connection are not supported: {domain} != {domain_from_record}");
+                    if ActiveDirectoryDomainName::from(domain.as_str())
+                        != ActiveDirectoryDomainName::from(domain_from_record.as_str())
+                    {
+                        return Err(SessionEventError::ConflictingDomains(
+                            domain,
+                            domain_from_record,
+                        ));
+                    }
                     Some(ActiveDirectoryDomainName::from(domain))
                 }
-                _ if ! domain_from_record.is_empty() => Some(ActiveDirectoryDomainName::from(domain_from_record)),
-                _ => None
-            };
-
-            match &self.domain {
-                None => self.domain = domain,
-                Some(d) => if let Some(new_domain) = domain {
-                    if d != &new_domain {
-                        log::warn!("multiple domains on one single connection are not supported: {d} != {new_domain}, failed event was {event}", event = event.record().data);
-                    }
+                _ if !domain_from_record.is_empty() => {
+                    Some(ActiveDirectoryDomainName::from(domain_from_record))
+                }
+                _ => None,
+            },
+            None => None,
+        };
+
+        if let Some(username) = username {
+            self.usernames.insert(username);
+        }
+
+        if let Some(addr) = event.event_type().client_address(event.record()) {
+            if let Some(hostname) = event.event_type().client_hostname(event.record()) {
+                self.clients.insert(format!("{hostname}({addr})"));
+            } else {
+                self.clients.insert(addr);
+            }
+        } else if let Some(hostname) = event.event_type().client_hostname(event.record()) {
+            self.clients.insert(hostname);
+        }
+
+        if self.server.is_none() {
+            self.server = server;
+        }
+
+        match &self.domain {
+            None => self.domain = domain,
+            Some(d) => if let Some(new_domain) = domain {
+                if d != &new_domain {
+                    log::warn!("multiple domains on one single connection are not supported: {d} != {new_domain}, failed event was {event}", event = event.record().data);
                 }
             }
         }
 
         self.events.insert(event);
+        Ok(())
     }
 
     pub fn first_event(&self) -> &SessionEvent {
@@ -120,8 +239,10 @@ impl Session {
     }
 }
 
-impl From<SessionEvent> for Session {
-    fn from(value: SessionEvent) -> Self {
+impl TryFrom<SessionEvent> for Session {
+    type Error = SessionEventError;
+
+    fn try_from(value: SessionEvent) -> Result<Self, Self::Error> {
         log::trace!(
             "creating new session, starting at {}",
             value.record().timestamp
@@ -130,9 +251,9 @@ impl From<SessionEvent> for Session {
         let events = BTreeSet::<SessionEvent>::new();
         let session_id = (*value.session_id()).clone();
 
-        let computer = value.record().data["Event"]["System"]["Computer"]
-            .as_str()
-            .unwrap()
+        let computer = value
+            .computer()
+            .ok_or(SessionEventError::MissingComputer)?
             .to_owned();
 
         let mut me = Self {
@@ -143,10 +264,11 @@ impl From<SessionEvent> for Session {
             clients: HashSet::new(),
             server: None,
             computer,
+            related_sessions: Vec::new(),
         };
 
-        me.add_event(value);
-        me
+        me.add_event(value)?;
+        Ok(me)
     }
 }
 
@@ -178,12 +300,29 @@ impl Into<SessionAsJson> for Session {
         let duration = end - begin;
         let session_id = self.session_id().clone();
         let events = self.events.len();
+        let lifecycle = self
+            .lifecycle()
+            .map(|(event, step)| LifecycleEntry {
+                timestamp: event.record().timestamp,
+                step,
+                event_id: event.event_type().event_id().value(),
+                description: event.event_type().description().to_owned(),
+            })
+            .collect();
+        let logon = self.logon_details();
         SessionAsJson {
             begin,
             end,
             duration,
             session_id,
             usernames: self.usernames,
+            domain: self.domain,
+            clients: self.clients,
+            server: self.server,
+            computer: self.computer,
+            related_sessions: self.related_sessions,
+            lifecycle,
+            logon,
             events,
         }
     }
@@ -197,6 +336,9 @@ impl Into<SessionAsCsv> for Session {
         let duration = end - begin;
         let session_id = self.session_id().clone();
         let events = self.events.len();
+        let related_sessions: Vec<_> = self.related_sessions.iter().map(|s| s.to_string()).collect();
+        let lifecycle: Vec<_> = self.lifecycle().map(|(_, step)| step.to_string()).collect();
+        let logon = self.logon_details().unwrap_or_default();
         let usernames: Vec<_> = self.usernames.into_iter().collect();
         let clients: Vec<_> = self.clients.into_iter().collect();
         SessionAsCsv {
@@ -209,6 +351,17 @@ impl Into<SessionAsCsv> for Session {
             clients: clients.join(", "),
             server: self.server,
             computer: self.computer,
+            related_sessions: related_sessions.join(", "),
+            lifecycle: lifecycle.join(", "),
+            logon_type: logon.logon_type,
+            authentication_package: logon.authentication_package,
+            lm_package_name: logon.lm_package_name,
+            ntlm_v1: logon.ntlm_v1,
+            elevated_token: logon.elevated_token,
+            impersonation_level: logon.impersonation_level,
+            ip_address: logon.ip_address,
+            ip_port: logon.ip_port,
+            process_name: logon.process_name,
             events,
         }
     }
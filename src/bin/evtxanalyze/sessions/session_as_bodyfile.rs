@@ -0,0 +1,146 @@
+use dfir_toolkit::common::bodyfile::{Bodyfile3Line, Modified};
+use serde_json::json;
+
+use super::{Session, SessionLifecycleStep};
+
+/// the timeline entries of a session: its begin, its end and every
+/// step of its lifecycle (e.g. logon, disconnect and reconnect)
+pub struct SessionAsBodyfile(Vec<Bodyfile3Line>);
+
+impl From<&Session> for SessionAsBodyfile {
+    fn from(session: &Session) -> Self {
+        let first = session.first_event();
+        let last = session.last_event();
+
+        let mut usernames: Vec<_> = session.usernames().collect();
+        usernames.sort();
+        let mut clients: Vec<_> = session.clients().collect();
+        clients.sort();
+
+        let lines = session
+            .iter_events()
+            .filter_map(|event| {
+                let action = match SessionLifecycleStep::of(event) {
+                    Some(step) => format!("session {step}"),
+                    None if std::ptr::eq(event, first) => "session begin".to_owned(),
+                    None if std::ptr::eq(event, last) => "session end".to_owned(),
+                    None => return None,
+                };
+
+                let name = json!({
+                    "action": action,
+                    "session_id": event.session_id(),
+                    "event_id": event.event_type().event_id(),
+                    "description": event.event_type().description(),
+                    "domain": session.domain(),
+                    "usernames": usernames,
+                    "clients": clients,
+                    "computer": session.computer(),
+                });
+                Some(
+                    Bodyfile3Line::new()
+                        .with_mtime(Modified::from(event.record().timestamp.timestamp()))
+                        .with_owned_name(name.to_string()),
+                )
+            })
+            .collect();
+        Self(lines)
+    }
+}
+
+impl IntoIterator for SessionAsBodyfile {
+    type Item = Bodyfile3Line;
+
+    type IntoIter = std::vec::IntoIter<Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use evtx::SerializedEvtxRecord;
+    use serde_json::{json, Value};
+
+    use super::SessionAsBodyfile;
+    use crate::{
+        helpers::record,
+        sessions::{Session, SessionEvent},
+    };
+
+    fn event(record_id: u64, event_id: u64, event_data: Value) -> SessionEvent {
+        SessionEvent::try_from(SerializedEvtxRecord {
+            event_record_id: record_id,
+            timestamp: Utc
+                .timestamp_opt(1_700_000_000 + record_id as i64, 0)
+                .unwrap(),
+            ..record(
+                "Microsoft-Windows-Security-Auditing",
+                event_id,
+                "server",
+                event_data,
+            )
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn lifecycle_lines() {
+        let mut session = Session::try_from(event(
+            1,
+            4627,
+            json!({"TargetUserName": "alice", "TargetLogonId": "0x1234"}),
+        ))
+        .unwrap();
+        let station =
+            json!({"AccountName": "alice", "LogonID": "0x1234", "ClientAddress": "10.0.0.5"});
+        session.add_event(event(2, 4779, station.clone())).unwrap();
+        session.add_event(event(3, 4778, station)).unwrap();
+        session
+            .add_event(event(
+                4,
+                4627,
+                json!({"TargetUserName": "alice", "TargetLogonId": "0x1234"}),
+            ))
+            .unwrap();
+
+        let lines: Vec<_> = SessionAsBodyfile::from(&session)
+            .into_iter()
+            .map(|line| {
+                let name: Value = serde_json::from_str(line.get_name()).unwrap();
+                (
+                    *line.get_mtime(),
+                    name["action"].as_str().unwrap().to_owned(),
+                    name["event_id"].as_u64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (1_700_000_001.into(), "session begin".to_owned(), 4627),
+                (1_700_000_002.into(), "session disconnect".to_owned(), 4779),
+                (1_700_000_003.into(), "session reconnect".to_owned(), 4778),
+                (1_700_000_004.into(), "session end".to_owned(), 4627),
+            ]
+        );
+    }
+
+    #[test]
+    fn steps_without_begin_and_end() {
+        let session = Session::try_from(event(
+            1,
+            4624,
+            json!({"TargetUserName": "alice", "TargetLogonId": "0x1234"}),
+        ))
+        .unwrap();
+        let actions: Vec<_> = SessionAsBodyfile::from(&session)
+            .into_iter()
+            .map(|line| line.get_name().to_owned())
+            .collect();
+        assert_eq!(actions.len(), 1);
+        assert!(actions[0].contains(r#""action":"session logon""#));
+    }
+}
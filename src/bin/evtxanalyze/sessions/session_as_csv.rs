@@ -1,5 +1,5 @@
 use chrono::{DateTime, Duration, Utc};
-use dfirtk_eventdata::SessionId;
+use dfirtk_eventdata::{LogonType, SessionId};
 use serde::{Serialize, Serializer};
 
 use super::ActiveDirectoryDomainName;
@@ -27,6 +27,28 @@ pub struct SessionAsCsv {
 
     pub session_id: SessionId,
 
+    pub related_sessions: String,
+
+    pub lifecycle: String,
+
+    pub logon_type: Option<LogonType>,
+
+    pub authentication_package: Option<String>,
+
+    pub lm_package_name: Option<String>,
+
+    pub ntlm_v1: bool,
+
+    pub elevated_token: Option<bool>,
+
+    pub impersonation_level: Option<String>,
+
+    pub ip_address: Option<String>,
+
+    pub ip_port: Option<u16>,
+
+    pub process_name: Option<String>,
+
     pub events: usize,
 }
 
@@ -5,6 +5,8 @@ use serde::{Serialize, Serializer};
 
 use dfirtk_eventdata::SessionId;
 
+use super::{ActiveDirectoryDomainName, LogonDetails, SessionLifecycleStep};
+
 #[derive(Serialize, Debug)]
 pub struct SessionAsJson {
     pub begin: DateTime<Utc>,
@@ -18,9 +20,34 @@ pub struct SessionAsJson {
 
     pub session_id: SessionId,
 
+    pub domain: Option<ActiveDirectoryDomainName>,
+
+    #[serde(skip_serializing_if = "HashSet::is_empty")]
+    pub clients: HashSet<String>,
+
+    pub server: Option<String>,
+
+    pub computer: String,
+
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub related_sessions: Vec<SessionId>,
+
+    pub lifecycle: Vec<LifecycleEntry>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logon: Option<LogonDetails>,
+
     pub events: usize,
 }
 
+#[derive(Serialize, Debug)]
+pub struct LifecycleEntry {
+    pub timestamp: DateTime<Utc>,
+    pub step: SessionLifecycleStep,
+    pub event_id: u16,
+    pub description: String,
+}
+
 fn serialize_duration<S>(duration: &Duration, s: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -0,0 +1,286 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Duration, Utc};
+use dfirtk_eventdata::{LogonType, SessionId};
+
+use super::Session;
+
+/// merges the sessions which belong to one single RDP logon into one
+/// logical session. Terminal services and RdpCoreTS events are keyed by
+/// their `ActivityId`, while Security events are keyed by their `LogonId`.
+/// Every RDP logon (4624 with logon type 10) is used as anchor, to which
+/// the `ActivityId` based sessions are assigned by time proximity, username,
+/// client address and terminal services session number. Sessions of
+/// different computers are never merged.
+pub struct SessionCorrelator {
+    window: Duration,
+}
+
+impl Default for SessionCorrelator {
+    fn default() -> Self {
+        Self {
+            window: Duration::seconds(60),
+        }
+    }
+}
+
+struct SessionProperties {
+    computer: String,
+    begin: DateTime<Utc>,
+    end: DateTime<Utc>,
+    usernames: HashSet<String>,
+    addresses: HashSet<String>,
+    session_numbers: HashSet<String>,
+}
+
+impl From<&Session> for SessionProperties {
+    fn from(session: &Session) -> Self {
+        Self {
+            computer: session.computer().to_lowercase(),
+            begin: session.first_event().record().timestamp,
+            end: session.last_event().record().timestamp,
+            usernames: session.usernames().map(|u| u.to_lowercase()).collect(),
+            addresses: session.client_addresses(),
+            session_numbers: session.terminal_session_numbers(),
+        }
+    }
+}
+
+enum Similarity {
+    Conflict,
+    Unknown,
+    Match,
+}
+
+fn compare(lhs: &HashSet<String>, rhs: &HashSet<String>) -> Similarity {
+    if lhs.is_empty() || rhs.is_empty() {
+        Similarity::Unknown
+    } else if lhs.is_disjoint(rhs) {
+        Similarity::Conflict
+    } else {
+        Similarity::Match
+    }
+}
+
+impl SessionCorrelator {
+    pub fn correlate(&self, sessions: impl IntoIterator<Item = Session>) -> Vec<Session> {
+        let mut anchors = Vec::new();
+        let mut candidates = Vec::new();
+        let mut result = Vec::new();
+
+        for session in sessions {
+            match session.session_id() {
+                SessionId::LogonId(_) if session.logon_type() == Some(LogonType::RemoteInteractive) => {
+                    anchors.push(session)
+                }
+                SessionId::ActivityId(id) if !id.is_empty() => candidates.push(Some(session)),
+                _ => result.push(session),
+            }
+        }
+
+        let mut anchor_properties: Vec<_> = anchors.iter().map(SessionProperties::from).collect();
+        let candidate_properties: Vec<_> = candidates
+            .iter()
+            .map(|c| SessionProperties::from(c.as_ref().unwrap()))
+            .collect();
+        let mut assignments = vec![None; candidates.len()];
+
+        // first, use usernames and client addresses
+        for (idx, candidate) in candidate_properties.iter().enumerate() {
+            if candidate.usernames.is_empty() && candidate.addresses.is_empty() {
+                continue;
+            }
+            assignments[idx] = self.find_anchor(&anchor_properties, candidate, |anchor| {
+                let users = compare(&anchor.usernames, &candidate.usernames);
+                let addresses = compare(&anchor.addresses, &candidate.addresses);
+                match (users, addresses) {
+                    (Similarity::Conflict, _) | (_, Similarity::Conflict) => false,
+                    (Similarity::Match, _) | (_, Similarity::Match) => true,
+                    _ => false,
+                }
+            });
+            if let Some(anchor) = assignments[idx] {
+                let anchor: &mut SessionProperties = &mut anchor_properties[anchor];
+                anchor
+                    .session_numbers
+                    .extend(candidate.session_numbers.iter().cloned());
+            }
+        }
+
+        // then, use the terminal services session numbers for the remaining sessions
+        for (idx, candidate) in candidate_properties.iter().enumerate() {
+            if assignments[idx].is_some() || candidate.session_numbers.is_empty() {
+                continue;
+            }
+            assignments[idx] = self.find_anchor(&anchor_properties, candidate, |anchor| {
+                matches!(
+                    compare(&anchor.session_numbers, &candidate.session_numbers),
+                    Similarity::Match
+                ) && !matches!(
+                    compare(&anchor.usernames, &candidate.usernames),
+                    Similarity::Conflict
+                ) && !matches!(
+                    compare(&anchor.addresses, &candidate.addresses),
+                    Similarity::Conflict
+                )
+            });
+        }
+
+        for (candidate, assignment) in candidates.iter_mut().zip(assignments) {
+            let candidate = candidate.take().unwrap();
+            match assignment {
+                Some(anchor) => anchors[anchor].merge(candidate),
+                None => result.push(candidate),
+            }
+        }
+
+        result.extend(anchors);
+        result.sort();
+        result
+    }
+
+    /// finds the anchor which is most close to `candidate` and for which
+    /// `is_similar` returns `true`
+    fn find_anchor<F>(
+        &self,
+        anchors: &[SessionProperties],
+        candidate: &SessionProperties,
+        is_similar: F,
+    ) -> Option<usize>
+    where
+        F: Fn(&SessionProperties) -> bool,
+    {
+        anchors
+            .iter()
+            .enumerate()
+            .filter(|(_, anchor)| {
+                anchor.computer == candidate.computer
+                    && candidate.begin >= anchor.begin - self.window
+                    && candidate.begin <= anchor.end + self.window
+            })
+            .filter(|(_, anchor)| is_similar(anchor))
+            .min_by_key(|(_, anchor)| (candidate.begin - anchor.begin).num_milliseconds().abs())
+            .map(|(idx, _)| idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, TimeZone, Utc};
+    use dfirtk_eventdata::SessionId;
+    use serde_json::{json, Value};
+
+    use super::SessionCorrelator;
+    use crate::{
+        helpers::record,
+        sessions::{Session, SessionEvent},
+    };
+
+    fn event(
+        id: u64,
+        seconds: i64,
+        provider: &str,
+        event_id: u64,
+        activity_id: &str,
+        data: Value,
+    ) -> SessionEvent {
+        event_on("server", id, seconds, provider, event_id, activity_id, data)
+    }
+
+    fn event_on(
+        computer: &str,
+        id: u64,
+        seconds: i64,
+        provider: &str,
+        event_id: u64,
+        activity_id: &str,
+        data: Value,
+    ) -> SessionEvent {
+        let mut record = record(provider, event_id, computer, data);
+        record.event_record_id = id;
+        record.timestamp =
+            Utc.with_ymd_and_hms(2023, 11, 14, 10, 0, 0).unwrap() + Duration::seconds(seconds);
+        let event = &mut record.data["Event"];
+        event["System"]["Correlation"] = json!({"#attributes": {"ActivityID": activity_id}});
+        if provider != "Microsoft-Windows-Security-Auditing" {
+            let data = event.as_object_mut().unwrap().remove("EventData").unwrap();
+            event["UserData"] = json!({ "EventXML": data });
+        }
+        SessionEvent::try_from(record).ok().unwrap()
+    }
+
+    fn sessions(events: Vec<SessionEvent>) -> Vec<Session> {
+        let mut sessions: HashMap<(String, SessionId), Session> = HashMap::new();
+        for event in events {
+            let key = (event.computer().unwrap().to_owned(), event.session_id().clone());
+            match sessions.get_mut(&key) {
+                Some(session) => session.add_event(event).unwrap(),
+                None => {
+                    sessions.insert(key, Session::try_from(event).unwrap());
+                }
+            }
+        }
+        sessions.into_values().collect()
+    }
+
+    #[test]
+    fn correlate_rdp_logon() {
+        const TSRCM: &str = "Microsoft-Windows-TerminalServices-RemoteConnectionManager";
+        const TSLSM: &str = "Microsoft-Windows-TerminalServices-LocalSessionManager";
+        const SECURITY: &str = "Microsoft-Windows-Security-Auditing";
+        let events = vec![
+            event(1, 0, TSRCM, 1149, "{A}", json!({"Param1": "alice", "Param2": "CONTOSO", "Param3": "10.0.0.5"})),
+            event(2, 2, SECURITY, 4624, "", json!({
+                "TargetUserName": "alice",
+                "TargetDomainName": "CONTOSO",
+                "TargetLogonId": "0x1234",
+                "LogonType": 10,
+                "IpAddress": "10.0.0.5",
+                "WorkstationName": "WS",
+            })),
+            event(3, 3, TSLSM, 21, "{B}", json!({"User": "CONTOSO\\alice", "SessionID": 2, "Address": "10.0.0.5"})),
+            event(4, 600, TSLSM, 24, "{C}", json!({"User": "CONTOSO\\alice", "SessionID": 2, "Address": "10.0.0.5"})),
+            event(5, 601, SECURITY, 4634, "", json!({"TargetUserName": "alice", "TargetLogonId": "0x1234"})),
+            event(6, 10, TSRCM, 1149, "{D}", json!({"Param1": "bob", "Param2": "CONTOSO", "Param3": "10.0.0.6"})),
+        ];
+
+        let sessions = SessionCorrelator::default().correlate(sessions(events));
+        assert_eq!(sessions.len(), 2);
+
+        let rdp = sessions
+            .iter()
+            .find(|s| s.has_id("0x1234"))
+            .expect("missing RDP session");
+        assert!(rdp.has_id("{A}"));
+        assert!(rdp.has_id("{B}"));
+        assert!(rdp.has_id("{C}"));
+        assert!(!rdp.has_id("{D}"));
+        let lifecycle: Vec<_> = rdp.lifecycle().map(|(_, step)| step.to_string()).collect();
+        assert_eq!(
+            lifecycle,
+            vec!["authenticate", "logon", "logon", "disconnect", "logoff"]
+        );
+    }
+
+    #[test]
+    fn do_not_correlate_across_computers() {
+        const TSRCM: &str = "Microsoft-Windows-TerminalServices-RemoteConnectionManager";
+        const SECURITY: &str = "Microsoft-Windows-Security-Auditing";
+        let events = vec![
+            event_on("other", 1, 0, TSRCM, 1149, "{A}", json!({"Param1": "alice", "Param2": "CONTOSO", "Param3": "10.0.0.5"})),
+            event(2, 2, SECURITY, 4624, "", json!({
+                "TargetUserName": "alice",
+                "TargetDomainName": "CONTOSO",
+                "TargetLogonId": "0x1234",
+                "LogonType": 10,
+                "IpAddress": "10.0.0.5",
+            })),
+        ];
+
+        let sessions = SessionCorrelator::default().correlate(sessions(events));
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().all(|s| !(s.has_id("0x1234") && s.has_id("{A}"))));
+    }
+}
@@ -1,7 +1,7 @@
-use std::io::Write;
+use std::{io::Write, sync::Arc};
 
 use dfirtk_eventdata::{
-    EventId, EventProvider, SessionId
+    EventId, EventProvider, MissingSessionId, SessionId
 };
 use evtx::SerializedEvtxRecord;
 use serde_json::Value;
@@ -10,7 +10,7 @@ use dfirtk_eventdata::SessionEventInfo;
 use super::EventAsCsv;
 use super::session_event_templates::*;
 
-use super::SessionEventError;
+use super::{SessionEventError, SessionEventRules};
 
 pub struct SessionEvent {
     event_type: Box<dyn SessionEventInfo>,
@@ -19,17 +19,25 @@ pub struct SessionEvent {
 }
 
 impl SessionEvent {
-    fn new<I>(record: SerializedEvtxRecord<serde_json::Value>) -> Self
+    fn new<I>(record: SerializedEvtxRecord<serde_json::Value>) -> Result<Self, SessionEventError>
     where
         I: SessionEventInfo + Default + 'static,
     {
-        let event_type = Box::<I>::default();
-        let session_id = event_type.generate_id(&record);
-        Self {
+        Self::with_event_type(Box::<I>::default(), record)
+    }
+
+    fn with_event_type(
+        event_type: Box<dyn SessionEventInfo>,
+        record: SerializedEvtxRecord<serde_json::Value>,
+    ) -> Result<Self, SessionEventError> {
+        let session_id = event_type
+            .generate_id(&record)
+            .map_err(|MissingSessionId(field)| SessionEventError::MissingSessionId(field))?;
+        Ok(Self {
             event_type,
             record,
             session_id,
-        }
+        })
     }
 
     pub fn event_type(&self) -> &dyn SessionEventInfo {
@@ -44,6 +52,11 @@ impl SessionEvent {
         &self.session_id
     }
 
+    /// the name of the computer which logged this event
+    pub fn computer(&self) -> Option<&str> {
+        self.record.data["Event"]["System"]["Computer"].as_str()
+    }
+
     pub fn to_csv<W>(&self, writer: &mut csv::Writer<W>) -> csv::Result<()>
     where
         W: Write,
@@ -56,37 +69,54 @@ impl TryFrom<SerializedEvtxRecord<serde_json::Value>> for SessionEvent {
     type Error = SessionEventError;
 
     fn try_from(record: SerializedEvtxRecord<serde_json::Value>) -> Result<Self, Self::Error> {
+        Self::try_from_record(record, &SessionEventRules::default())
+    }
+}
+
+impl SessionEvent {
+    /// creates a session event from `record`, using the definitions of `rules`
+    /// or, if there is no matching definition, the builtin definitions
+    pub fn try_from_record(
+        record: SerializedEvtxRecord<serde_json::Value>,
+        rules: &SessionEventRules,
+    ) -> Result<Self, SessionEventError> {
         let event_id = EventId::try_from(&record)?;
-        let provider = EventProvider::try_from(&record)?;
+        let provider_name = EventProvider::name_of(&record)
+            .ok_or_else(|| anyhow::anyhow!("missing provider name in event"))?;
+        if let Some(definition) = rules.find(provider_name, &event_id) {
+            return Self::with_event_type(Box::new(Arc::clone(definition)), record);
+        }
+        let provider = EventProvider::try_from(provider_name)?;
+
         let event = match provider {
             EventProvider::TerminalServicesRemoteConnectionManager => match event_id.value() {
-                1149 => Self::new::<TSRCMUserAuthenticationSucceeded>(record),
+                1149 => Self::new::<TSRCMUserAuthenticationSucceeded>(record)?,
                 _ => return Err(SessionEventError::NoSessionEvent),
             },
             EventProvider::TerminalServicesLocalSessionManager => match event_id.value() {
-                21 => Self::new::<TSLCMSessionLogonSucceeded>(record),
-                22 => Self::new::<TSLCMShellStartNotificationReceived>(record),
-                23 => Self::new::<TSLCMSessionLogoffSucceeded>(record),
-                24 => Self::new::<TSLCMSessionHasBeenDisconnected>(record),
-                25 => Self::new::<TSLCMSessionReconnectionSucceeded>(record),
-                39 => Self::new::<TSLCMSessionXHasBeenDisconnectedBySessionY>(record),
-                40 => Self::new::<TSLCMSessionXHasBeenDisconnectedReasonCodeZ>(record),
+                21 => Self::new::<TSLCMSessionLogonSucceeded>(record)?,
+                22 => Self::new::<TSLCMShellStartNotificationReceived>(record)?,
+                23 => Self::new::<TSLCMSessionLogoffSucceeded>(record)?,
+                24 => Self::new::<TSLCMSessionHasBeenDisconnected>(record)?,
+                25 => Self::new::<TSLCMSessionReconnectionSucceeded>(record)?,
+                39 => Self::new::<TSLCMSessionXHasBeenDisconnectedBySessionY>(record)?,
+                40 => Self::new::<TSLCMSessionXHasBeenDisconnectedReasonCodeZ>(record)?,
                 _ => return Err(SessionEventError::NoSessionEvent),
             },
             EventProvider::SecurityAuditing => match event_id.value() {
-                4624 => Self::new::<SecuritySuccessfulLogin>(record),
-                4625 => Self::new::<SecurityFailedLogin>(record),
-                4627 => Self::new::<SecurityGroupMembership>(record),
-                4634 => Self::new::<SecuritySuccessfulLogoff>(record),
-                4647 => Self::new::<SecurityUserInitiatedLogoff>(record),
-                4778 => Self::new::<SecuritySessionWasReconnected>(record),
-                4779 => Self::new::<SecuritySessionWasDisconnected>(record),
+                4624 => Self::new::<SecuritySuccessfulLogin>(record)?,
+                4625 => Self::new::<SecurityFailedLogin>(record)?,
+                4627 => Self::new::<SecurityGroupMembership>(record)?,
+                4634 => Self::new::<SecuritySuccessfulLogoff>(record)?,
+                4647 => Self::new::<SecurityUserInitiatedLogoff>(record)?,
+                4778 => Self::new::<SecuritySessionWasReconnected>(record)?,
+                4779 => Self::new::<SecuritySessionWasDisconnected>(record)?,
                 _ => return Err(SessionEventError::NoSessionEvent),
             },
             EventProvider::RemoteDesktopServicesRdpCoreTS => match event_id.value() {
-                131 => Self::new::<RdpAcceptedConnection>(record),
+                131 => Self::new::<RdpAcceptedConnection>(record)?,
                 _ => return Err(SessionEventError::NoSessionEvent),
-            }
+            },
             _ => {
                 log::warn!("unknown event provider: {provider}");
                 return Err(SessionEventError::NoSessionEvent);
@@ -102,7 +132,18 @@ impl TryFrom<SerializedEvtxRecord<serde_json::Value>> for SessionEvent {
 
 impl Ord for SessionEvent {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.record.timestamp.cmp(&other.record.timestamp)
+        // events of different files might have the same timestamp,
+        // but we must not lose one of them
+        self.record
+            .timestamp
+            .cmp(&other.record.timestamp)
+            .then_with(|| self.record.event_record_id.cmp(&other.record.event_record_id))
+            .then_with(|| {
+                self.event_type
+                    .event_id()
+                    .value()
+                    .cmp(&other.event_type.event_id().value())
+            })
     }
 }
 
@@ -116,6 +157,123 @@ impl Eq for SessionEvent {}
 
 impl PartialEq for SessionEvent {
     fn eq(&self, other: &Self) -> bool {
-        self.record.timestamp.eq(&other.record.timestamp)
+        self.cmp(other).is_eq()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::SessionEvent;
+    use crate::{
+        helpers::record,
+        sessions::{Session, SessionEventError, SessionEventRules},
+    };
+
+    const SECURITY: &str = "Microsoft-Windows-Security-Auditing";
+
+    #[test]
+    fn missing_logon_id() {
+        let result = SessionEvent::try_from(record(
+            SECURITY,
+            4634,
+            "server",
+            json!({"TargetUserName": "alice"}),
+        ));
+        assert!(matches!(
+            result,
+            Err(SessionEventError::MissingSessionId("LogonId"))
+        ));
+    }
+
+    #[test]
+    fn invalid_username() {
+        let logon = SessionEvent::try_from(record(
+            SECURITY,
+            4624,
+            "server",
+            json!({"TargetUserName": "alice", "TargetLogonId": "0x1234"}),
+        ))
+        .unwrap();
+        let mut session = Session::try_from(logon).unwrap();
+
+        let logoff = SessionEvent::try_from(record(
+            SECURITY,
+            4634,
+            "server",
+            json!({"TargetUserName": "a\\b\\c", "TargetLogonId": "0x1234"}),
+        ))
+        .unwrap();
+        assert!(matches!(
+            session.add_event(logoff),
+            Err(SessionEventError::InvalidUsername(_))
+        ));
+        assert_eq!(session.iter_events().count(), 1);
+    }
+
+    #[test]
+    fn event_defined_by_rules() {
+        let rules = SessionEventRules::from_toml(
+            r#"
+            [[event]]
+            provider = "Microsoft-Windows-Security-Auditing"
+            event_id = 4624
+            description = "Logon with custom fields"
+            session_id = "LogonId"
+            username_path = "Event/EventData/TargetUserName"
+            client_address_path = "Event/EventData/IpAddress"
+            "#,
+        )
+        .unwrap();
+        let event = SessionEvent::try_from_record(
+            record(
+                SECURITY,
+                4624,
+                "server",
+                json!({"TargetUserName": "alice", "TargetLogonId": "0x1234", "IpAddress": "10.0.0.5"}),
+            ),
+            &rules,
+        )
+        .unwrap();
+        assert_eq!(event.event_type().description(), "Logon with custom fields");
+        assert_eq!(event.session_id().to_string(), "0x1234");
+        assert_eq!(
+            event.event_type().username(event.record()).as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            event.event_type().client_address(event.record()).as_deref(),
+            Some("10.0.0.5")
+        );
+        assert_eq!(event.event_type().domain(event.record()), None);
+    }
+
+    #[test]
+    fn event_of_unknown_provider() {
+        let rules = SessionEventRules::from_toml(
+            r#"
+            [[event]]
+            provider = "Microsoft-Windows-SMBServer"
+            event_id = 551
+            description = "SMB session authentication failure"
+            session_id = "SessionName"
+            username_path = "Event/EventData/UserName"
+            "#,
+        )
+        .unwrap();
+        let smb_record =
+            |event_data| record("Microsoft-Windows-SMBServer", 551, "server", event_data);
+
+        let event = SessionEvent::try_from_record(
+            smb_record(json!({"UserName": "alice", "SessionName": "smb-1"})),
+            &rules,
+        )
+        .unwrap();
+        assert_eq!(event.session_id().to_string(), "smb-1");
+        assert!(matches!(
+            SessionEvent::try_from_record(smb_record(json!({"UserName": "alice"})), &rules),
+            Err(SessionEventError::MissingSessionId("SessionName"))
+        ));
     }
 }
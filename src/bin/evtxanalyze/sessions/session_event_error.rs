@@ -6,7 +6,22 @@ pub enum SessionEventError {
     #[error("this event does not belong to a session")]
     NoSessionEvent,
 
-    #[error("some other error has occurred")]
+    #[error("the event does not contain the field '{0}', which should hold the session id")]
+    MissingSessionId(&'static str),
+
+    #[error("the event does not contain the name of the computer")]
+    MissingComputer,
+
+    #[error("unexpected format of username: '{0}'")]
+    InvalidUsername(String),
+
+    #[error("multiple servers on one single connection are not supported: {0} != {1}")]
+    ConflictingServers(String, String),
+
+    #[error("multiple domains on one single connection are not supported: {0} != {1}")]
+    ConflictingDomains(String, String),
+
+    #[error(transparent)]
     WrappedError(anyhow::Error),
 }
 
@@ -0,0 +1,155 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use anyhow::bail;
+use dfirtk_eventdata::{EventId, SessionEventDefinition};
+use serde::Deserialize;
+
+/// session event definitions which are loaded at runtime, from a TOML file like
+///
+/// ```toml
+/// [[event]]
+/// provider = "Microsoft-Windows-Security-Auditing"
+/// event_id = 4648
+/// description = "A logon was attempted using explicit credentials"
+/// session_id = "LogonId"
+/// username_path = "Event/EventData/TargetUserName"
+/// ```
+///
+/// The fields are the same as those of the `event_data(...)` attribute
+/// of `#[derive(SessionEvent)]`. Definitions found here take precedence
+/// over the builtin definitions. Events of other channels than the builtin
+/// ones need a `channel` field, so that the files of this channel are read.
+#[derive(Default)]
+pub struct SessionEventRules {
+    definitions: HashMap<(String, u16), Arc<SessionEventDefinition>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RulesFile {
+    #[serde(default, rename = "event")]
+    events: Vec<SessionEventDefinition>,
+}
+
+impl SessionEventRules {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        match Self::from_toml(&content) {
+            Ok(rules) => {
+                log::info!(
+                    "loaded {} session event definitions from {}",
+                    rules.definitions.len(),
+                    path.display()
+                );
+                Ok(rules)
+            }
+            Err(why) => bail!("invalid rules file {}: {why}", path.display()),
+        }
+    }
+
+    /// loads the rules from `path`, if any, or returns no rules otherwise
+    pub fn load_or_default(path: Option<&Path>) -> anyhow::Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_toml(content: &str) -> anyhow::Result<Self> {
+        let file: RulesFile = toml::from_str(content)?;
+        let mut definitions = HashMap::new();
+        for definition in file.events {
+            let key = (definition.provider.clone(), definition.event_id.value());
+            if definitions.contains_key(&key) {
+                bail!("event {} of {} is defined more than once", key.1, key.0);
+            }
+            definitions.insert(key, Arc::new(definition));
+        }
+        Ok(Self { definitions })
+    }
+
+    /// returns the definition of the event `event_id` of the provider
+    /// named `provider`, if there is one
+    pub fn find(&self, provider: &str, event_id: &EventId) -> Option<&Arc<SessionEventDefinition>> {
+        self.definitions
+            .get(&(provider.to_owned(), event_id.value()))
+    }
+
+    /// the channels named by the definitions
+    pub fn channels(&self) -> impl Iterator<Item = &str> {
+        self.definitions
+            .values()
+            .filter_map(|definition| definition.channel.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dfirtk_eventdata::{EventId, SessionIdType};
+
+    use super::SessionEventRules;
+
+    #[test]
+    fn load_rules() {
+        let rules = SessionEventRules::from_toml(
+            r#"
+            [[event]]
+            provider = "Microsoft-Windows-Security-Auditing"
+            event_id = 4648
+            description = "A logon was attempted using explicit credentials"
+            session_id = "LogonId"
+            username_path = "Event/EventData/TargetUserName"
+            "#,
+        )
+        .unwrap();
+        let definition = rules
+            .find("Microsoft-Windows-Security-Auditing", &EventId(4648))
+            .unwrap();
+        assert_eq!(definition.session_id, SessionIdType::LogonId);
+        assert_eq!(
+            definition.username_path.as_deref(),
+            Some("Event/EventData/TargetUserName")
+        );
+        assert!(rules
+            .find("Microsoft-Windows-Security-Auditing", &EventId(4624))
+            .is_none());
+    }
+
+    #[test]
+    fn reject_unknown_fields() {
+        assert!(SessionEventRules::from_toml(
+            r#"
+            [[event]]
+            provider = "Microsoft-Windows-Security-Auditing"
+            event_id = 4648
+            description = "A logon was attempted using explicit credentials"
+            session_id = "LogonId"
+            user_path = "Event/EventData/TargetUserName"
+            "#,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn unknown_provider() {
+        let rules = SessionEventRules::from_toml(
+            r#"
+            [[event]]
+            provider = "Microsoft-Windows-SMBServer"
+            channel = "Microsoft-Windows-SMBServer/Security"
+            event_id = 1009
+            description = "The server denied anonymous access"
+            session_id = "None"
+            client_address_path = "Event/EventData/ClientName"
+            "#,
+        )
+        .unwrap();
+        assert!(rules
+            .find("Microsoft-Windows-SMBServer", &EventId(1009))
+            .is_some());
+        assert_eq!(
+            rules.channels().collect::<Vec<_>>(),
+            vec!["Microsoft-Windows-SMBServer/Security"]
+        );
+    }
+}
@@ -29,7 +29,6 @@ pub struct TSRCMUserAuthenticationSucceeded {}
     username_path = "Event/UserData/EventXML/User",
     client_address_path = "Event/UserData/EventXML/Address"
 )]
-
 pub struct TSLCMSessionLogonSucceeded {}
 
 #[derive(SessionEvent, Default)]
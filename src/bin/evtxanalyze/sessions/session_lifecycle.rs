@@ -0,0 +1,65 @@
+use std::fmt::Display;
+
+use dfirtk_eventdata::EventProvider;
+use serde::Serialize;
+
+use super::SessionEvent;
+
+/// the phase of a (RDP) session which is documented by a session event
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionLifecycleStep {
+    Connect,
+    Authenticate,
+    Logon,
+    ShellStart,
+    Disconnect,
+    Reconnect,
+    Logoff,
+}
+
+impl SessionLifecycleStep {
+    pub fn of(event: &SessionEvent) -> Option<Self> {
+        let event_id = event.event_type().event_id().value();
+        match event.event_type().provider() {
+            EventProvider::RemoteDesktopServicesRdpCoreTS => match event_id {
+                131 => Some(Self::Connect),
+                _ => None,
+            },
+            EventProvider::TerminalServicesRemoteConnectionManager => match event_id {
+                1149 => Some(Self::Authenticate),
+                _ => None,
+            },
+            EventProvider::TerminalServicesLocalSessionManager => match event_id {
+                21 => Some(Self::Logon),
+                22 => Some(Self::ShellStart),
+                23 => Some(Self::Logoff),
+                24 | 39 | 40 => Some(Self::Disconnect),
+                25 => Some(Self::Reconnect),
+                _ => None,
+            },
+            EventProvider::SecurityAuditing => match event_id {
+                4624 => Some(Self::Logon),
+                4634 | 4647 => Some(Self::Logoff),
+                4778 => Some(Self::Reconnect),
+                4779 => Some(Self::Disconnect),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl Display for SessionLifecycleStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connect => write!(f, "connect"),
+            Self::Authenticate => write!(f, "authenticate"),
+            Self::Logon => write!(f, "logon"),
+            Self::ShellStart => write!(f, "shell start"),
+            Self::Disconnect => write!(f, "disconnect"),
+            Self::Reconnect => write!(f, "reconnect"),
+            Self::Logoff => write!(f, "logoff"),
+        }
+    }
+}
@@ -1,25 +1,32 @@
-use std::{collections::HashMap, path::Path};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use anyhow::bail;
 use dfirtk_eventdata::SessionId;
 use evtx::EvtxParser;
 use walkdir::WalkDir;
 
-use super::{Session, SessionEvent};
+use super::{Session, SessionCorrelator, SessionEvent, SessionEventError, SessionEventRules};
 
-static KNOWN_FILES: &[&str] = &[
-    "Security.evtx",
-    "Microsoft-Windows-TerminalServices-RDPClient%4Operational.evtx",
-    "Microsoft-Windows-TerminalServices-RemoteConnectionManager%4Operational.evtx",
-    "Microsoft-Windows-TerminalServices-LocalSessionManager%4Operational.evtx",
+static KNOWN_CHANNELS: &[&str] = &[
+    "Security",
+    "Microsoft-Windows-TerminalServices-RDPClient/Operational",
+    "Microsoft-Windows-TerminalServices-RemoteConnectionManager/Operational",
+    "Microsoft-Windows-TerminalServices-LocalSessionManager/Operational",
 ];
 
-pub struct SessionStore {
-    sessions: HashMap<SessionId, Session>,
-}
-
-impl SessionStore {
-    pub fn import(evtx_files_dir: &Path, include_anonymous: bool) -> Result<Self, anyhow::Error> {
+/// returns all evtx files in `evtx_files_dirs` and their subdirectories,
+/// whose channel is one of `channels`. The channel is read from the first
+/// record of every file, so that renamed or archived files (such as
+/// `Archive-Security-*.evtx`) are found as well.
+pub fn find_evtx_files_of_channels(
+    evtx_files_dirs: &[PathBuf],
+    channels: &[&str],
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for evtx_files_dir in evtx_files_dirs {
         if !evtx_files_dir.exists() {
             bail!(
                 "directory '{}' does not exist. Aborting now.",
@@ -34,58 +41,114 @@ impl SessionStore {
             );
         }
 
+        for entry in WalkDir::new(evtx_files_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(Result::ok)
+        {
+            if !entry.file_type().is_file()
+                || !entry
+                    .file_name()
+                    .to_string_lossy()
+                    .to_lowercase()
+                    .ends_with(".evtx")
+            {
+                continue;
+            }
+
+            match channel_of(entry.path()) {
+                Some(channel) if channels.contains(&&channel[..]) => {
+                    files.push(entry.into_path())
+                }
+                Some(channel) => log::debug!(
+                    "ignoring {}, which contains events of {channel}",
+                    entry.path().display()
+                ),
+                None => log::debug!("ignoring {}, which has no events", entry.path().display()),
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// the channel of the first readable record in `path`
+fn channel_of(path: &Path) -> Option<String> {
+    let mut parser = match EvtxParser::from_path(path) {
+        Ok(parser) => parser,
+        Err(why) => {
+            log::warn!("unable to read {}: {why}", path.display());
+            return None;
+        }
+    };
+    let record = parser.records_json_value().find_map(Result::ok)?;
+    record.data["Event"]["System"]["Channel"]
+        .as_str()
+        .map(|channel| channel.to_owned())
+}
+
+/// sessions are identified by their session id and the computer which logged them,
+/// because logon ids and activity ids are not unique across multiple hosts
+type SessionKey = (String, SessionId);
+
+pub struct SessionStore {
+    sessions: HashMap<SessionKey, Session>,
+}
+
+impl SessionStore {
+    pub fn import(
+        evtx_files_dirs: &[PathBuf],
+        include_anonymous: bool,
+        rules: &SessionEventRules,
+    ) -> Result<Self, anyhow::Error> {
         let mut sessions = Self {
-            sessions: HashMap::<SessionId, Session>::new(),
+            sessions: HashMap::<SessionKey, Session>::new(),
         };
 
-        for filename in KNOWN_FILES {
-            let mut path = evtx_files_dir.join(filename);
-
-            // maybe we have troubles with case sensitivity
-            // Let's try this:
-            if !path.exists() {
-                let mut files = WalkDir::new(evtx_files_dir)
-                    .max_depth(1)
-                    .into_iter()
-                    .filter_map(Result::ok)
-                    .filter(|f| f.file_name().to_string_lossy().to_lowercase() == filename.to_lowercase());
-                        
-                if let Some(first_entry) = files.next() {
-                    path = first_entry.into_path();
-
-                    // there should be no more entry, otherwise
-                    // the filename is unambigious
-                    if let Some(next_entry) = files.next() {
-                        log::error!(
-                            "expected file '{filename}', but there exist \
-                            multiple variants of this name. I found at least \
-                            '{}' and '{}'. Omitting those files...",
-                            path.file_name().unwrap().to_string_lossy(),
-                            next_entry.file_name().to_string_lossy()
-                        );
+        let mut channels = KNOWN_CHANNELS.to_vec();
+        for channel in rules.channels() {
+            if !channels.contains(&channel) {
+                channels.push(channel);
+            }
+        }
+
+        for path in find_evtx_files_of_channels(evtx_files_dirs, &channels)? {
+            log::info!("importing {} into session store", path.to_string_lossy());
+
+            let mut skipped_events = 0;
+            for record in EvtxParser::from_path(&path)?.records_json_value() {
+                let record = match record {
+                    Ok(record) => record,
+                    Err(why) => {
+                        log::warn!("error while reading an event of {}: {why}", path.display());
+                        skipped_events += 1;
                         continue;
                     }
+                };
+
+                let event_record_id = record.event_record_id;
+                let result = match SessionEvent::try_from_record(record, rules) {
+                    Err(SessionEventError::NoSessionEvent) => continue,
+                    Err(why) => Err(why),
+                    Ok(event) => {
+                        log::trace!("found session event at {}", event.record().timestamp);
+                        sessions.add_event(event)
+                    }
+                };
+
+                if let Err(why) = result {
+                    log::warn!(
+                        "skipping event {event_record_id} of {}: {why}",
+                        path.display()
+                    );
+                    skipped_events += 1;
                 }
             }
 
-            if !path.is_file() {
-                log::error!(
-                    "tried to read '{}', but it is not a file. Omiting it...",
+            if skipped_events > 0 {
+                log::warn!(
+                    "skipped {skipped_events} events of {}, because they could not be analyzed",
                     path.display()
                 );
-                continue;
-            }
-
-            log::info!("importing {} into session store", path.to_string_lossy());
-
-            for event in EvtxParser::from_path(path)?
-                .records_json_value()
-                .map(|r| r.expect("error reading event"))
-                .map(SessionEvent::try_from)
-                .filter_map(|r| r.ok())
-            {
-                log::trace!("found session event at {}", event.record().timestamp);
-                sessions.add_event(event);
             }
         }
 
@@ -104,28 +167,49 @@ impl SessionStore {
         })
     }
 
-    fn add_event(&mut self, event: SessionEvent) {
-        if self.sessions.contains_key(event.session_id()) {
-            self.sessions
-                .entry(event.session_id().clone())
-                .and_modify(|s| s.add_event(event));
-        } else {
-            self.sessions
-                .insert(event.session_id().clone(), Session::from(event));
+    fn add_event(&mut self, event: SessionEvent) -> Result<(), SessionEventError> {
+        let computer = event
+            .computer()
+            .ok_or(SessionEventError::MissingComputer)?
+            .to_lowercase();
+        let key = (computer, event.session_id().clone());
+        match self.sessions.get_mut(&key) {
+            Some(session) => session.add_event(event),
+            None => {
+                self.sessions.insert(key, Session::try_from(event)?);
+                Ok(())
+            }
+        }
+    }
+
+    /// returns the session with the id `index`. If sessions of multiple
+    /// computers have this id, `computer` must be specified.
+    pub fn find_session(&self, index: &str, computer: Option<&str>) -> Option<&Session> {
+        let mut candidates: Vec<_> = self
+            .sessions
+            .values()
+            .filter(|s| s.has_id(index))
+            .filter(|s| computer.map_or(true, |c| s.computer().eq_ignore_ascii_case(c)))
+            .collect();
+        candidates.sort();
+        if candidates.len() > 1 {
+            let computers: Vec<_> = candidates.iter().map(|s| s.computer()).collect();
+            log::warn!(
+                "session '{index}' has been found on multiple computers ({}), displaying the first one",
+                computers.join(", ")
+            );
         }
+        candidates.into_iter().next()
     }
 
-    pub fn find_session(&self, index: &str) -> Option<&Session> {
-        self.sessions
-            .iter()
-            .find(|(k, _)| match k {
-                SessionId::ActivityId(id)
-                | SessionId::SessionName(id)
-                | SessionId::LogonId(id)
-                | SessionId::SessionId(id) => index == id,
-                SessionId::None(id) => index == id.to_string(),
-            })
-            .map(|(_, v)| v)
+    /// merges all sessions which belong to the same RDP logon
+    pub fn correlate(&mut self) {
+        let sessions = std::mem::take(&mut self.sessions);
+        self.sessions = SessionCorrelator::default()
+            .correlate(sessions.into_values())
+            .into_iter()
+            .map(|s| ((s.computer().to_lowercase(), s.session_id().clone()), s))
+            .collect();
     }
 }
 
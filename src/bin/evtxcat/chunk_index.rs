@@ -0,0 +1,267 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use dfir_toolkit::evtx::{
+    chunk_header_checksum, chunk_offset, FileHeader, CHUNK_FLAG_NO_CRC32, EVTX_CHUNK_HEADER_SIZE, EVTX_CHUNK_SIZE,
+    EVTX_FILE_HEADER_SIZE,
+};
+use evtx::EvtxChunkHeader;
+use serde::{Deserialize, Serialize};
+
+use crate::record_selection::RecordSelection;
+
+/// the range of event record identifiers stored in a chunk
+#[derive(Serialize, Deserialize)]
+struct ChunkIndexEntry {
+    chunk_number: u64,
+
+    /// the first and the last record id according to the chunk header, or
+    /// `None` if the header cannot be trusted
+    record_ids: Option<(u64, u64)>,
+}
+
+/// maps event record identifiers to the chunks they are stored in. The
+/// index is built from the chunk headers only, so that the chunks
+/// containing the requested records can be read directly.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ChunkIndex {
+    file_size: u64,
+    modified: Option<SystemTime>,
+    chunks: Vec<ChunkIndexEntry>,
+}
+
+impl ChunkIndex {
+    /// reads the index from the cache file next to `path`, if it exists and
+    /// still matches the evtx file. Otherwise, the index is built and, if
+    /// `use_cache` is set, stored in the cache file.
+    pub fn load_or_build(path: &Path, use_cache: bool) -> anyhow::Result<Self> {
+        if !use_cache {
+            return Self::build(path);
+        }
+
+        let cache_path = Self::cache_path(path);
+        match Self::load(path, &cache_path) {
+            Ok(Some(index)) => return Ok(index),
+            Ok(None) => log::info!("chunk index '{}' is outdated", cache_path.display()),
+            Err(why) => log::info!("unable to read chunk index '{}': {why}", cache_path.display()),
+        }
+
+        let index = Self::build(path)?;
+        if let Err(why) = index.store(&cache_path) {
+            log::warn!("unable to store chunk index '{}': {why}", cache_path.display());
+        }
+        Ok(index)
+    }
+
+    pub fn build(path: &Path) -> anyhow::Result<Self> {
+        let mut file =
+            File::open(path).with_context(|| format!("unable to open '{}'", path.display()))?;
+        let metadata = file.metadata()?;
+        let file_size = metadata.len();
+
+        Ok(Self {
+            file_size,
+            modified: metadata.modified().ok(),
+            chunks: Self::read_chunks(&mut file, file_size)?,
+        })
+    }
+
+    /// reads the headers of all chunks. Chunks whose header is invalid are
+    /// kept in the index, because they might still contain records. In a
+    /// dirty file, the header of the chunk which is currently written to
+    /// has not been updated, so this chunk is kept as well.
+    fn read_chunks<R: Read + Seek>(
+        reader: &mut R,
+        file_size: u64,
+    ) -> anyhow::Result<Vec<ChunkIndexEntry>> {
+        reader.seek(SeekFrom::Start(0))?;
+        let file_header = FileHeader::from_reader(reader)?;
+
+        let mut chunks = Vec::new();
+        let mut header = [0u8; EVTX_CHUNK_HEADER_SIZE];
+        let chunk_count =
+            file_size.saturating_sub(EVTX_FILE_HEADER_SIZE as u64) / EVTX_CHUNK_SIZE as u64;
+        for chunk_number in 0..chunk_count {
+            reader.seek(SeekFrom::Start(chunk_offset(chunk_number)))?;
+            reader.read_exact(&mut header)?;
+
+            // unused chunks are filled with zeros
+            if header.iter().all(|b| *b == 0) {
+                continue;
+            }
+
+            let record_ids = match EvtxChunkHeader::from_reader(&mut Cursor::new(&header[..])) {
+                Ok(chunk_header) => {
+                    let has_checksum = chunk_header.flags.bits() & CHUNK_FLAG_NO_CRC32 == 0;
+                    if !has_checksum
+                        || chunk_header_checksum(&header) == chunk_header.header_chunk_checksum
+                    {
+                        Some((
+                            chunk_header.first_event_record_id,
+                            chunk_header.last_event_record_id,
+                        ))
+                    } else {
+                        log::warn!("chunk {chunk_number} has an invalid header checksum");
+                        None
+                    }
+                }
+                Err(why) => {
+                    log::warn!("chunk {chunk_number} has an invalid header: {why}");
+                    None
+                }
+            };
+            chunks.push(ChunkIndexEntry {
+                chunk_number,
+                record_ids,
+            });
+        }
+
+        if file_header.is_dirty() {
+            let newest_chunk = chunks
+                .iter()
+                .filter_map(|c| c.record_ids.map(|(_, last)| (last, c.chunk_number)))
+                .max()
+                .map(|(_, chunk_number)| chunk_number);
+            for chunk in chunks.iter_mut() {
+                if Some(chunk.chunk_number) == newest_chunk
+                    || chunk.chunk_number == file_header.last_chunk_number
+                {
+                    log::info!(
+                        "the file is dirty, so chunk {} might contain more records than its header says",
+                        chunk.chunk_number
+                    );
+                    chunk.record_ids = None;
+                }
+            }
+        }
+        Ok(chunks)
+    }
+
+    /// returns the numbers of all chunks which might contain records
+    /// matching `selection`, in the order in which they are stored in the file
+    pub fn chunks_for(&self, selection: &RecordSelection) -> Vec<u64> {
+        self.chunks
+            .iter()
+            .filter(|c| {
+                c.record_ids
+                    .map_or(true, |(first, last)| selection.may_contain(first, last))
+            })
+            .map(|c| c.chunk_number)
+            .collect()
+    }
+
+    fn cache_path(path: &Path) -> PathBuf {
+        let mut cache_path = path.as_os_str().to_owned();
+        cache_path.push(".idx");
+        PathBuf::from(cache_path)
+    }
+
+    fn load(path: &Path, cache_path: &Path) -> anyhow::Result<Option<Self>> {
+        let index: Self = serde_json::from_reader(BufReader::new(File::open(cache_path)?))?;
+        let metadata = std::fs::metadata(path)?;
+        if index.file_size == metadata.len() && index.modified == metadata.modified().ok() {
+            Ok(Some(index))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn store(&self, cache_path: &Path) -> anyhow::Result<()> {
+        serde_json::to_writer(BufWriter::new(File::create(cache_path)?), self)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use clap::Parser;
+    use dfir_toolkit::evtx::{
+        chunk_header_checksum, EVTX_CHUNK_SIZE, EVTX_FILE_HEADER_SIZE, FILE_FLAG_DIRTY,
+    };
+
+    use super::ChunkIndex;
+    use crate::{cli::Cli, record_selection::RecordSelection};
+
+    fn file_header(last_chunk_number: u64, flags: u32) -> Vec<u8> {
+        let mut header = vec![0; EVTX_FILE_HEADER_SIZE];
+        header[..8].copy_from_slice(b"ElfFile\0");
+        header[16..24].copy_from_slice(&last_chunk_number.to_le_bytes());
+        header[120..124].copy_from_slice(&flags.to_le_bytes());
+        header
+    }
+
+    fn chunk(first_id: u64, last_id: u64) -> Vec<u8> {
+        let mut chunk = vec![0; EVTX_CHUNK_SIZE];
+        chunk[..8].copy_from_slice(b"ElfChnk\0");
+        chunk[24..32].copy_from_slice(&first_id.to_le_bytes());
+        chunk[32..40].copy_from_slice(&last_id.to_le_bytes());
+        chunk[40..44].copy_from_slice(&128u32.to_le_bytes());
+        let header_checksum = chunk_header_checksum(&chunk);
+        chunk[124..128].copy_from_slice(&header_checksum.to_le_bytes());
+        chunk
+    }
+
+    fn index_of(data: Vec<u8>) -> ChunkIndex {
+        let file_size = data.len() as u64;
+        ChunkIndex {
+            file_size,
+            modified: None,
+            chunks: ChunkIndex::read_chunks(&mut Cursor::new(data), file_size).unwrap(),
+        }
+    }
+
+    fn select_id(id: u64) -> RecordSelection {
+        RecordSelection::from(&Cli::parse_from([
+            "evtxcat",
+            "Security.evtx",
+            "--id",
+            &id.to_string(),
+        ]))
+    }
+
+    #[test]
+    fn find_chunks_by_header() {
+        let mut data = file_header(2, 0);
+        data.extend(chunk(1, 10));
+        data.extend(vec![0; EVTX_CHUNK_SIZE]);
+        data.extend(chunk(11, 20));
+        let index = index_of(data);
+
+        assert_eq!(index.chunks_for(&select_id(5)), vec![0]);
+        assert_eq!(index.chunks_for(&select_id(15)), vec![2]);
+        assert!(index.chunks_for(&select_id(25)).is_empty());
+    }
+
+    #[test]
+    fn include_chunks_with_invalid_header() {
+        let mut data = file_header(1, 0);
+        data.extend(chunk(1, 10));
+        let mut corrupted = chunk(11, 20);
+        corrupted[32..40].copy_from_slice(&12u64.to_le_bytes());
+        data.extend(corrupted);
+        let index = index_of(data);
+
+        assert_eq!(index.chunks_for(&select_id(15)), vec![1]);
+        assert_eq!(index.chunks_for(&select_id(5)), vec![0, 1]);
+    }
+
+    #[test]
+    fn include_current_chunk_of_dirty_file() {
+        // the header of chunk 1 has not been updated since record 12 was written
+        let mut data = file_header(0, FILE_FLAG_DIRTY);
+        data.extend(chunk(1, 10));
+        data.extend(chunk(11, 12));
+        data.extend(chunk(5, 9));
+        let index = index_of(data);
+
+        assert_eq!(index.chunks_for(&select_id(15)), vec![0, 1]);
+        assert_eq!(index.chunks_for(&select_id(7)), vec![0, 1, 2]);
+    }
+}
@@ -1,8 +1,9 @@
 use clap::{Parser, ValueHint};
-use dfir_toolkit::common::HasVerboseFlag;
+use dfir_toolkit::common::{HasVerboseFlag, Rfc3339Datetime};
 use log::LevelFilter;
 
 use crate::output_format::OutputFormat;
+use crate::record_id_list::RecordIdList;
 
 /// Display one or more events from an evtx file
 #[derive(Parser)]
@@ -20,14 +21,55 @@ pub (crate) struct Cli {
     #[clap(long)]
     pub (crate) max: Option<u64>,
 
-    /// show only the one event with this record identifier
+    /// show only the events with these record identifiers. Multiple identifiers
+    /// and ranges of identifiers must be separated by ',', e.g. '10,55,1200-1300'
     #[clap(short, long)]
-    pub (crate) id: Option<u64>,
+    pub (crate) id: Option<RecordIdList>,
+
+    /// hide events older than the specified date (hint: use RFC 3339 syntax)
+    #[clap(short('f'), long("from"))]
+    pub (crate) not_before: Option<Rfc3339Datetime>,
+
+    /// hide events newer than the specified date (hint: use RFC 3339 syntax)
+    #[clap(short('t'), long("to"))]
+    pub (crate) not_after: Option<Rfc3339Datetime>,
+
+    /// show only events with the specified event ids, separated by ','
+    #[clap(
+        short('I'),
+        long("include"),
+        use_value_delimiter = true,
+        value_delimiter = ','
+    )]
+    pub (crate) included_event_ids: Vec<u16>,
+
+    /// hide events with the specified event ids, separated by ','
+    #[clap(
+        short('X'),
+        long("exclude"),
+        use_value_delimiter = true,
+        value_delimiter = ','
+    )]
+    pub (crate) excluded_event_ids: Vec<u16>,
+
+    /// additionally show this number of records before and after each matching record
+    #[clap(short('C'), long("context"), default_value_t = 0)]
+    pub (crate) context: usize,
+
+    /// store the index of record identifiers next to the evtx file (with the
+    /// additional extension '.idx') and reuse it, as long as the evtx file is not modified
+    #[clap(long("cache-index"))]
+    pub (crate) cache_index: bool,
 
     /// don't display the records in a table format
     #[clap(short('T'), long("display-table"))]
     pub (crate) show_table: bool,
 
+    /// display a hexdump of the records, of the templates they use and the
+    /// headers of the chunks they are stored in, instead of the parsed records
+    #[clap(long("raw"), conflicts_with_all(["context", "show_table", "format"]))]
+    pub (crate) raw: bool,
+
     /// output format
     #[clap(value_enum, short('F'), long("format"), default_value_t = OutputFormat::Xml)]
     pub (crate) format: OutputFormat,
@@ -5,43 +5,51 @@ use evtx::EvtxParser;
 
 use dfir_toolkit::common::FancyParser;
 
+mod chunk_index;
 mod cli;
 mod output_format;
+mod raw_dump;
 mod record_filter;
+mod record_id_list;
 mod record_list_formatter;
+mod record_selection;
 mod unfiltered;
 
+use chunk_index::ChunkIndex;
 use cli::Cli;
 use output_format::OutputFormat;
 use record_filter::RecordFilter;
 use record_list_formatter::RecordListFormatter;
+use record_selection::RecordSelection;
 
 fn main() -> Result<()> {
     let cli = Cli::parse_cli();
 
     let path = PathBuf::from(&cli.evtx_file);
 
-    let parser = EvtxParser::from_path(path)?;
+    let mut parser = EvtxParser::from_path(&path)?;
+
+    let selection = RecordSelection::from(&cli);
+
+    // with context, records outside of the selected chunks might be needed
+    let index = if selection.is_restricted_by_id() && selection.context() == 0 {
+        Some(ChunkIndex::load_or_build(&path, cli.cache_index)?)
+    } else {
+        None
+    };
+
+    if cli.raw {
+        let chunk_numbers = index.map(|index| index.chunks_for(&selection));
+        return raw_dump::display_raw(&mut parser, chunk_numbers, &selection);
+    }
 
     match cli.format {
         OutputFormat::Json => {
-            let (record_ids, records) = if let Some(filter_id) = cli.id {
-                serde_json::Value::filter_by_id(parser, filter_id)
-            } else {
-                let min = cli.min.unwrap_or(u64::MIN);
-                let max = cli.max.unwrap_or(u64::MAX);
-                serde_json::Value::filter_by_range(parser, min, max)
-            };
+            let (record_ids, records) = serde_json::Value::filter(parser, index.as_ref(), &selection);
             serde_json::Value::display_results(record_ids, records, &cli);
         }
         OutputFormat::Xml => {
-            let (record_ids, records) = if let Some(filter_id) = cli.id {
-                String::filter_by_id(parser, filter_id)
-            } else {
-                let min = cli.min.unwrap_or(u64::MIN);
-                let max = cli.max.unwrap_or(u64::MAX);
-                String::filter_by_range(parser, min, max)
-            };
+            let (record_ids, records) = String::filter(parser, index.as_ref(), &selection);
             String::display_results(record_ids, records, &cli);
         }
     }
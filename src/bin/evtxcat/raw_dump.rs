@@ -0,0 +1,332 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{stdout, Write},
+    sync::Arc,
+};
+
+use dfir_toolkit::evtx::{
+    chunk_offset, EVTX_CHUNK_HEADER_SIZE, EVTX_RECORD_HEADER_SIZE, EVTX_RECORD_MAGIC,
+};
+use dfirtk_eventdata::EventId;
+use evtx::{
+    binxml::value_variant::BinXmlValue, model::deserialized::BinXMLDeserializedTokens, ChunkOffset,
+    EvtxChunk, EvtxChunkData, EvtxParser, ParserSettings,
+};
+use winstructs::guid::Guid;
+
+use crate::record_selection::RecordSelection;
+
+const TEMPLATE_DEFINITION_HEADER_SIZE: usize = 24;
+
+/// position of a record inside of its chunk
+struct RecordLocation {
+    offset: usize,
+    size: usize,
+}
+
+/// displays the binary content of all records matching `selection`,
+/// together with the templates they use and the headers of their chunks.
+/// If `chunk_numbers` is given, only those chunks are read.
+pub(crate) fn display_raw(
+    parser: &mut EvtxParser<File>,
+    chunk_numbers: Option<Vec<u64>>,
+    selection: &RecordSelection,
+) -> anyhow::Result<()> {
+    let settings = Arc::new(ParserSettings::default());
+    let mut out = stdout().lock();
+    let mut chunk_numbers = chunk_numbers.map(Vec::into_iter);
+    let mut next_chunk_number = 0;
+
+    loop {
+        let chunk_number = match chunk_numbers.as_mut() {
+            Some(chunk_numbers) => match chunk_numbers.next() {
+                Some(chunk_number) => chunk_number,
+                None => break,
+            },
+            None => next_chunk_number,
+        };
+
+        let (chunk_data, found_chunk_number) = match parser.find_next_chunk(chunk_number) {
+            Some(result) => result,
+            None => break,
+        };
+        next_chunk_number = found_chunk_number + 1;
+
+        // the requested chunk is empty
+        if found_chunk_number != chunk_number && chunk_numbers.is_some() {
+            continue;
+        }
+
+        match chunk_data {
+            Ok(mut chunk_data) => display_chunk(
+                &mut out,
+                found_chunk_number,
+                &mut chunk_data,
+                &settings,
+                selection,
+            )?,
+            Err(why) => log::warn!("unable to read chunk {found_chunk_number}: {why}"),
+        }
+    }
+    Ok(())
+}
+
+/// displays the header of a chunk and the selected records in it. Chunks
+/// which cannot be parsed are displayed without their records.
+fn display_chunk<W: Write>(
+    out: &mut W,
+    chunk_number: u64,
+    chunk_data: &mut EvtxChunkData,
+    settings: &Arc<ParserSettings>,
+    selection: &RecordSelection,
+) -> anyhow::Result<()> {
+    let chunk_offset = chunk_offset(chunk_number);
+    let validity = |valid| if valid { "valid" } else { "INVALID" };
+    let header = &chunk_data.header;
+    let chunk_info = [
+        format!("chunk {chunk_number} (file offset 0x{chunk_offset:08x})"),
+        format!(
+            "  record ids:      {} - {}",
+            header.first_event_record_id, header.last_event_record_id
+        ),
+        format!(
+            "  record numbers:  {} - {}",
+            header.first_event_record_number, header.last_event_record_number
+        ),
+        format!(
+            "  header checksum: 0x{:08x} ({})",
+            header.header_chunk_checksum,
+            validity(chunk_data.validate_header_checksum())
+        ),
+        format!(
+            "  data checksum:   0x{:08x} ({})",
+            header.events_checksum,
+            validity(chunk_data.validate_data_checksum())
+        ),
+        format!("  flags:           {:?}", header.flags),
+    ];
+
+    let mut chunk = match chunk_data.parse(Arc::clone(settings)) {
+        Ok(chunk) => chunk,
+        Err(why) => {
+            for line in chunk_info.iter() {
+                writeln!(out, "{line}")?;
+            }
+            log::warn!("unable to parse chunk {chunk_number}: {why}");
+            return Ok(());
+        }
+    };
+    let locations = record_locations(&chunk);
+    let mut chunk_info_displayed = false;
+    let mut displayed_templates = Vec::new();
+
+    for record in chunk.iter() {
+        let record = match record {
+            Ok(record) => record,
+            Err(why) => {
+                log::warn!("error while parsing a record in chunk {chunk_number}: {why}");
+                continue;
+            }
+        };
+
+        let event_id = || {
+            let record = record.clone().into_json_value().ok()?;
+            EventId::try_from(&record).ok().map(|id| id.value())
+        };
+        if !selection.matches(record.event_record_id, &record.timestamp, event_id) {
+            continue;
+        }
+
+        if !chunk_info_displayed {
+            for line in chunk_info.iter() {
+                writeln!(out, "{line}")?;
+            }
+            chunk_info_displayed = true;
+        }
+
+        let location = match locations.get(&record.event_record_id) {
+            Some(location) => location,
+            None => {
+                log::warn!(
+                    "unable to find the data of record {} in chunk {chunk_number}",
+                    record.event_record_id
+                );
+                continue;
+            }
+        };
+
+        writeln!(
+            out,
+            "record {} (chunk {chunk_number}, file offset 0x{:08x}, {} bytes, {})",
+            record.event_record_id,
+            chunk_offset + location.offset as u64,
+            location.size,
+            record.timestamp.to_rfc3339()
+        )?;
+
+        let mut template_offsets = Vec::new();
+        collect_template_offsets(&record.tokens, &mut template_offsets);
+        for template_offset in template_offsets {
+            display_template(
+                out,
+                record.chunk,
+                chunk_offset,
+                template_offset,
+                !displayed_templates.contains(&template_offset),
+            )?;
+            displayed_templates.push(template_offset);
+        }
+
+        writeln!(out, "  record data:")?;
+        hexdump(
+            out,
+            chunk_offset + location.offset as u64,
+            &record.chunk.data[location.offset..location.offset + location.size],
+        )?;
+    }
+    Ok(())
+}
+
+/// finds the records of a chunk by their signatures
+fn record_locations(chunk: &EvtxChunk) -> HashMap<u64, RecordLocation> {
+    let mut locations = HashMap::new();
+    let data = chunk.data;
+    let end = (chunk.header.free_space_offset as usize).min(data.len());
+    let mut offset = EVTX_CHUNK_HEADER_SIZE;
+
+    while offset + EVTX_RECORD_HEADER_SIZE <= end
+        && &data[offset..offset + EVTX_RECORD_MAGIC.len()] == EVTX_RECORD_MAGIC
+    {
+        let size = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let event_record_id = u64::from_le_bytes(data[offset + 8..offset + 16].try_into().unwrap());
+        if size < EVTX_RECORD_HEADER_SIZE || offset + size > end {
+            break;
+        }
+        locations.insert(event_record_id, RecordLocation { offset, size });
+        offset += size;
+    }
+    locations
+}
+
+fn collect_template_offsets(tokens: &[BinXMLDeserializedTokens], offsets: &mut Vec<ChunkOffset>) {
+    for token in tokens {
+        match token {
+            BinXMLDeserializedTokens::TemplateInstance(template) => {
+                if !offsets.contains(&template.template_def_offset) {
+                    offsets.push(template.template_def_offset);
+                }
+                collect_template_offsets(&template.substitution_array, offsets);
+            }
+            BinXMLDeserializedTokens::Value(BinXmlValue::BinXmlType(tokens)) => {
+                collect_template_offsets(tokens, offsets)
+            }
+            _ => (),
+        }
+    }
+}
+
+/// displays the GUID of a template, and its definition if `with_definition` is set
+fn display_template<W: Write>(
+    out: &mut W,
+    chunk: &EvtxChunk,
+    chunk_offset: u64,
+    template_offset: ChunkOffset,
+    with_definition: bool,
+) -> std::io::Result<()> {
+    let start = template_offset as usize;
+    let header = match chunk
+        .data
+        .get(start..start + TEMPLATE_DEFINITION_HEADER_SIZE)
+    {
+        Some(header) => header,
+        None => {
+            return writeln!(
+                out,
+                "  template at invalid chunk offset 0x{template_offset:x}"
+            );
+        }
+    };
+    let guid = match Guid::from_buffer(&header[4..20]) {
+        Ok(guid) => guid.to_string(),
+        Err(_) => "(invalid guid)".to_owned(),
+    };
+    let size = u32::from_le_bytes(header[20..24].try_into().unwrap()) as usize;
+    let end = (start + TEMPLATE_DEFINITION_HEADER_SIZE + size).min(chunk.data.len());
+
+    writeln!(
+        out,
+        "  template {{{guid}}} (file offset 0x{:08x}, {size} bytes)",
+        chunk_offset + start as u64
+    )?;
+    if with_definition {
+        hexdump(out, chunk_offset + start as u64, &chunk.data[start..end])?;
+    }
+    Ok(())
+}
+
+fn hexdump<W: Write>(out: &mut W, base_offset: u64, bytes: &[u8]) -> std::io::Result<()> {
+    for (line_number, line) in bytes.chunks(16).enumerate() {
+        let hex: Vec<_> = line.iter().map(|b| format!("{b:02x}")).collect();
+        let ascii: String = line
+            .iter()
+            .map(|b| {
+                if b.is_ascii_graphic() || *b == b' ' {
+                    *b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        writeln!(
+            out,
+            "    {:08x}  {:<47}  |{ascii}|",
+            base_offset + (line_number * 16) as u64,
+            hex.join(" ")
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use clap::Parser;
+    use dfir_toolkit::evtx::{chunk_header_checksum, EVTX_CHUNK_SIZE};
+    use evtx::{EvtxChunkData, ParserSettings};
+
+    use super::display_chunk;
+    use crate::{cli::Cli, record_selection::RecordSelection};
+
+    #[test]
+    fn display_header_of_unparseable_chunk() {
+        let mut data = vec![0; EVTX_CHUNK_SIZE];
+        data[..8].copy_from_slice(b"ElfChnk\0");
+        data[24..32].copy_from_slice(&1u64.to_le_bytes());
+        data[32..40].copy_from_slice(&10u64.to_le_bytes());
+        data[40..44].copy_from_slice(&128u32.to_le_bytes());
+        data[48..52].copy_from_slice(&512u32.to_le_bytes());
+        // the first template points beyond the end of the chunk
+        data[384..388].copy_from_slice(&0xffff_ff00u32.to_le_bytes());
+        let header_checksum = chunk_header_checksum(&data);
+        data[124..128].copy_from_slice(&header_checksum.to_le_bytes());
+
+        let mut chunk_data = EvtxChunkData::new(data, false).unwrap();
+        let selection = RecordSelection::from(&Cli::parse_from(["evtxcat", "Security.evtx"]));
+        let mut out = Vec::new();
+        display_chunk(
+            &mut out,
+            0,
+            &mut chunk_data,
+            &Arc::new(ParserSettings::default()),
+            &selection,
+        )
+        .unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("chunk 0 (file offset 0x00001000)\n"));
+        assert!(out.contains("  record ids:      1 - 10\n"));
+        assert!(out.contains("(valid)"));
+    }
+}
@@ -1,51 +1,107 @@
-use std::{io::{Read, Seek}, collections::HashMap, fs::File};
+use std::{io::{Read, Seek}, collections::{HashMap, VecDeque}, fs::File, sync::Arc};
 
-use evtx::{EvtxParser, SerializedEvtxRecord};
+use dfirtk_eventdata::EventId;
+use evtx::{EvtxParser, EvtxRecord, ParserSettings, SerializedEvtxRecord};
+use lazy_regex::regex_captures;
 
-use crate::unfiltered::Unfiltered;
+use crate::{chunk_index::ChunkIndex, record_selection::RecordSelection, unfiltered::Unfiltered};
 
 
-pub (crate) trait RecordFilter: Sized {
+pub (crate) trait RecordFilter: Sized + 'static {
     type ReaderType: Read + Seek;
 
-    fn unfiltered(parser: &mut EvtxParser<Self::ReaderType>) -> Unfiltered<Self>;
+    fn unfiltered(parser: &mut EvtxParser<Self::ReaderType>) -> Unfiltered<'_, Self>;
 
-    fn filter_by_id(
-        mut parser: EvtxParser<Self::ReaderType>,
-        filter_id: u64,
-    ) -> (Vec<u64>, HashMap<u64, SerializedEvtxRecord<Self>>) {
-        let mut record_ids: Vec<u64> = Vec::new();
-        let mut records: HashMap<u64, SerializedEvtxRecord<Self>> = HashMap::new();
-        if let Some(result) = Self::unfiltered(&mut parser).find(|record| match record {
-                    Ok(evt) => evt.event_record_id == filter_id,
-                    _ => false,
-                }) {
-            let evt = result.unwrap();
-            record_ids.push(evt.event_record_id);
-            records.insert(evt.event_record_id, evt);
+    #[allow(clippy::result_large_err)]
+    fn serialize(record: EvtxRecord<'_>) -> evtx::err::Result<SerializedEvtxRecord<Self>>;
+
+    /// returns all records stored in the chunks with the specified numbers
+    fn indexed(
+        parser: &mut EvtxParser<Self::ReaderType>,
+        chunk_numbers: Vec<u64>,
+    ) -> Unfiltered<'_, Self> {
+        let settings = Arc::new(ParserSettings::default());
+        Unfiltered {
+            inner: Box::new(chunk_numbers.into_iter().flat_map(move |chunk_number| {
+                let mut chunk_data = match parser.find_next_chunk(chunk_number) {
+                    Some((Ok(chunk_data), n)) if n == chunk_number => chunk_data,
+                    Some((Err(why), _)) => {
+                        log::warn!("unable to read chunk {chunk_number}: {why}");
+                        return Vec::new();
+                    }
+                    _ => return Vec::new(),
+                };
+                match chunk_data.parse(Arc::clone(&settings)) {
+                    Ok(mut chunk) => {
+                        let mut records = Vec::new();
+                        for record in chunk.iter() {
+                            records.push(match record {
+                                Ok(record) => Self::serialize(record),
+                                Err(why) => Err(why),
+                            });
+                        }
+                        records
+                    }
+                    Err(why) => {
+                        log::warn!("unable to parse chunk {chunk_number}: {why}");
+                        Vec::new()
+                    }
+                }
+            })),
         }
-        (record_ids, records)
     }
 
-    fn filter_by_range(
+    /// returns the event id of a record, if it can be determined
+    fn event_id(record: &SerializedEvtxRecord<Self>) -> Option<u16>;
+
+    /// returns all records matching `selection`, together with the requested
+    /// number of records which have been stored before and after each match
+    ///
+    /// If a chunk index is given, only those chunks are read which might
+    /// contain matching records.
+    fn filter(
         mut parser: EvtxParser<Self::ReaderType>,
-        min: u64,
-        max: u64,
+        index: Option<&ChunkIndex>,
+        selection: &RecordSelection,
     ) -> (Vec<u64>, HashMap<u64, SerializedEvtxRecord<Self>>) {
         let mut record_ids: Vec<u64> = Vec::new();
         let mut records: HashMap<u64, SerializedEvtxRecord<Self>> = HashMap::new();
 
-        for record in Self::unfiltered(&mut parser) {
-            match record {
-                Err(_) => (),
-                Ok(evt) => {
-                    let id = evt.event_record_id;
+        let context = selection.context();
+        let mut preceding_records: VecDeque<SerializedEvtxRecord<Self>> =
+            VecDeque::with_capacity(context);
+        let mut remaining_context = 0;
 
-                    if id >= min && id <= max {
-                        record_ids.push(id);
-                        records.insert(id, evt);
-                    }
+        let mut add_record = |evt: SerializedEvtxRecord<Self>| {
+            let id = evt.event_record_id;
+            if records.insert(id, evt).is_none() {
+                record_ids.push(id);
+            }
+        };
+
+        let candidates = match index {
+            Some(index) => Self::indexed(&mut parser, index.chunks_for(selection)),
+            None => Self::unfiltered(&mut parser),
+        };
+
+        for record in candidates {
+            let evt = match record {
+                Err(_) => continue,
+                Ok(evt) => evt,
+            };
+
+            if selection.matches(evt.event_record_id, &evt.timestamp, || Self::event_id(&evt)) {
+                preceding_records.drain(..).for_each(&mut add_record);
+                add_record(evt);
+                remaining_context = context;
+            } else if remaining_context > 0 {
+                add_record(evt);
+                remaining_context -= 1;
+            } else if context > 0 {
+                if preceding_records.len() == context {
+                    preceding_records.pop_front();
                 }
+                preceding_records.push_back(evt);
             }
         }
 
@@ -57,19 +113,36 @@ pub (crate) trait RecordFilter: Sized {
 impl RecordFilter for serde_json::Value {
     type ReaderType = File;
 
-    fn unfiltered(parser: &mut EvtxParser<Self::ReaderType>) -> Unfiltered<Self> {
+    fn unfiltered(parser: &mut EvtxParser<Self::ReaderType>) -> Unfiltered<'_, Self> {
         Unfiltered {
             inner: Box::new(parser.records_json_value()),
         }
     }
+
+    fn serialize(record: EvtxRecord<'_>) -> evtx::err::Result<SerializedEvtxRecord<Self>> {
+        record.into_json_value()
+    }
+
+    fn event_id(record: &SerializedEvtxRecord<Self>) -> Option<u16> {
+        EventId::try_from(record).ok().map(|id| id.value())
+    }
 }
 
 impl RecordFilter for String {
     type ReaderType = File;
 
-    fn unfiltered(parser: &mut EvtxParser<Self::ReaderType>) -> Unfiltered<Self> {
+    fn unfiltered(parser: &mut EvtxParser<Self::ReaderType>) -> Unfiltered<'_, Self> {
         Unfiltered {
             inner: Box::new(parser.records()),
         }
     }
+
+    fn serialize(record: EvtxRecord<'_>) -> evtx::err::Result<SerializedEvtxRecord<Self>> {
+        record.into_xml()
+    }
+
+    fn event_id(record: &SerializedEvtxRecord<Self>) -> Option<u16> {
+        let (_, event_id) = regex_captures!(r"<EventID[^>]*>\s*(\d+)\s*</EventID>", &record.data)?;
+        event_id.parse().ok()
+    }
 }
@@ -0,0 +1,82 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+/// a list of event record identifiers and ranges of identifiers, such as
+/// `10,55,1200-1300`
+#[derive(Clone, Debug)]
+pub(crate) struct RecordIdList(Vec<RangeInclusive<u64>>);
+
+impl RecordIdList {
+    pub fn contains(&self, id: u64) -> bool {
+        self.0.iter().any(|range| range.contains(&id))
+    }
+
+    /// checks if at least one of the identifiers is between `first` and `last`
+    pub fn intersects(&self, first: u64, last: u64) -> bool {
+        self.0
+            .iter()
+            .any(|range| *range.start() <= last && first <= *range.end())
+    }
+}
+
+impl FromStr for RecordIdList {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_id = |id: &str| {
+            id.trim()
+                .parse::<u64>()
+                .map_err(|why| format!("invalid record id '{id}': {why}"))
+        };
+
+        let mut ranges = Vec::new();
+        for part in s.split(',').filter(|p| !p.trim().is_empty()) {
+            let range = match part.split_once('-') {
+                None => {
+                    let id = parse_id(part)?;
+                    id..=id
+                }
+                Some((first, last)) => {
+                    let first = parse_id(first)?;
+                    let last = parse_id(last)?;
+                    if first > last {
+                        return Err(format!("invalid record id range '{part}'"));
+                    }
+                    first..=last
+                }
+            };
+            ranges.push(range);
+        }
+
+        if ranges.is_empty() {
+            return Err("no record ids specified".into());
+        }
+        Ok(Self(ranges))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RecordIdList;
+
+    #[test]
+    fn ids_and_ranges() {
+        let list: RecordIdList = "10,55,1200-1300".parse().unwrap();
+        for id in [10, 55, 1200, 1250, 1300] {
+            assert!(list.contains(id));
+        }
+        for id in [0, 11, 54, 1199, 1301] {
+            assert!(!list.contains(id));
+        }
+        assert!(list.intersects(1, 10));
+        assert!(list.intersects(1250, 2000));
+        assert!(!list.intersects(56, 1199));
+    }
+
+    #[test]
+    fn invalid_lists() {
+        assert!("".parse::<RecordIdList>().is_err());
+        assert!("10,a".parse::<RecordIdList>().is_err());
+        assert!("20-10".parse::<RecordIdList>().is_err());
+        assert!("10-".parse::<RecordIdList>().is_err());
+    }
+}
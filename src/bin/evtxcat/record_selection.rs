@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+
+use crate::{cli::Cli, record_id_list::RecordIdList};
+
+/// describes which records shall be displayed
+pub(crate) struct RecordSelection {
+    ids: Option<RecordIdList>,
+    min: u64,
+    max: u64,
+    not_before: Option<DateTime<Utc>>,
+    not_after: Option<DateTime<Utc>>,
+    included_event_ids: Vec<u16>,
+    excluded_event_ids: Vec<u16>,
+    context: usize,
+}
+
+impl From<&Cli> for RecordSelection {
+    fn from(cli: &Cli) -> Self {
+        Self {
+            ids: cli.id.clone(),
+            min: cli.min.unwrap_or(u64::MIN),
+            max: cli.max.unwrap_or(u64::MAX),
+            not_before: cli.not_before.as_ref().map(|ts| **ts),
+            not_after: cli.not_after.as_ref().map(|ts| **ts),
+            included_event_ids: cli.included_event_ids.clone(),
+            excluded_event_ids: cli.excluded_event_ids.clone(),
+            context: cli.context,
+        }
+    }
+}
+
+impl RecordSelection {
+    /// number of records to display before and after each matching record
+    pub fn context(&self) -> usize {
+        self.context
+    }
+
+    /// checks if the selection is restricted to some event record identifiers,
+    /// so that a [`crate::chunk_index::ChunkIndex`] can be used to find the records
+    pub fn is_restricted_by_id(&self) -> bool {
+        self.ids.is_some() || self.min > u64::MIN || self.max < u64::MAX
+    }
+
+    /// checks if a chunk containing the records from `first` to `last` might
+    /// contain matching records
+    pub fn may_contain(&self, first: u64, last: u64) -> bool {
+        first <= self.max
+            && self.min <= last
+            && self.ids.as_ref().map_or(true, |ids| ids.intersects(first, last))
+    }
+
+    /// checks if a record matches all conditions. The event id is only
+    /// computed if it is needed.
+    pub fn matches<F>(&self, record_id: u64, timestamp: &DateTime<Utc>, event_id: F) -> bool
+    where
+        F: FnOnce() -> Option<u16>,
+    {
+        if record_id < self.min || record_id > self.max {
+            return false;
+        }
+
+        if let Some(ids) = self.ids.as_ref() {
+            if !ids.contains(record_id) {
+                return false;
+            }
+        }
+
+        if let Some(not_before) = self.not_before.as_ref() {
+            if timestamp < not_before {
+                return false;
+            }
+        }
+
+        if let Some(not_after) = self.not_after.as_ref() {
+            if timestamp > not_after {
+                return false;
+            }
+        }
+
+        if self.included_event_ids.is_empty() && self.excluded_event_ids.is_empty() {
+            return true;
+        }
+
+        match event_id() {
+            None => self.included_event_ids.is_empty(),
+            Some(event_id) => {
+                (self.included_event_ids.is_empty() || self.included_event_ids.contains(&event_id))
+                    && !self.excluded_event_ids.contains(&event_id)
+            }
+        }
+    }
+}
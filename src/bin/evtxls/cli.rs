@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clap::{ColorChoice, Parser, ValueEnum, ValueHint};
 
 use dfir_toolkit::common::{Rfc3339Datetime, HasVerboseFlag};
@@ -49,6 +51,25 @@ pub(crate) struct Cli {
     )]
     pub(crate) excluded_event_ids: Vec<u16>,
 
+    /// List only events matching at least one of the specified presets, separated by ','.
+    /// Use '--list-presets' to see which presets are available
+    #[clap(
+        short('P'),
+        long("preset"),
+        use_value_delimiter = true,
+        value_delimiter = ','
+    )]
+    pub(crate) presets: Vec<String>,
+
+    /// load additional presets from this TOML file. Presets in this file
+    /// replace built-in presets with the same name
+    #[clap(long("preset-file"), value_hint=ValueHint::FilePath)]
+    pub(crate) preset_file: Option<PathBuf>,
+
+    /// display all available presets and exit
+    #[clap(long("list-presets"))]
+    pub(crate) list_presets: bool,
+
     /// highlight interesting content using colors
     #[clap(short('C'), long("color"), default_value_t = ColorChoice::Auto)]
     pub(crate) display_colors: ColorChoice,
@@ -80,6 +101,21 @@ pub(crate) struct Cli {
         default_values_t=vec![SystemField::EventId, SystemField::EventRecordId])]
     pub(crate) display_system_fields: Vec<SystemField>,
 
+    /// don't display the records, but the unique set of IP addresses, URLs,
+    /// SIDs, GUIDs, hashes and base64 blobs found in their event data, together
+    /// with the number of occurrences and the first and last timestamps they were seen
+    #[clap(short('e'), long("extract"))]
+    pub(crate) extract: bool,
+
+    /// don't exit after all records have been displayed, but periodically check
+    /// the files for new records and display them (sort order is ignored)
+    #[clap(long("follow"), conflicts_with_all(["extract", "list_presets"]))]
+    pub(crate) follow: bool,
+
+    /// number of seconds to wait before checking for new records
+    #[clap(long("interval"), requires("follow"), default_value_t = 5)]
+    pub(crate) interval: u64,
+
     /// don't display any common event fields at all. This corresponds to
     /// specifying '--base-fields' without any values (which is not allowed, that's why there is this flag)
     #[clap(short('B'), long("hide-base-fields"), default_value_t=false)]
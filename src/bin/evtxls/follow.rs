@@ -0,0 +1,270 @@
+use std::{collections::BTreeSet, fs::File, path::PathBuf, sync::Arc};
+
+use dfir_toolkit::evtx::FileHeader;
+use evtx::{EvtxParser, ParserSettings, SerializedEvtxRecord};
+use serde_json::Value;
+
+/// an evtx file which is still being written to. Every time the file is
+/// read, only those records are returned which have not been seen before.
+pub(crate) struct FollowedFile {
+    path: PathBuf,
+    seen: SeenRecords,
+    settings: Arc<ParserSettings>,
+}
+
+impl From<PathBuf> for FollowedFile {
+    fn from(path: PathBuf) -> Self {
+        Self {
+            path,
+            seen: SeenRecords::default(),
+            settings: Arc::new(ParserSettings::default()),
+        }
+    }
+}
+
+impl FollowedFile {
+    /// reopens the file and returns all records which have not been returned
+    /// before, sorted by their `EventRecordId`.
+    ///
+    /// Circular logs overwrite the oldest chunk when they are full, so new
+    /// records are not necessarily stored in the last chunk of the file.
+    /// That's why all chunks are checked, but chunks whose records have all
+    /// been seen (according to their chunk header) are skipped. The chunk
+    /// header of the chunk which is currently being written is not updated
+    /// in dirty files, so this chunk is always read.
+    pub fn read_new_records(&mut self) -> anyhow::Result<Vec<SerializedEvtxRecord<Value>>> {
+        let header = FileHeader::from_reader(&mut File::open(&self.path)?)?;
+        if self.seen.is_cleared(&header) {
+            log::warn!(
+                "'{}' has been cleared or replaced, displaying all of its records again",
+                self.path.display()
+            );
+            self.seen = SeenRecords::default();
+        }
+
+        let mut parser = EvtxParser::from_path(&self.path)?;
+        let mut records = Vec::new();
+        let mut oldest_record_id = None;
+        let mut next_chunk_number = 0;
+
+        while let Some((chunk, chunk_number)) = parser.find_next_chunk(next_chunk_number) {
+            next_chunk_number = chunk_number + 1;
+            let mut chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(why) => {
+                    // the last chunk might be incomplete if the file is currently
+                    // being written to. We will read it again next time.
+                    log::info!("unable to read chunk {chunk_number}: {why}");
+                    continue;
+                }
+            };
+
+            let first_record_id = chunk.header.first_event_record_id;
+            let last_record_id = chunk.header.last_event_record_id;
+            oldest_record_id =
+                Some(oldest_record_id.map_or(first_record_id, |id: u64| id.min(first_record_id)));
+            if !self
+                .seen
+                .must_read_chunk(chunk_number, first_record_id, last_record_id, &header)
+            {
+                continue;
+            }
+
+            let mut chunk = match chunk.parse(Arc::clone(&self.settings)) {
+                Ok(chunk) => chunk,
+                Err(why) => {
+                    log::warn!("unable to parse chunk {chunk_number}: {why}");
+                    continue;
+                }
+            };
+
+            for record in chunk.iter() {
+                let record = match record {
+                    Ok(record) => record.into_json_value(),
+                    Err(why) => Err(why),
+                };
+                match record {
+                    // the id of this record has not been seen, so we will try
+                    // to read it again next time
+                    Err(why) => log::warn!("error while parsing a record: {why}"),
+                    Ok(record) => {
+                        if self.seen.add(record.event_record_id, chunk_number) {
+                            records.push(record);
+                        }
+                    }
+                }
+            }
+        }
+
+        self.seen.finish(&header, oldest_record_id);
+        records.sort_by_key(|r| r.event_record_id);
+        Ok(records)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+/// the ids of the records of a file which have already been returned.
+/// Which records are new is decided only by the ids of the records
+/// themselves, because chunk headers and the file header are outdated
+/// while the file is opened by the eventlog service.
+#[derive(Default)]
+struct SeenRecords {
+    ids: BTreeSet<u64>,
+
+    /// the largest record id seen until now, and the chunk which contains it
+    newest: Option<(u64, u64)>,
+
+    /// the next record id of the file header when the file has been read
+    next_record_id: Option<u64>,
+}
+
+impl SeenRecords {
+    /// returns `true` if the log has been cleared since the last time it has
+    /// been read. Records are numbered starting with 1 again in this case,
+    /// which is also noted in the file header.
+    fn is_cleared(&self, header: &FileHeader) -> bool {
+        self.next_record_id
+            .is_some_and(|next_record_id| header.next_record_id < next_record_id)
+    }
+
+    /// returns `true` if the chunk might contain records which have not been
+    /// seen before
+    fn must_read_chunk(
+        &self,
+        chunk_number: u64,
+        first_record_id: u64,
+        last_record_id: u64,
+        header: &FileHeader,
+    ) -> bool {
+        let is_current_chunk = match self.newest {
+            None => true,
+            Some((_, newest_chunk)) => {
+                chunk_number == newest_chunk || chunk_number == header.last_chunk_number
+            }
+        };
+        if is_current_chunk || last_record_id < first_record_id {
+            return true;
+        }
+
+        let seen = self.ids.range(first_record_id..=last_record_id).count() as u64;
+        seen <= last_record_id - first_record_id
+    }
+
+    /// marks a record as seen, and returns `true` if it has not been seen before
+    fn add(&mut self, record_id: u64, chunk_number: u64) -> bool {
+        if !self.ids.insert(record_id) {
+            return false;
+        }
+        if self.newest.map_or(true, |(newest_id, _)| record_id > newest_id) {
+            self.newest = Some((record_id, chunk_number));
+        }
+        true
+    }
+
+    /// forgets all records which have been overwritten
+    fn finish(&mut self, header: &FileHeader, oldest_record_id: Option<u64>) {
+        if let Some(oldest_record_id) = oldest_record_id {
+            self.ids = self.ids.split_off(&oldest_record_id);
+        }
+        self.next_record_id = Some(header.next_record_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dfir_toolkit::evtx::FileHeader;
+
+    use super::SeenRecords;
+
+    fn header(last_chunk_number: u64, next_record_id: u64) -> FileHeader {
+        FileHeader {
+            first_chunk_number: 0,
+            last_chunk_number,
+            next_record_id,
+            flags: 0,
+        }
+    }
+
+    /// simulates reading the file, with `chunks` containing the numbers
+    /// of the chunks and the ids of the records which could be parsed
+    fn read(seen: &mut SeenRecords, chunks: &[(u64, &[u64])], header: &FileHeader) -> Vec<u64> {
+        let mut new_ids = Vec::new();
+        for (chunk_number, ids) in chunks {
+            for id in ids.iter() {
+                if seen.add(*id, *chunk_number) {
+                    new_ids.push(*id);
+                }
+            }
+        }
+        let oldest = chunks.iter().flat_map(|(_, ids)| ids.iter()).min().cloned();
+        seen.finish(header, oldest);
+        new_ids
+    }
+
+    #[test]
+    fn read_current_chunk_with_outdated_header() {
+        let mut seen = SeenRecords::default();
+        // the file header is outdated as well and still names chunk 0 as current chunk
+        let header = header(0, 1);
+        assert!(seen.must_read_chunk(1, 6, 10, &header));
+        read(
+            &mut seen,
+            &[(0, &[1, 2, 3, 4, 5]), (1, &[6, 7, 8, 9, 10]), (2, &[11, 12])],
+            &header,
+        );
+
+        // the header of chunk 2 still says that it contains 11 and 12 only
+        assert!(!seen.must_read_chunk(1, 6, 10, &header));
+        assert!(seen.must_read_chunk(2, 11, 12, &header));
+        assert_eq!(read(&mut seen, &[(2, &[11, 12, 13])], &header), vec![13]);
+    }
+
+    #[test]
+    fn retry_unparsable_records() {
+        let mut seen = SeenRecords::default();
+        let header = header(1, 13);
+        read(&mut seen, &[(0, &[1, 2, 4, 5]), (1, &[6, 7])], &header);
+
+        // record 3 could not be parsed
+        assert!(seen.must_read_chunk(0, 1, 5, &header));
+        assert_eq!(read(&mut seen, &[(0, &[1, 2, 3, 4, 5])], &header), vec![3]);
+        assert!(!seen.must_read_chunk(0, 1, 5, &header));
+    }
+
+    #[test]
+    fn partially_copied_file() {
+        let mut seen = SeenRecords::default();
+        let header = header(1, 8);
+        read(&mut seen, &[(0, &[1, 2, 3, 4, 5]), (1, &[6, 7])], &header);
+
+        // the second chunk has not yet been copied
+        assert!(!seen.is_cleared(&header));
+        assert!(read(&mut seen, &[(0, &[1, 2, 3, 4, 5])], &header).is_empty());
+        assert_eq!(
+            read(&mut seen, &[(0, &[1, 2, 3, 4, 5]), (1, &[6, 7, 8])], &header),
+            vec![8]
+        );
+    }
+
+    #[test]
+    fn cleared_log() {
+        let mut seen = SeenRecords::default();
+        read(&mut seen, &[(0, &[100, 101, 102])], &header(0, 103));
+        assert!(!seen.is_cleared(&header(0, 104)));
+        assert!(seen.is_cleared(&header(0, 2)));
+    }
+
+    #[test]
+    fn forget_overwritten_records() {
+        let mut seen = SeenRecords::default();
+        let header = header(1, 0);
+        read(&mut seen, &[(0, &[1, 2]), (1, &[3, 4])], &header);
+
+        // chunk 0 has been overwritten by records 5 and 6
+        assert_eq!(read(&mut seen, &[(0, &[5, 6]), (1, &[3, 4])], &header), vec![5, 6]);
+        assert_eq!(seen.ids.iter().cloned().collect::<Vec<_>>(), vec![3, 4, 5, 6]);
+    }
+}
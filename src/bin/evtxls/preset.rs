@@ -0,0 +1,261 @@
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+use anyhow::{bail, Context};
+use dfirtk_eventdata::{EventId, EventProvider};
+use evtx::SerializedEvtxRecord;
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
+
+static BUILTIN_PRESETS: &str = include_str!("presets.toml");
+
+/// a named filter, which selects all records matching at least one of its rules
+#[derive(Deserialize, Clone)]
+pub(crate) struct Preset {
+    #[serde(default)]
+    description: String,
+    rules: Vec<PresetRule>,
+}
+
+#[derive(Deserialize, Clone)]
+pub(crate) struct PresetRule {
+    channel: Option<String>,
+
+    /// the name of the provider, which need not be known to [`EventProvider`]
+    provider: Option<String>,
+
+    #[serde(default)]
+    event_ids: Vec<EventId>,
+
+    #[serde(default)]
+    fields: BTreeMap<String, FieldPattern>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(try_from = "String")]
+struct FieldPattern(Regex);
+
+impl TryFrom<String> for FieldPattern {
+    type Error = regex::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Ok(Self(Regex::new(&value)?))
+    }
+}
+
+impl Preset {
+    pub fn matches(&self, record: &SerializedEvtxRecord<Value>) -> bool {
+        self.rules.iter().any(|r| r.matches(record))
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &PresetRule> {
+        self.rules.iter()
+    }
+}
+
+impl PresetRule {
+    pub fn matches(&self, record: &SerializedEvtxRecord<Value>) -> bool {
+        let system = &record.data["Event"]["System"];
+
+        if let Some(channel) = self.channel.as_ref() {
+            match system["Channel"].as_str() {
+                Some(c) if c.eq_ignore_ascii_case(channel) => (),
+                _ => return false,
+            }
+        }
+
+        if let Some(provider) = self.provider.as_ref() {
+            match EventProvider::name_of(record) {
+                Some(p) if p.eq_ignore_ascii_case(provider) => (),
+                _ => return false,
+            }
+        }
+
+        if !self.event_ids.is_empty() {
+            match EventId::try_from(record) {
+                Ok(event_id) if self.event_ids.contains(&event_id) => (),
+                _ => return false,
+            }
+        }
+
+        self.fields.iter().all(|(name, pattern)| {
+            let value = if name.contains('/') {
+                name.split('/').fold(&record.data, |v, part| &v[part])
+            } else {
+                &record.data["Event"]["EventData"][name]
+            };
+
+            match value {
+                Value::Null => false,
+                Value::String(s) => pattern.0.is_match(s),
+                v => pattern.0.is_match(&v.to_string()),
+            }
+        })
+    }
+}
+
+impl Display for PresetRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(channel) = self.channel.as_ref() {
+            parts.push(format!("channel={channel}"));
+        }
+        if let Some(provider) = self.provider.as_ref() {
+            parts.push(format!("provider={provider}"));
+        }
+        if !self.event_ids.is_empty() {
+            let ids: Vec<_> = self.event_ids.iter().map(|id| id.to_string()).collect();
+            parts.push(format!("event_ids={}", ids.join(",")));
+        }
+        for (name, pattern) in self.fields.iter() {
+            parts.push(format!("{name}=~/{}/", pattern.0));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// all presets known to evtxls, indexed by their name
+pub(crate) struct PresetCatalogue {
+    presets: BTreeMap<String, Preset>,
+}
+
+impl Default for PresetCatalogue {
+    fn default() -> Self {
+        Self {
+            presets: toml::from_str(BUILTIN_PRESETS).expect("invalid built-in presets"),
+        }
+    }
+}
+
+impl PresetCatalogue {
+    /// loads additional presets from a TOML file. Presets with the same name
+    /// as an already known preset replace the known preset.
+    pub fn load_from(&mut self, path: &Path) -> anyhow::Result<()> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("unable to read preset file '{}'", path.display()))?;
+        let presets: BTreeMap<String, Preset> = toml::from_str(&content)
+            .with_context(|| format!("invalid preset file '{}'", path.display()))?;
+        self.presets.extend(presets);
+        Ok(())
+    }
+
+    pub fn select(&self, names: &[String]) -> anyhow::Result<Vec<Preset>> {
+        let mut presets = Vec::with_capacity(names.len());
+        for name in names {
+            match self.presets.get(name) {
+                Some(preset) => presets.push(preset.clone()),
+                None => {
+                    let known: Vec<_> = self.presets.keys().map(|k| &k[..]).collect();
+                    bail!(
+                        "unknown preset '{name}', known presets are: {}",
+                        known.join(", ")
+                    )
+                }
+            }
+        }
+        Ok(presets)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Preset)> {
+        self.presets.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use evtx::SerializedEvtxRecord;
+    use serde_json::{json, Value};
+
+    use super::{Preset, PresetCatalogue};
+
+    fn record(
+        channel: &str,
+        provider: &str,
+        event_id: u16,
+        event_data: Value,
+    ) -> SerializedEvtxRecord<Value> {
+        SerializedEvtxRecord {
+            event_record_id: 1,
+            timestamp: Utc::now(),
+            data: json!({
+                "Event": {
+                    "System": {
+                        "Channel": channel,
+                        "Provider": {"#attributes": {"Name": provider}},
+                        "EventID": event_id,
+                    },
+                    "EventData": event_data,
+                }
+            }),
+        }
+    }
+
+    #[test]
+    fn builtin_presets_are_valid() {
+        let catalogue = PresetCatalogue::default();
+        assert!(catalogue
+            .select(&["lateral-movement".into(), "logons".into()])
+            .is_ok());
+        assert!(catalogue.select(&["no-such-preset".into()]).is_err());
+    }
+
+    #[test]
+    fn field_conditions() {
+        let catalogue = PresetCatalogue::default();
+        let presets = catalogue.select(&["lateral-movement".into()]).unwrap();
+        let preset = &presets[0];
+
+        let network_logon = record(
+            "Security",
+            "Microsoft-Windows-Security-Auditing",
+            4624,
+            json!({"LogonType": 3}),
+        );
+        let local_logon = record(
+            "Security",
+            "Microsoft-Windows-Security-Auditing",
+            4624,
+            json!({"LogonType": 2}),
+        );
+        let other_channel = record(
+            "System",
+            "Microsoft-Windows-Security-Auditing",
+            4624,
+            json!({"LogonType": 3}),
+        );
+        assert!(preset.matches(&network_logon));
+        assert!(!preset.matches(&local_logon));
+        assert!(!preset.matches(&other_channel));
+    }
+
+    #[test]
+    fn unknown_provider() {
+        let preset: Preset = toml::from_str(
+            r#"
+            [[rules]]
+            provider = "Microsoft-Windows-Sysmon"
+            event_ids = [3]
+            "#,
+        )
+        .unwrap();
+        let network_connection = record(
+            "Microsoft-Windows-Sysmon/Operational",
+            "Microsoft-Windows-Sysmon",
+            3,
+            json!({"DestinationPort": 445}),
+        );
+        let process_creation = record(
+            "Microsoft-Windows-Sysmon/Operational",
+            "Microsoft-Windows-Sysmon",
+            1,
+            json!({}),
+        );
+        assert!(preset.matches(&network_connection));
+        assert!(!preset.matches(&process_creation));
+    }
+}
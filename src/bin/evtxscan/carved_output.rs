@@ -0,0 +1,106 @@
+use std::io::Write;
+
+use dfir_toolkit::common::bodyfile::{Bodyfile3Line, Modified};
+use serde_json::json;
+
+use crate::carver::CarvedRecord;
+
+#[derive(clap::ValueEnum, Clone)]
+pub(crate) enum CarvedRecordFormat {
+    Xml,
+    Json,
+    Bodyfile,
+}
+
+/// writes carved records in the requested format
+pub(crate) struct CarvedRecordWriter<W: Write> {
+    format: CarvedRecordFormat,
+    writer: W,
+}
+
+impl<W: Write> CarvedRecordWriter<W> {
+    pub fn new(format: CarvedRecordFormat, writer: W) -> Self {
+        Self { format, writer }
+    }
+
+    pub fn write(&mut self, carved: CarvedRecord) -> anyhow::Result<()> {
+        let location = match carved.chunk_offset {
+            Some(chunk_offset) if carved.in_slack => format!(
+                "offset 0x{:x} (in the slack of the chunk at offset 0x{chunk_offset:x})",
+                carved.offset
+            ),
+            Some(chunk_offset) => format!(
+                "offset 0x{:x} (chunk at offset 0x{chunk_offset:x})",
+                carved.offset
+            ),
+            None => format!("offset 0x{:x} (outside of any chunk)", carved.offset),
+        };
+
+        match self.format {
+            CarvedRecordFormat::Xml => {
+                writeln!(
+                    self.writer,
+                    "<!-- record {} at {location}, {} -->",
+                    carved.event_record_id,
+                    carved.timestamp.to_rfc3339()
+                )?;
+                match carved.record {
+                    Some(record) => writeln!(self.writer, "{}", record.into_xml()?.data)?,
+                    None if carved.in_slack => writeln!(
+                        self.writer,
+                        "<!-- the record has been overwritten and cannot be parsed -->"
+                    )?,
+                    None => writeln!(
+                        self.writer,
+                        "<!-- the record cannot be parsed without its chunk -->"
+                    )?,
+                }
+            }
+            CarvedRecordFormat::Json => {
+                let data = match carved.record {
+                    Some(record) => record.into_json_value()?.data,
+                    None => serde_json::Value::Null,
+                };
+                let value = json!({
+                    "offset": carved.offset,
+                    "chunk_offset": carved.chunk_offset,
+                    "in_slack": carved.in_slack,
+                    "event_record_id": carved.event_record_id,
+                    "timestamp": carved.timestamp,
+                    "data": data,
+                });
+                writeln!(self.writer, "{value}")?;
+            }
+            CarvedRecordFormat::Bodyfile => {
+                let (event_id, provider_name, channel_name) = match carved.record {
+                    Some(record) => {
+                        let data = record.into_json_value()?.data;
+                        let system = &data["Event"]["System"];
+                        let event_id = match system["EventID"].get("#text") {
+                            Some(event_id) => event_id.clone(),
+                            None => system["EventID"].clone(),
+                        };
+                        (
+                            event_id,
+                            system["Provider"]["#attributes"]["Name"].clone(),
+                            system["Channel"].clone(),
+                        )
+                    }
+                    None => Default::default(),
+                };
+                let name = json!({
+                    "event_record_id": carved.event_record_id,
+                    "event_id": event_id,
+                    "provider_name": provider_name,
+                    "channel_name": channel_name,
+                    "carved_from": location,
+                });
+                let bf_line = Bodyfile3Line::new()
+                    .with_mtime(Modified::from(carved.timestamp.timestamp()))
+                    .with_owned_name(name.to_string());
+                writeln!(self.writer, "{bf_line}")?;
+            }
+        }
+        Ok(())
+    }
+}
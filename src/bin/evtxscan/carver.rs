@@ -0,0 +1,429 @@
+use std::{io::Read, sync::Arc};
+
+use chrono::{DateTime, Datelike, Utc};
+use dfir_toolkit::evtx::{
+    EVTX_CHUNK_HEADER_SIZE, EVTX_CHUNK_MAGIC, EVTX_CHUNK_SIZE, EVTX_RECORD_HEADER_SIZE,
+    EVTX_RECORD_MAGIC,
+};
+use evtx::{EvtxChunkData, EvtxRecord, ParserSettings};
+
+const BINXML_FRAGMENT_HEADER: &[u8] = &[0x0f, 0x01, 0x01, 0x00];
+
+/// number of bytes which are scanned at once. Additionally, the following
+/// chunk size is kept in memory, so that signatures at the end of a block
+/// can be validated.
+const BLOCK_SIZE: usize = 16 * 1024 * 1024;
+
+/// a record which has been found in arbitrary binary data
+pub(crate) struct CarvedRecord<'a> {
+    /// offset of the record in the input data
+    pub offset: u64,
+
+    /// offset of the chunk in the input data, if the record has been found
+    /// in a valid chunk
+    pub chunk_offset: Option<u64>,
+
+    /// the record has been found in the slack of its chunk, i.e. after the
+    /// free space offset, where overwritten records remain
+    pub in_slack: bool,
+
+    pub event_record_id: u64,
+    pub timestamp: DateTime<Utc>,
+
+    /// the parsed record. Records whose chunk could not be found cannot be
+    /// parsed, because they reference strings and templates of their chunk.
+    pub record: Option<EvtxRecord<'a>>,
+}
+
+/// scans arbitrary binary data for evtx chunks and records
+pub(crate) struct Carver {
+    settings: Arc<ParserSettings>,
+}
+
+impl Default for Carver {
+    fn default() -> Self {
+        Self {
+            settings: Arc::new(ParserSettings::default().validate_checksums(false)),
+        }
+    }
+}
+
+impl Carver {
+    /// calls `handle_record` for every record which can be found in
+    /// `reader`. Records are searched in intact chunks first. Records outside
+    /// of any intact chunk, or in the unused space at the end of an intact
+    /// chunk, are reported without their content.
+    pub fn carve<R, F>(&self, mut reader: R, mut handle_record: F) -> anyhow::Result<()>
+    where
+        R: Read,
+        F: FnMut(CarvedRecord) -> anyhow::Result<()>,
+    {
+        let mut buffer: Vec<u8> = Vec::with_capacity(BLOCK_SIZE + EVTX_CHUNK_SIZE);
+        let mut buffer_offset: u64 = 0;
+        let mut skip_until: u64 = 0;
+        let mut eof = false;
+
+        loop {
+            while !eof && buffer.len() < BLOCK_SIZE + EVTX_CHUNK_SIZE {
+                let start = buffer.len();
+                buffer.resize(BLOCK_SIZE + EVTX_CHUNK_SIZE, 0);
+                let bytes_read = reader.read(&mut buffer[start..])?;
+                buffer.truncate(start + bytes_read);
+                eof = bytes_read == 0;
+            }
+
+            let limit = if eof { buffer.len() } else { BLOCK_SIZE };
+
+            let mut pos = skip_until.saturating_sub(buffer_offset) as usize;
+            while let Some(next) = buffer[pos.min(limit)..limit]
+                .iter()
+                .position(|b| *b == EVTX_CHUNK_MAGIC[0] || *b == EVTX_RECORD_MAGIC[0])
+            {
+                pos += next;
+                let offset = buffer_offset + pos as u64;
+                let data = &buffer[pos..];
+
+                // only the last chunk of the input can be shorter than
+                // `EVTX_CHUNK_SIZE`, if the input has been truncated
+                if data.starts_with(EVTX_CHUNK_MAGIC) && data.len() >= EVTX_CHUNK_HEADER_SIZE {
+                    let chunk_size = data.len().min(EVTX_CHUNK_SIZE);
+                    if self.carve_chunk(offset, &data[..chunk_size], &mut handle_record)? {
+                        pos += chunk_size;
+                        continue;
+                    }
+                } else if let Some((size, event_record_id, timestamp)) = validate_record(data) {
+                    log::info!("found record {event_record_id} outside of any chunk at offset 0x{offset:x}");
+                    handle_record(CarvedRecord {
+                        offset,
+                        chunk_offset: None,
+                        in_slack: false,
+                        event_record_id,
+                        timestamp,
+                        record: None,
+                    })?;
+                    pos += size;
+                    continue;
+                }
+                pos += 1;
+            }
+
+            skip_until = buffer_offset + pos as u64;
+            if eof {
+                break;
+            }
+            buffer.drain(..limit);
+            buffer_offset += limit as u64;
+        }
+        Ok(())
+    }
+
+    /// tries to parse a chunk and returns `true` if this was successful.
+    /// A truncated chunk is padded with zeros, and only its complete records
+    /// are parsed.
+    fn carve_chunk<F>(
+        &self,
+        chunk_offset: u64,
+        data: &[u8],
+        handle_record: &mut F,
+    ) -> anyhow::Result<bool>
+    where
+        F: FnMut(CarvedRecord) -> anyhow::Result<()>,
+    {
+        let truncated = data.len() < EVTX_CHUNK_SIZE;
+        let mut chunk_bytes = data.to_vec();
+        chunk_bytes.resize(EVTX_CHUNK_SIZE, 0);
+
+        let mut chunk_data = match EvtxChunkData::new(chunk_bytes, false) {
+            Ok(chunk_data) => chunk_data,
+            Err(why) => {
+                log::info!("invalid chunk at offset 0x{chunk_offset:x}: {why}");
+                return Ok(false);
+            }
+        };
+
+        if !chunk_data.validate_header_checksum() {
+            log::warn!("the chunk at offset 0x{chunk_offset:x} has an invalid header checksum");
+        }
+        if truncated {
+            log::warn!(
+                "the chunk at offset 0x{chunk_offset:x} is truncated after 0x{:x} bytes",
+                data.len()
+            );
+        } else if !chunk_data.validate_data_checksum() {
+            log::warn!("the chunk at offset 0x{chunk_offset:x} has an invalid data checksum");
+        }
+
+        let mut chunk = match chunk_data.parse(Arc::clone(&self.settings)) {
+            Ok(chunk) => chunk,
+            Err(why) => {
+                log::info!("unable to parse chunk at offset 0x{chunk_offset:x}: {why}");
+                return Ok(false);
+            }
+        };
+
+        let record_offsets = record_offsets(data, chunk.header.free_space_offset as usize);
+        log::info!(
+            "found chunk at offset 0x{chunk_offset:x} with {} records",
+            record_offsets.len()
+        );
+
+        for record in chunk.iter() {
+            match record {
+                Err(why) => log::warn!(
+                    "unable to parse record in chunk at offset 0x{chunk_offset:x}: {why}"
+                ),
+                Ok(record) => {
+                    let offset = record_offsets
+                        .iter()
+                        .find(|(_, id)| *id == record.event_record_id)
+                        .map(|(offset, _)| chunk_offset + *offset as u64);
+                    if offset.is_none() && truncated {
+                        log::warn!(
+                            "record {} in chunk at offset 0x{chunk_offset:x} is truncated",
+                            record.event_record_id
+                        );
+                        continue;
+                    }
+                    let offset = offset.unwrap_or(chunk_offset);
+                    handle_record(CarvedRecord {
+                        offset,
+                        chunk_offset: Some(chunk_offset),
+                        in_slack: false,
+                        event_record_id: record.event_record_id,
+                        timestamp: record.timestamp,
+                        record: Some(record),
+                    })?;
+                }
+            }
+        }
+
+        let free_space_offset = chunk.header.free_space_offset as usize;
+        carve_slack(chunk_offset, data, free_space_offset, handle_record)?;
+        Ok(true)
+    }
+}
+
+/// searches for records in the unused space at the end of a chunk. Such
+/// records cannot be parsed, because the strings and templates they
+/// reference might have been overwritten.
+fn carve_slack<F>(
+    chunk_offset: u64,
+    data: &[u8],
+    free_space_offset: usize,
+    handle_record: &mut F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(CarvedRecord) -> anyhow::Result<()>,
+{
+    let mut pos = free_space_offset.clamp(EVTX_CHUNK_HEADER_SIZE, data.len());
+    while let Some(next) = data[pos..].iter().position(|b| *b == EVTX_RECORD_MAGIC[0]) {
+        pos += next;
+        match validate_record(&data[pos..]) {
+            Some((size, event_record_id, timestamp)) => {
+                let offset = chunk_offset + pos as u64;
+                log::info!(
+                    "found record {event_record_id} in the slack of the chunk at offset 0x{chunk_offset:x}"
+                );
+                handle_record(CarvedRecord {
+                    offset,
+                    chunk_offset: Some(chunk_offset),
+                    in_slack: true,
+                    event_record_id,
+                    timestamp,
+                    record: None,
+                })?;
+                pos += size;
+            }
+            None => pos += 1,
+        }
+    }
+    Ok(())
+}
+
+/// returns the offsets and ids of all records of a chunk
+fn record_offsets(chunk: &[u8], free_space_offset: usize) -> Vec<(usize, u64)> {
+    let end = free_space_offset.min(chunk.len());
+    let mut offsets = Vec::new();
+    let mut offset = EVTX_CHUNK_HEADER_SIZE;
+    while offset < end {
+        match validate_record(&chunk[offset..end]) {
+            Some((size, event_record_id, _)) => {
+                offsets.push((offset, event_record_id));
+                offset += size;
+            }
+            None => break,
+        }
+    }
+    offsets
+}
+
+/// checks if `data` starts with a plausible record, and returns its size,
+/// its event record id and its timestamp
+fn validate_record(data: &[u8]) -> Option<(usize, u64, DateTime<Utc>)> {
+    if data.len() < EVTX_RECORD_HEADER_SIZE + BINXML_FRAGMENT_HEADER.len() + 4
+        || !data.starts_with(EVTX_RECORD_MAGIC)
+    {
+        return None;
+    }
+
+    let size = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+    if size < EVTX_RECORD_HEADER_SIZE + BINXML_FRAGMENT_HEADER.len() + 4
+        || size > EVTX_CHUNK_SIZE - EVTX_CHUNK_HEADER_SIZE
+        || size > data.len()
+    {
+        return None;
+    }
+
+    // the size is repeated at the end of the record
+    let trailing_size = u32::from_le_bytes(data[size - 4..size].try_into().unwrap()) as usize;
+    if trailing_size != size {
+        return None;
+    }
+
+    if !data[EVTX_RECORD_HEADER_SIZE..].starts_with(BINXML_FRAGMENT_HEADER) {
+        return None;
+    }
+
+    let event_record_id = u64::from_le_bytes(data[8..16].try_into().unwrap());
+    let filetime = u64::from_le_bytes(data[16..24].try_into().unwrap());
+    let timestamp = filetime_to_datetime(filetime)?;
+    if !(1990..=2100).contains(&timestamp.year()) {
+        return None;
+    }
+
+    Some((size, event_record_id, timestamp))
+}
+
+pub(crate) fn filetime_to_datetime(filetime: u64) -> Option<DateTime<Utc>> {
+    const FILETIME_UNIX_EPOCH: i64 = 116_444_736_000_000_000;
+    let hundred_nanos = i64::try_from(filetime).ok()? - FILETIME_UNIX_EPOCH;
+    DateTime::from_timestamp(
+        hundred_nanos.div_euclid(10_000_000),
+        (hundred_nanos.rem_euclid(10_000_000) * 100) as u32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use dfir_toolkit::evtx::{chunk_header_checksum, EVTX_CHUNK_HEADER_SIZE, EVTX_CHUNK_SIZE};
+
+    use super::{validate_record, Carver, BINXML_FRAGMENT_HEADER};
+
+    fn record(event_record_id: u64, filetime: u64) -> Vec<u8> {
+        let size: u32 = 40;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"**\0\0");
+        data.extend_from_slice(&size.to_le_bytes());
+        data.extend_from_slice(&event_record_id.to_le_bytes());
+        data.extend_from_slice(&filetime.to_le_bytes());
+        data.extend_from_slice(BINXML_FRAGMENT_HEADER);
+        data.resize(size as usize - 4, 0);
+        data.extend_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    // 2023-11-14T14:06:40Z
+    const FILETIME: u64 = 133_444_444_000_000_000;
+
+    #[test]
+    fn validates_records() {
+        let (size, id, timestamp) = validate_record(&record(42, FILETIME)).unwrap();
+        assert_eq!(size, 40);
+        assert_eq!(id, 42);
+        assert_eq!(timestamp.to_rfc3339(), "2023-11-14T14:06:40+00:00");
+
+        assert!(validate_record(&record(42, 0)).is_none());
+
+        let mut corrupted = record(42, FILETIME);
+        corrupted[36] = 0xff;
+        assert!(validate_record(&corrupted).is_none());
+    }
+
+    #[test]
+    fn finds_orphaned_records() {
+        let mut data = vec![b'*'; 1000];
+        data.extend(record(7, FILETIME));
+        data.extend(vec![0; 3]);
+        data.extend(record(8, FILETIME));
+
+        let mut found = Vec::new();
+        Carver::default()
+            .carve(&data[..], |record| {
+                assert!(record.record.is_none());
+                found.push((record.offset, record.event_record_id));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(found, vec![(1000, 7), (1043, 8)]);
+    }
+
+    #[test]
+    fn finds_records_in_chunk_slack() {
+        // an intact chunk without any records, but with the remains of
+        // an overwritten record after its free space offset
+        let mut chunk = vec![0; EVTX_CHUNK_SIZE];
+        chunk[..8].copy_from_slice(b"ElfChnk\0");
+        chunk[40..44].copy_from_slice(&128u32.to_le_bytes());
+        chunk[48..52].copy_from_slice(&(EVTX_CHUNK_HEADER_SIZE as u32).to_le_bytes());
+        chunk[52..56].copy_from_slice(&crc32fast::hash(&[]).to_le_bytes());
+        let header_checksum = chunk_header_checksum(&chunk);
+        chunk[124..128].copy_from_slice(&header_checksum.to_le_bytes());
+        chunk[0x1000..0x1000 + 40].copy_from_slice(&record(13, FILETIME));
+
+        let mut data = vec![0; 100];
+        data.extend(chunk);
+
+        let mut found = Vec::new();
+        Carver::default()
+            .carve(&data[..], |record| {
+                assert!(record.record.is_none());
+                found.push((
+                    record.offset,
+                    record.chunk_offset,
+                    record.in_slack,
+                    record.event_record_id,
+                ));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(found, vec![(100 + 0x1000, Some(100), true, 13)]);
+    }
+
+    #[test]
+    fn finds_records_in_truncated_chunk() {
+        let mut chunk = vec![0; EVTX_CHUNK_SIZE];
+        chunk[..8].copy_from_slice(b"ElfChnk\0");
+        chunk[8..16].copy_from_slice(&1u64.to_le_bytes());
+        chunk[16..24].copy_from_slice(&2u64.to_le_bytes());
+        chunk[24..32].copy_from_slice(&1u64.to_le_bytes());
+        chunk[32..40].copy_from_slice(&2u64.to_le_bytes());
+        chunk[40..44].copy_from_slice(&128u32.to_le_bytes());
+        chunk[44..48].copy_from_slice(&(EVTX_CHUNK_HEADER_SIZE as u32 + 40).to_le_bytes());
+        chunk[48..52].copy_from_slice(&(EVTX_CHUNK_HEADER_SIZE as u32 + 80).to_le_bytes());
+        let records = [record(1, FILETIME), record(2, FILETIME)].concat();
+        chunk[EVTX_CHUNK_HEADER_SIZE..EVTX_CHUNK_HEADER_SIZE + 80].copy_from_slice(&records);
+        let header_checksum = chunk_header_checksum(&chunk);
+        chunk[124..128].copy_from_slice(&header_checksum.to_le_bytes());
+
+        // the input ends in the middle of the second record
+        chunk.truncate(EVTX_CHUNK_HEADER_SIZE + 60);
+
+        let mut found = Vec::new();
+        Carver::default()
+            .carve(&chunk[..], |record| {
+                found.push((
+                    record.offset,
+                    record.chunk_offset,
+                    record.in_slack,
+                    record.event_record_id,
+                    record.record.is_some(),
+                ));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![(EVTX_CHUNK_HEADER_SIZE as u64, Some(0), false, 1, true)]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::io::{Read, Seek};
+
+use dfir_toolkit::evtx::chunk_offset;
+use evtx::EvtxParser;
+use serde::Serialize;
+
+/// information about a chunk and the validity of its checksums
+#[derive(Serialize)]
+pub(crate) struct ChunkSummary {
+    pub chunk_number: u64,
+    pub offset: u64,
+    pub first_record_id: u64,
+    pub last_record_id: u64,
+    pub header_checksum_valid: bool,
+    pub data_checksum_valid: bool,
+}
+
+impl ChunkSummary {
+    /// reads the headers of all non-empty chunks of a file
+    pub fn read_all<T: Read + Seek>(parser: &mut EvtxParser<T>) -> Vec<Self> {
+        let mut summaries = Vec::new();
+        let mut next_chunk_number = 0;
+
+        while let Some((chunk_data, chunk_number)) = parser.find_next_chunk(next_chunk_number) {
+            next_chunk_number = chunk_number + 1;
+            match chunk_data {
+                Err(why) => log::warn!("unable to read chunk {chunk_number}: {why}"),
+                Ok(chunk_data) => summaries.push(Self {
+                    chunk_number,
+                    offset: chunk_offset(chunk_number),
+                    first_record_id: chunk_data.header.first_event_record_id,
+                    last_record_id: chunk_data.header.last_event_record_id,
+                    header_checksum_valid: chunk_data.validate_header_checksum(),
+                    data_checksum_valid: chunk_data.validate_data_checksum(),
+                }),
+            }
+        }
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dfir_toolkit::evtx::{
+        chunk_header_checksum, EVTX_CHUNK_HEADER_SIZE, EVTX_CHUNK_SIZE, EVTX_FILE_HEADER_SIZE,
+    };
+    use evtx::EvtxParser;
+
+    use super::ChunkSummary;
+
+    fn file_header() -> Vec<u8> {
+        let mut header = vec![0; EVTX_FILE_HEADER_SIZE];
+        header[..8].copy_from_slice(b"ElfFile\0");
+        header[32..36].copy_from_slice(&128u32.to_le_bytes());
+        header[36..38].copy_from_slice(&1u16.to_le_bytes());
+        header[38..40].copy_from_slice(&3u16.to_le_bytes());
+        header[40..42].copy_from_slice(&(EVTX_FILE_HEADER_SIZE as u16).to_le_bytes());
+        header
+    }
+
+    /// creates an empty chunk with correct checksums
+    fn chunk(first_id: u64, last_id: u64) -> Vec<u8> {
+        let mut chunk = vec![0; EVTX_CHUNK_SIZE];
+        chunk[..8].copy_from_slice(b"ElfChnk\0");
+        chunk[24..32].copy_from_slice(&first_id.to_le_bytes());
+        chunk[32..40].copy_from_slice(&last_id.to_le_bytes());
+        chunk[40..44].copy_from_slice(&128u32.to_le_bytes());
+        chunk[48..52].copy_from_slice(&(EVTX_CHUNK_HEADER_SIZE as u32).to_le_bytes());
+        chunk[52..56].copy_from_slice(&crc32fast::hash(&[]).to_le_bytes());
+        let header_checksum = chunk_header_checksum(&chunk);
+        chunk[124..128].copy_from_slice(&header_checksum.to_le_bytes());
+        chunk
+    }
+
+    #[test]
+    fn read_chunk_summaries() {
+        let mut data = file_header();
+        data.extend(chunk(1, 10));
+        data.extend(vec![0; EVTX_CHUNK_SIZE]);
+
+        // the last record id has been modified without updating the checksum
+        let mut modified = chunk(11, 20);
+        modified[32..40].copy_from_slice(&19u64.to_le_bytes());
+        data.extend(modified);
+
+        let mut parser = EvtxParser::from_buffer(data).unwrap();
+        let summaries = ChunkSummary::read_all(&mut parser);
+        assert_eq!(summaries.len(), 2);
+
+        assert_eq!(summaries[0].chunk_number, 0);
+        assert_eq!(summaries[0].offset, 0x1000);
+        assert_eq!(summaries[0].first_record_id, 1);
+        assert_eq!(summaries[0].last_record_id, 10);
+        assert!(summaries[0].header_checksum_valid);
+        assert!(summaries[0].data_checksum_valid);
+
+        assert_eq!(summaries[1].chunk_number, 2);
+        assert_eq!(summaries[1].offset, 0x21000);
+        assert_eq!(summaries[1].last_record_id, 19);
+        assert!(!summaries[1].header_checksum_valid);
+        assert!(summaries[1].data_checksum_valid);
+    }
+}
@@ -2,12 +2,16 @@ use clap::{Parser, ValueHint};
 use dfir_toolkit::common::HasVerboseFlag;
 use log::LevelFilter;
 
+use crate::carved_output::CarvedRecordFormat;
+use crate::report::ReportFormat;
 
-/// Find time skews in an evtx file
+
+/// Find time skews in an evtx file, or carve evtx records from arbitrary binary data
 #[derive(Parser)]
 #[clap(name=env!("CARGO_BIN_NAME"), author, version)]
 pub (crate) struct Cli {
-    /// name of the evtx file to scan
+    /// name of the evtx file to scan. When carving, this can be any file,
+    /// such as a raw disk image or a pagefile
     #[clap(value_hint=ValueHint::FilePath)]
     pub (crate) evtx_file: String,
 
@@ -19,6 +23,22 @@ pub (crate) struct Cli {
     #[clap(short = 'N', long, default_value_t = 5)]
     pub (crate) negative_tolerance: u32,
 
+    /// don't search for time skews, but scan the file for evtx chunks and
+    /// records and display all records found in the specified format
+    #[clap(short = 'c', long, value_enum, conflicts_with = "show_records")]
+    pub (crate) carve: Option<CarvedRecordFormat>,
+
+    /// check the checksums and the consistency of file header, chunk headers
+    /// and records, and display all anomalies which might indicate tampering
+    #[clap(short = 'I', long, conflicts_with = "carve")]
+    pub (crate) integrity: bool,
+
+    /// instead of displaying the findings as text, write a report in the specified
+    /// format, containing all ranges, gaps, time skews, log clearings, chunk checksums
+    /// and all integrity anomalies
+    #[clap(short = 'R', long, value_enum, conflicts_with_all = ["show_records", "carve"])]
+    pub (crate) report: Option<ReportFormat>,
+
     #[clap(flatten)]
     verbose: clap_verbosity_flag::Verbosity,
 }
@@ -0,0 +1,502 @@
+use std::{
+    fmt::Display,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::bail;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+use dfir_toolkit::evtx::{
+    chunk_header_checksum, chunk_offset, CHUNK_FLAG_NO_CRC32, EVTX_CHUNK_HEADER_SIZE,
+    EVTX_CHUNK_MAGIC, EVTX_CHUNK_SIZE, EVTX_FILE_HEADER_SIZE, EVTX_FILE_MAGIC, EVTX_RECORD_HEADER_SIZE,
+    EVTX_RECORD_MAGIC, FILE_FLAG_DIRTY,
+};
+
+use crate::carver::filetime_to_datetime;
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AnomalyKind {
+    FileHeaderChecksum,
+    FileHeaderInconsistency,
+    FileSize,
+    ChunkHeaderChecksum,
+    ChunkDataChecksum,
+    ChunkHeaderInconsistency,
+    RecordSize,
+    RecordIdSequence,
+    EmbeddedRecord,
+    TimestampOrder,
+}
+
+/// something in the structure of an evtx file which should not be there if
+/// the file had been written by Windows only
+#[derive(Serialize)]
+pub(crate) struct Anomaly {
+    pub kind: AnomalyKind,
+    pub chunk_number: Option<u64>,
+    pub offset: Option<u64>,
+    pub record_id: Option<u64>,
+    pub description: String,
+}
+
+impl Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut location = Vec::new();
+        if let Some(chunk_number) = self.chunk_number {
+            location.push(format!("chunk {chunk_number}"));
+        }
+        if let Some(record_id) = self.record_id {
+            location.push(format!("record {record_id}"));
+        }
+        if let Some(offset) = self.offset {
+            location.push(format!("offset 0x{offset:x}"));
+        }
+        if location.is_empty() {
+            write!(f, "{}", self.description)
+        } else {
+            write!(f, "{}: {}", location.join(", "), self.description)
+        }
+    }
+}
+
+/// checks the checksums and the consistency of the headers and records of
+/// an evtx file. Windows never modifies single records, so every
+/// inconsistency hints to a manipulation, such as the removal of records.
+pub(crate) struct IntegrityChecker {
+    allowed_bias: Duration,
+    anomalies: Vec<Anomaly>,
+}
+
+impl IntegrityChecker {
+    pub fn new(allowed_bias: Duration) -> Self {
+        Self {
+            allowed_bias,
+            anomalies: Vec::new(),
+        }
+    }
+
+    pub fn check_file(mut self, path: &Path) -> anyhow::Result<Vec<Anomaly>> {
+        let mut file = File::open(path)?;
+        let file_size = file.metadata()?.len();
+
+        let mut header = vec![0; EVTX_FILE_HEADER_SIZE];
+        if file_size < EVTX_FILE_HEADER_SIZE as u64 {
+            bail!("the file is too small to be an evtx file");
+        }
+        file.read_exact(&mut header)?;
+        if !header.starts_with(EVTX_FILE_MAGIC) {
+            bail!("the file has no valid evtx file header");
+        }
+
+        let data_size = file_size - EVTX_FILE_HEADER_SIZE as u64;
+        if data_size % EVTX_CHUNK_SIZE as u64 != 0 {
+            self.add(
+                AnomalyKind::FileSize,
+                None,
+                None,
+                None,
+                format!("the file size is not a multiple of the chunk size, {} bytes remain after the last chunk", data_size % EVTX_CHUNK_SIZE as u64),
+            );
+        }
+
+        let mut max_record_id = None;
+        let mut chunk_count = 0;
+        let mut chunk = vec![0; EVTX_CHUNK_SIZE];
+        for chunk_number in 0..data_size / EVTX_CHUNK_SIZE as u64 {
+            let chunk_offset = chunk_offset(chunk_number);
+            file.seek(SeekFrom::Start(chunk_offset))?;
+            file.read_exact(&mut chunk)?;
+
+            // unused chunks are filled with zeros
+            if chunk.iter().all(|b| *b == 0) {
+                continue;
+            }
+            chunk_count += 1;
+
+            if let Some(last_record_id) = self.check_chunk(chunk_number, chunk_offset, &chunk) {
+                max_record_id = max_record_id.max(Some(last_record_id));
+            }
+        }
+
+        self.check_file_header(&header, chunk_count, max_record_id);
+        Ok(self.anomalies)
+    }
+
+    fn check_file_header(&mut self, header: &[u8], chunk_count: u64, max_record_id: Option<u64>) {
+        let stored_checksum = read_u32(header, 124);
+        let checksum = crc32fast::hash(&header[..120]);
+        if checksum != stored_checksum {
+            self.add(
+                AnomalyKind::FileHeaderChecksum,
+                None,
+                Some(0),
+                None,
+                format!("the file header checksum is 0x{stored_checksum:08x}, but should be 0x{checksum:08x}"),
+            );
+        }
+
+        let header_chunk_count = read_u16(header, 42) as u64;
+        if header_chunk_count != chunk_count {
+            self.add(
+                AnomalyKind::FileHeaderInconsistency,
+                None,
+                Some(42),
+                None,
+                format!("the file header announces {header_chunk_count} chunks, but {chunk_count} chunks are in use"),
+            );
+        }
+
+        // the header is not updated while the file is opened by the eventlog service
+        let is_dirty = read_u32(header, 120) & FILE_FLAG_DIRTY != 0;
+        let next_record_id = read_u64(header, 24);
+        if let Some(max_record_id) = max_record_id {
+            if !is_dirty && next_record_id != max_record_id + 1 {
+                self.add(
+                    AnomalyKind::FileHeaderInconsistency,
+                    None,
+                    Some(24),
+                    None,
+                    format!("the next record id in the file header is {next_record_id}, but the last record has the id {max_record_id}"),
+                );
+            }
+        }
+    }
+
+    /// checks a chunk and returns the id of its last record
+    fn check_chunk(&mut self, chunk_number: u64, chunk_offset: u64, chunk: &[u8]) -> Option<u64> {
+        if !chunk.starts_with(EVTX_CHUNK_MAGIC) {
+            self.add(
+                AnomalyKind::ChunkHeaderInconsistency,
+                Some(chunk_number),
+                Some(chunk_offset),
+                None,
+                "the chunk has no valid signature".into(),
+            );
+            return None;
+        }
+
+        let first_record_number = read_u64(chunk, 8);
+        let last_record_number = read_u64(chunk, 16);
+        let first_record_id = read_u64(chunk, 24);
+        let last_record_id = read_u64(chunk, 32);
+        let last_record_offset = read_u32(chunk, 44) as usize;
+        let free_space_offset = read_u32(chunk, 48) as usize;
+        let stored_data_checksum = read_u32(chunk, 52);
+        let flags = read_u32(chunk, 120);
+        let stored_header_checksum = read_u32(chunk, 124);
+
+        let header_checksum = chunk_header_checksum(chunk);
+        if header_checksum != stored_header_checksum {
+            self.add(
+                AnomalyKind::ChunkHeaderChecksum,
+                Some(chunk_number),
+                Some(chunk_offset),
+                None,
+                format!("the chunk header checksum is 0x{stored_header_checksum:08x}, but should be 0x{header_checksum:08x}"),
+            );
+        }
+
+        if !(EVTX_CHUNK_HEADER_SIZE..=EVTX_CHUNK_SIZE).contains(&free_space_offset) {
+            self.add(
+                AnomalyKind::ChunkHeaderInconsistency,
+                Some(chunk_number),
+                Some(chunk_offset + 48),
+                None,
+                format!("invalid free space offset 0x{free_space_offset:x}"),
+            );
+            return None;
+        }
+
+        if flags & CHUNK_FLAG_NO_CRC32 == 0 {
+            let data_checksum = crc32fast::hash(&chunk[EVTX_CHUNK_HEADER_SIZE..free_space_offset]);
+            if data_checksum != stored_data_checksum {
+                self.add(
+                    AnomalyKind::ChunkDataChecksum,
+                    Some(chunk_number),
+                    Some(chunk_offset),
+                    None,
+                    format!("the checksum of the records is 0x{stored_data_checksum:08x}, but should be 0x{data_checksum:08x}"),
+                );
+            }
+        }
+
+        let mut previous: Option<(u64, DateTime<Utc>)> = None;
+        let mut current_record_offset = None;
+        let mut record_count = 0;
+        let mut offset = EVTX_CHUNK_HEADER_SIZE;
+        while offset < free_space_offset {
+            let record_offset = Some(chunk_offset + offset as u64);
+            let size = match record_size(&chunk[offset..free_space_offset]) {
+                Ok(size) => size,
+                Err(why) => {
+                    self.add(
+                        AnomalyKind::RecordSize,
+                        Some(chunk_number),
+                        record_offset,
+                        None,
+                        why,
+                    );
+                    break;
+                }
+            };
+
+            let record = &chunk[offset..offset + size];
+            let record_id = read_u64(record, 8);
+            let timestamp = filetime_to_datetime(read_u64(record, 16)).unwrap_or_default();
+
+            let trailing_size = read_u32(record, size - 4) as usize;
+            if trailing_size != size {
+                self.add(
+                    AnomalyKind::RecordSize,
+                    Some(chunk_number),
+                    record_offset,
+                    Some(record_id),
+                    format!("the record has a size of {size} bytes, but its trailer says {trailing_size} bytes"),
+                );
+            }
+
+            // removed records are often merged into their predecessor
+            if let Some(embedded_id) = find_embedded_record(record) {
+                self.add(
+                    AnomalyKind::EmbeddedRecord,
+                    Some(chunk_number),
+                    record_offset,
+                    Some(record_id),
+                    format!("the record contains the remains of the record {embedded_id}"),
+                );
+            }
+
+            match previous {
+                None => {
+                    if record_id != first_record_id {
+                        self.add(
+                            AnomalyKind::ChunkHeaderInconsistency,
+                            Some(chunk_number),
+                            record_offset,
+                            Some(record_id),
+                            format!("the first record should have the id {first_record_id}"),
+                        );
+                    }
+                }
+                Some((previous_id, previous_timestamp)) => {
+                    if record_id != previous_id + 1 {
+                        self.add(
+                            AnomalyKind::RecordIdSequence,
+                            Some(chunk_number),
+                            record_offset,
+                            Some(record_id),
+                            format!("the record follows the record {previous_id}"),
+                        );
+                    }
+                    if timestamp + self.allowed_bias < previous_timestamp {
+                        self.add(
+                            AnomalyKind::TimestampOrder,
+                            Some(chunk_number),
+                            record_offset,
+                            Some(record_id),
+                            format!(
+                                "the record has been written at {}, but the record {previous_id} at {}",
+                                timestamp.format("%FT%T"),
+                                previous_timestamp.format("%FT%T")
+                            ),
+                        );
+                    }
+                }
+            }
+
+            previous = Some((record_id, timestamp));
+            current_record_offset = Some(offset);
+            record_count += 1;
+            offset += size;
+        }
+
+        let (last_id, _) = previous?;
+        if last_id != last_record_id {
+            self.add(
+                AnomalyKind::ChunkHeaderInconsistency,
+                Some(chunk_number),
+                Some(chunk_offset + 32),
+                Some(last_id),
+                format!("the last record should have the id {last_record_id}"),
+            );
+        }
+        if current_record_offset != Some(last_record_offset) {
+            self.add(
+                AnomalyKind::ChunkHeaderInconsistency,
+                Some(chunk_number),
+                Some(chunk_offset + 44),
+                Some(last_id),
+                format!("the chunk header says that the last record starts at chunk offset 0x{last_record_offset:x}"),
+            );
+        }
+        let expected_count = (last_record_number + 1).saturating_sub(first_record_number);
+        if record_count != expected_count {
+            self.add(
+                AnomalyKind::ChunkHeaderInconsistency,
+                Some(chunk_number),
+                Some(chunk_offset + 8),
+                None,
+                format!("the chunk contains {record_count} records, but its header announces {expected_count} records"),
+            );
+        }
+
+        Some(last_id)
+    }
+
+    fn add(
+        &mut self,
+        kind: AnomalyKind,
+        chunk_number: Option<u64>,
+        offset: Option<u64>,
+        record_id: Option<u64>,
+        description: String,
+    ) {
+        self.anomalies.push(Anomaly {
+            kind,
+            chunk_number,
+            offset,
+            record_id,
+            description,
+        })
+    }
+}
+
+/// returns the size of the record at the beginning of `data`
+fn record_size(data: &[u8]) -> Result<usize, String> {
+    if data.len() < EVTX_RECORD_HEADER_SIZE + 4 || !data.starts_with(EVTX_RECORD_MAGIC) {
+        return Err("there is no record where a record is expected".into());
+    }
+    let size = read_u32(data, 4) as usize;
+    if size < EVTX_RECORD_HEADER_SIZE + 4 || size > data.len() {
+        return Err(format!("the record has an invalid size of {size} bytes"));
+    }
+    Ok(size)
+}
+
+/// searches for a complete record inside of the data of a record
+fn find_embedded_record(record: &[u8]) -> Option<u64> {
+    let data = &record[EVTX_RECORD_HEADER_SIZE..record.len() - 4];
+    (0..data.len()).find_map(|pos| {
+        let candidate = &data[pos..];
+        let size = record_size(candidate).ok()?;
+        if read_u32(candidate, size - 4) as usize == size {
+            Some(read_u64(candidate, 8))
+        } else {
+            None
+        }
+    })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(data[offset..offset + 2].try_into().unwrap())
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use dfir_toolkit::evtx::{chunk_header_checksum, EVTX_CHUNK_HEADER_SIZE, EVTX_CHUNK_SIZE};
+
+    use super::{AnomalyKind, IntegrityChecker};
+
+    // 2023-11-14T14:06:40Z
+    const FILETIME: u64 = 133_444_444_000_000_000;
+
+    fn record(event_record_id: u64, filetime: u64) -> Vec<u8> {
+        let size: u32 = 48;
+        let mut data = Vec::new();
+        data.extend_from_slice(b"**\0\0");
+        data.extend_from_slice(&size.to_le_bytes());
+        data.extend_from_slice(&event_record_id.to_le_bytes());
+        data.extend_from_slice(&filetime.to_le_bytes());
+        data.resize(size as usize - 4, 0);
+        data.extend_from_slice(&size.to_le_bytes());
+        data
+    }
+
+    /// creates a chunk with correct checksums
+    fn chunk(records: &[Vec<u8>], first_id: u64, last_id: u64) -> Vec<u8> {
+        let mut chunk = vec![0; EVTX_CHUNK_HEADER_SIZE];
+        chunk[..8].copy_from_slice(b"ElfChnk\0");
+        chunk[8..16].copy_from_slice(&first_id.to_le_bytes());
+        chunk[16..24].copy_from_slice(&last_id.to_le_bytes());
+        chunk[24..32].copy_from_slice(&first_id.to_le_bytes());
+        chunk[32..40].copy_from_slice(&last_id.to_le_bytes());
+        chunk[40..44].copy_from_slice(&128u32.to_le_bytes());
+
+        let mut last_record_offset = 0;
+        for record in records {
+            last_record_offset = chunk.len() as u32;
+            chunk.extend(record);
+        }
+        let free_space_offset = chunk.len() as u32;
+        chunk[44..48].copy_from_slice(&last_record_offset.to_le_bytes());
+        chunk[48..52].copy_from_slice(&free_space_offset.to_le_bytes());
+        let data_checksum = crc32fast::hash(&chunk[EVTX_CHUNK_HEADER_SIZE..]);
+        chunk[52..56].copy_from_slice(&data_checksum.to_le_bytes());
+
+        let header_checksum = chunk_header_checksum(&chunk);
+        chunk[124..128].copy_from_slice(&header_checksum.to_le_bytes());
+
+        chunk.resize(EVTX_CHUNK_SIZE, 0);
+        chunk
+    }
+
+    fn anomalies_of(chunk: &[u8]) -> Vec<AnomalyKind> {
+        let mut checker = IntegrityChecker::new(Duration::seconds(5));
+        checker.check_chunk(0, 4096, chunk);
+        checker.anomalies.into_iter().map(|a| a.kind).collect()
+    }
+
+    #[test]
+    fn intact_chunk() {
+        let records: Vec<_> = (1..=3).map(|id| record(id, FILETIME + id)).collect();
+        assert!(anomalies_of(&chunk(&records, 1, 3)).is_empty());
+    }
+
+    #[test]
+    fn merged_record() {
+        // record 2 has been merged into record 1
+        let mut record1 = record(1, FILETIME);
+        record1.truncate(record1.len() - 4);
+        record1.extend(record(2, FILETIME));
+        let size = (record1.len() + 4) as u32;
+        record1[4..8].copy_from_slice(&size.to_le_bytes());
+        record1.extend_from_slice(&size.to_le_bytes());
+
+        let records = vec![record1, record(3, FILETIME)];
+        assert_eq!(
+            anomalies_of(&chunk(&records, 1, 3)),
+            vec![
+                AnomalyKind::EmbeddedRecord,
+                AnomalyKind::RecordIdSequence,
+                AnomalyKind::ChunkHeaderInconsistency
+            ]
+        );
+    }
+
+    #[test]
+    fn modified_records() {
+        let records: Vec<_> = (1..=3).map(|id| record(id, FILETIME)).collect();
+        let mut chunk = chunk(&records, 1, 3);
+
+        // change the timestamp of the last record
+        chunk[EVTX_CHUNK_HEADER_SIZE + 2 * 48 + 16..][..8]
+            .copy_from_slice(&(FILETIME - 3600 * 10_000_000).to_le_bytes());
+        assert_eq!(
+            anomalies_of(&chunk),
+            vec![AnomalyKind::ChunkDataChecksum, AnomalyKind::TimestampOrder]
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{stdout, BufReader, BufWriter},
+    path::PathBuf,
+};
 
 use anyhow::Result;
 use chrono::Duration;
@@ -13,7 +18,18 @@ use term_table::{
     table_cell::{Alignment, TableCell},
 };
 
+mod carved_output;
+mod carver;
+mod chunk_summary;
 mod cli;
+mod integrity;
+mod report;
+
+use carved_output::CarvedRecordWriter;
+use carver::Carver;
+use chunk_summary::ChunkSummary;
+use integrity::IntegrityChecker;
+use report::ScanReport;
 
 fn main() -> Result<()> {
     let cli = Cli::parse_cli();
@@ -22,7 +38,13 @@ fn main() -> Result<()> {
 
     let path = PathBuf::from(&cli.evtx_file);
 
-    let mut parser = EvtxParser::from_path(path)?;
+    if let Some(format) = cli.carve.clone() {
+        let mut writer = CarvedRecordWriter::new(format, BufWriter::new(stdout().lock()));
+        let file = File::open(&path)?;
+        return Carver::default().carve(BufReader::new(file), |record| writer.write(record));
+    }
+
+    let mut parser = EvtxParser::from_path(&path)?;
     for record in parser.records_json_value() {
         match record {
             Err(_) => (),
@@ -55,7 +77,35 @@ fn main() -> Result<()> {
         ranges.push(range)
     }
     ranges.sort();
+
+    let allowed_bias = Duration::seconds(cli.negative_tolerance.into());
+    if let Some(format) = cli.report.clone() {
+        let chunks = ChunkSummary::read_all(&mut parser);
+        let anomalies = IntegrityChecker::new(allowed_bias).check_file(&path)?;
+        let report = ScanReport::new(
+            cli.evtx_file.clone(),
+            &ranges,
+            &records,
+            chunks,
+            anomalies,
+            allowed_bias,
+        );
+        return report.write(format, BufWriter::new(stdout().lock()));
+    }
+
     print_ranges(&ranges, &records, &cli);
+
+    if cli.integrity {
+        let anomalies = IntegrityChecker::new(allowed_bias).check_file(&path)?;
+        if anomalies.is_empty() {
+            println!("no anomalies found");
+        } else {
+            println!("ANOMALIES:");
+            for anomaly in anomalies {
+                println!("  {anomaly}");
+            }
+        }
+    }
     Ok(())
 }
 
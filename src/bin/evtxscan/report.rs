@@ -0,0 +1,406 @@
+use std::{collections::HashMap, io::Write};
+
+use chrono::{DateTime, Duration, Utc};
+use dfir_toolkit::evtx::{EventId, Range};
+use evtx::SerializedEvtxRecord;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{chunk_summary::ChunkSummary, integrity::Anomaly};
+
+#[derive(clap::ValueEnum, Clone)]
+pub(crate) enum ReportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Serialize)]
+struct RangeFinding {
+    first_record_id: u64,
+    last_record_id: u64,
+    first_timestamp: DateTime<Utc>,
+    last_timestamp: DateTime<Utc>,
+    count: usize,
+}
+
+/// records which are missing between two ranges
+#[derive(Serialize)]
+struct GapFinding {
+    last_record_id_before: u64,
+    first_record_id_after: u64,
+    timestamp_before: DateTime<Utc>,
+    timestamp_after: DateTime<Utc>,
+    missing: u64,
+}
+
+/// a record which is older than the record before it
+#[derive(Serialize)]
+struct TimeSkewFinding {
+    previous_record_id: u64,
+    previous_timestamp: DateTime<Utc>,
+    record_id: u64,
+    timestamp: DateTime<Utc>,
+    skew_seconds: i64,
+}
+
+/// a 1102 (Security) or 104 (System) event
+#[derive(Serialize)]
+struct LogClearedFinding {
+    record_id: u64,
+    timestamp: DateTime<Utc>,
+    event_id: u64,
+
+    /// the log which has been cleared
+    channel: Option<String>,
+    user: Option<String>,
+}
+
+/// all findings of evtxscan for one file
+#[derive(Serialize)]
+pub(crate) struct ScanReport {
+    file: String,
+    ranges: Vec<RangeFinding>,
+    gaps: Vec<GapFinding>,
+    time_skews: Vec<TimeSkewFinding>,
+    log_clearings: Vec<LogClearedFinding>,
+    chunks: Vec<ChunkSummary>,
+    anomalies: Vec<Anomaly>,
+}
+
+/// a single finding as line in a CSV file
+#[derive(Serialize)]
+struct FindingAsCsv {
+    finding: &'static str,
+    first_record_id: Option<u64>,
+    last_record_id: Option<u64>,
+    first_timestamp: Option<DateTime<Utc>>,
+    last_timestamp: Option<DateTime<Utc>>,
+    count: Option<u64>,
+    details: String,
+}
+
+impl ScanReport {
+    pub fn new(
+        file: String,
+        ranges: &[Range],
+        records: &HashMap<EventId, SerializedEvtxRecord<Value>>,
+        chunks: Vec<ChunkSummary>,
+        anomalies: Vec<Anomaly>,
+        allowed_bias: Duration,
+    ) -> Self {
+        let mut ranges_by_id: Vec<_> = ranges.iter().collect();
+        ranges_by_id.sort_by_key(|r| r.begin().event_record_id());
+
+        let gaps = ranges_by_id
+            .windows(2)
+            .map(|pair| GapFinding {
+                last_record_id_before: pair[0].end().event_record_id(),
+                first_record_id_after: pair[1].begin().event_record_id(),
+                timestamp_before: *pair[0].end().timestamp(),
+                timestamp_after: *pair[1].begin().timestamp(),
+                missing: (pair[1].begin().event_record_id())
+                    .saturating_sub(pair[0].end().event_record_id() + 1),
+            })
+            .collect();
+
+        let mut time_skews = Vec::new();
+        for range in ranges_by_id.iter() {
+            let events: Vec<_> = range.events().collect();
+            for pair in events.windows(2) {
+                let (previous, current) = (pair[0], pair[1]);
+                if *current.timestamp() + allowed_bias < *previous.timestamp() {
+                    time_skews.push(TimeSkewFinding {
+                        previous_record_id: previous.event_record_id(),
+                        previous_timestamp: *previous.timestamp(),
+                        record_id: current.event_record_id(),
+                        timestamp: *current.timestamp(),
+                        skew_seconds: (*current.timestamp() - *previous.timestamp()).num_seconds(),
+                    });
+                }
+            }
+        }
+
+        let mut log_clearings: Vec<_> = records
+            .values()
+            .filter_map(LogClearedFinding::try_from_record)
+            .collect();
+        log_clearings.sort_by_key(|f| f.record_id);
+
+        Self {
+            file,
+            ranges: ranges_by_id
+                .iter()
+                .map(|range| RangeFinding {
+                    first_record_id: range.begin().event_record_id(),
+                    last_record_id: range.end().event_record_id(),
+                    first_timestamp: *range.begin().timestamp(),
+                    last_timestamp: *range.end().timestamp(),
+                    count: range.len(),
+                })
+                .collect(),
+            gaps,
+            time_skews,
+            log_clearings,
+            chunks,
+            anomalies,
+        }
+    }
+
+    pub fn write<W: Write>(&self, format: ReportFormat, mut writer: W) -> anyhow::Result<()> {
+        match format {
+            ReportFormat::Json => {
+                serde_json::to_writer_pretty(&mut writer, self)?;
+                writeln!(writer)?;
+            }
+            ReportFormat::Csv => {
+                let mut csv_writer = csv::Writer::from_writer(writer);
+                for finding in self.csv_lines() {
+                    csv_writer.serialize(finding)?;
+                }
+                csv_writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn csv_lines(&self) -> Vec<FindingAsCsv> {
+        let mut lines = Vec::new();
+        lines.extend(self.ranges.iter().map(|r| FindingAsCsv {
+            finding: "range",
+            first_record_id: Some(r.first_record_id),
+            last_record_id: Some(r.last_record_id),
+            first_timestamp: Some(r.first_timestamp),
+            last_timestamp: Some(r.last_timestamp),
+            count: Some(r.count as u64),
+            details: String::new(),
+        }));
+        lines.extend(self.gaps.iter().map(|g| FindingAsCsv {
+            finding: "gap",
+            first_record_id: Some(g.last_record_id_before),
+            last_record_id: Some(g.first_record_id_after),
+            first_timestamp: Some(g.timestamp_before),
+            last_timestamp: Some(g.timestamp_after),
+            count: Some(g.missing),
+            details: format!("{} records are missing", g.missing),
+        }));
+        lines.extend(self.time_skews.iter().map(|s| FindingAsCsv {
+            finding: "time_skew",
+            first_record_id: Some(s.previous_record_id),
+            last_record_id: Some(s.record_id),
+            first_timestamp: Some(s.previous_timestamp),
+            last_timestamp: Some(s.timestamp),
+            count: None,
+            details: format!("skew of {} seconds", s.skew_seconds),
+        }));
+        lines.extend(self.log_clearings.iter().map(|c| FindingAsCsv {
+            finding: "log_cleared",
+            first_record_id: Some(c.record_id),
+            last_record_id: Some(c.record_id),
+            first_timestamp: Some(c.timestamp),
+            last_timestamp: Some(c.timestamp),
+            count: None,
+            details: format!(
+                "event {} in {} by {}",
+                c.event_id,
+                c.channel.as_deref().unwrap_or("unknown channel"),
+                c.user.as_deref().unwrap_or("unknown user")
+            ),
+        }));
+        lines.extend(self.chunks.iter().map(|c| {
+            let validity = |valid| if valid { "valid" } else { "invalid" };
+            FindingAsCsv {
+                finding: "chunk",
+                first_record_id: Some(c.first_record_id),
+                last_record_id: Some(c.last_record_id),
+                first_timestamp: None,
+                last_timestamp: None,
+                count: None,
+                details: format!(
+                    "chunk {} at offset 0x{:x}: header checksum {}, data checksum {}",
+                    c.chunk_number,
+                    c.offset,
+                    validity(c.header_checksum_valid),
+                    validity(c.data_checksum_valid)
+                ),
+            }
+        }));
+        lines.extend(self.anomalies.iter().map(|a| FindingAsCsv {
+            finding: "anomaly",
+            first_record_id: a.record_id,
+            last_record_id: a.record_id,
+            first_timestamp: None,
+            last_timestamp: None,
+            count: None,
+            details: a.to_string(),
+        }));
+        lines
+    }
+}
+
+impl LogClearedFinding {
+    fn try_from_record(record: &SerializedEvtxRecord<Value>) -> Option<Self> {
+        let event = &record.data["Event"];
+        let system = &event["System"];
+        let event_id = match system["EventID"].get("#text") {
+            Some(event_id) => event_id,
+            None => &system["EventID"],
+        }
+        .as_u64()?;
+        let channel = system["Channel"].as_str();
+        let provider = system["Provider"]["#attributes"]["Name"].as_str();
+
+        // 1102 is logged in the Security log, 104 in the System log
+        match (event_id, channel, provider) {
+            (1102, Some("Security"), Some("Microsoft-Windows-Eventlog")) => (),
+            (104, Some("System"), Some("Microsoft-Windows-Eventlog")) => (),
+            _ => return None,
+        }
+
+        let data = &event["UserData"]["LogFileCleared"];
+        let user = match (
+            data["SubjectDomainName"].as_str(),
+            data["SubjectUserName"].as_str(),
+        ) {
+            (Some(domain), Some(user)) => Some(format!("{domain}\\{user}")),
+            (None, Some(user)) => Some(user.to_owned()),
+            _ => None,
+        };
+
+        // 104 events contain the name of the log which has been cleared
+        let cleared_channel = data["Channel"].as_str().or(channel);
+
+        Some(Self {
+            record_id: record.event_record_id,
+            timestamp: record.timestamp,
+            event_id,
+            channel: cleared_channel.map(ToOwned::to_owned),
+            user,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{Duration, TimeZone, Utc};
+    use dfir_toolkit::evtx::{EventId, Range};
+    use evtx::SerializedEvtxRecord;
+    use serde_json::{json, Value};
+
+    use super::{ReportFormat, ScanReport};
+
+    fn record(event_record_id: u64, seconds: i64, data: Value) -> SerializedEvtxRecord<Value> {
+        SerializedEvtxRecord {
+            event_record_id,
+            timestamp: Utc.with_ymd_and_hms(2023, 11, 14, 10, 0, 0).unwrap()
+                + Duration::seconds(seconds),
+            data,
+        }
+    }
+
+    fn log_cleared() -> Value {
+        json!({
+            "Event": {
+                "System": {
+                    "Provider": {"#attributes": {"Name": "Microsoft-Windows-Eventlog"}},
+                    "EventID": 1102,
+                    "Channel": "Security",
+                },
+                "UserData": {
+                    "LogFileCleared": {
+                        "SubjectDomainName": "CONTOSO",
+                        "SubjectUserName": "mallory",
+                    }
+                }
+            }
+        })
+    }
+
+    /// creates a report of the records 1 to 3 and 10 to 11. The record 3 is
+    /// 60 seconds older than record 2, and record 10 reports a log clearing
+    fn report() -> ScanReport {
+        let records = vec![
+            record(1, 0, Value::Null),
+            record(2, 100, Value::Null),
+            record(3, 40, Value::Null),
+            record(10, 200, log_cleared()),
+            record(11, 210, Value::Null),
+        ];
+
+        let mut ranges: Vec<Range> = Vec::new();
+        for record in records.iter() {
+            let id = EventId::from(record);
+            match ranges.last_mut() {
+                Some(range) if range.can_contain(&id) => range.add_event(id),
+                _ => ranges.push(Range::from(id)),
+            }
+        }
+        let records: HashMap<_, _> = records
+            .into_iter()
+            .map(|r| (EventId::from(&r), r))
+            .collect();
+
+        ScanReport::new(
+            "Security.evtx".into(),
+            &ranges,
+            &records,
+            Vec::new(),
+            Vec::new(),
+            Duration::seconds(5),
+        )
+    }
+
+    #[test]
+    fn findings() {
+        let report = report();
+        assert_eq!(report.ranges.len(), 2);
+        assert_eq!(report.ranges[0].count, 3);
+        assert_eq!(report.ranges[1].first_record_id, 10);
+
+        assert_eq!(report.gaps.len(), 1);
+        assert_eq!(report.gaps[0].last_record_id_before, 3);
+        assert_eq!(report.gaps[0].first_record_id_after, 10);
+        assert_eq!(report.gaps[0].missing, 6);
+
+        assert_eq!(report.time_skews.len(), 1);
+        assert_eq!(report.time_skews[0].record_id, 3);
+        assert_eq!(report.time_skews[0].skew_seconds, -60);
+
+        assert_eq!(report.log_clearings.len(), 1);
+        assert_eq!(report.log_clearings[0].record_id, 10);
+        assert_eq!(report.log_clearings[0].channel.as_deref(), Some("Security"));
+        assert_eq!(
+            report.log_clearings[0].user.as_deref(),
+            Some("CONTOSO\\mallory")
+        );
+    }
+
+    #[test]
+    fn write_csv() {
+        let mut output = Vec::new();
+        report().write(ReportFormat::Csv, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let findings: Vec<_> = output
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap())
+            .collect();
+        assert_eq!(
+            findings,
+            vec!["range", "range", "gap", "time_skew", "log_cleared"]
+        );
+        assert!(output.contains("6 records are missing"));
+        assert!(output.contains("event 1102 in Security by CONTOSO\\mallory"));
+    }
+
+    #[test]
+    fn write_json() {
+        let mut output = Vec::new();
+        report().write(ReportFormat::Json, &mut output).unwrap();
+        let report: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(report["file"], "Security.evtx");
+        assert_eq!(report["gaps"][0]["missing"], 6);
+        assert_eq!(report["log_clearings"][0]["event_id"], 1102);
+    }
+}
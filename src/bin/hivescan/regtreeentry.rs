@@ -29,7 +29,7 @@ impl RegTreeEntry {
         self.is_deleted
     }
 
-    pub fn children(&self) -> Children {
+    pub fn children(&self) -> Children<'_> {
         Children{
             children: self.children.iter()
         }
@@ -24,7 +24,6 @@ comply with the POSIX standard.</red>"##
 /// Replacement for `mactime`
 #[derive(Parser)]
 #[clap(name="mactime2", author, version, long_about = None, after_help=AFTER_HELP)]
-
 pub struct Cli {
     #[clap(short('b'), value_parser, value_hint=ValueHint::FilePath, default_value="-", help=BODYFILE_HELP, display_order(100))]
     pub(crate) input_file: Input,
@@ -310,7 +310,6 @@ impl TryFrom<&str> for Bodyfile3Line {
     /// assert_eq!(*bf_line.get_ctime(), Changed::from(8));
     /// assert_eq!(*bf_line.get_crtime(), Created::from(9)); 
     /// ```
-
     fn try_from(line: &str) -> Result<Self, Self::Error> {
         let parts: Vec<&str> = line.split('|').collect();
         if parts.len() < 11 {
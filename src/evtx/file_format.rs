@@ -0,0 +1,68 @@
+//! sizes, offsets and signatures of the structures of an evtx file
+
+use std::io::{self, Read};
+
+pub const EVTX_FILE_HEADER_SIZE: usize = 4096;
+pub const EVTX_FILE_MAGIC: &[u8] = b"ElfFile\0";
+pub const EVTX_CHUNK_SIZE: usize = 65536;
+pub const EVTX_CHUNK_HEADER_SIZE: usize = 512;
+pub const EVTX_CHUNK_MAGIC: &[u8] = b"ElfChnk\0";
+pub const EVTX_RECORD_MAGIC: &[u8] = b"**\0\0";
+pub const EVTX_RECORD_HEADER_SIZE: usize = 24;
+
+/// the chunk has no checksum of its records
+pub const CHUNK_FLAG_NO_CRC32: u32 = 0x4;
+
+/// the file has not been closed properly, so that its header might be outdated
+pub const FILE_FLAG_DIRTY: u32 = 0x1;
+
+/// the fields of the file header which describe the state of an evtx file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileHeader {
+    pub first_chunk_number: u64,
+    pub last_chunk_number: u64,
+    pub next_record_id: u64,
+    pub flags: u32,
+}
+
+impl FileHeader {
+    /// reads the file header from the beginning of `reader`
+    pub fn from_reader<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut header = [0; 128];
+        reader.read_exact(&mut header)?;
+        if !header.starts_with(EVTX_FILE_MAGIC) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the file has no valid evtx file header",
+            ));
+        }
+        let read_u64 = |offset: usize| u64::from_le_bytes(header[offset..offset + 8].try_into().unwrap());
+        Ok(Self {
+            first_chunk_number: read_u64(8),
+            last_chunk_number: read_u64(16),
+            next_record_id: read_u64(24),
+            flags: u32::from_le_bytes(header[120..124].try_into().unwrap()),
+        })
+    }
+
+    /// the header is not updated while the file is opened by the eventlog
+    /// service, so it might be outdated
+    pub fn is_dirty(&self) -> bool {
+        self.flags & FILE_FLAG_DIRTY != 0
+    }
+}
+
+/// returns the offset of the chunk with the number `chunk_number`
+pub fn chunk_offset(chunk_number: u64) -> u64 {
+    EVTX_FILE_HEADER_SIZE as u64 + chunk_number * EVTX_CHUNK_SIZE as u64
+}
+
+/// calculates the checksum of a chunk header, which covers the first 120
+/// bytes and the bytes 128 to 512 of the chunk
+#[cfg(feature = "crc32fast")]
+pub fn chunk_header_checksum(chunk: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&chunk[..120]);
+    hasher.update(&chunk[128..EVTX_CHUNK_HEADER_SIZE]);
+    hasher.finalize()
+}
@@ -1,5 +1,7 @@
 mod event_id;
+mod file_format;
 mod range;
 
 pub use event_id::*;
-pub use range::*;
\ No newline at end of file
+pub use file_format::*;
+pub use range::*;
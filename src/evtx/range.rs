@@ -15,7 +15,7 @@ impl Ord for Range {
 
 impl PartialOrd for Range {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.begin().timestamp().cmp(other.begin().timestamp()))
+        Some(self.cmp(other))
     }
 }
 